@@ -0,0 +1,91 @@
+use crate::{
+	pos::Pos,
+	symmetry::Symmetry,
+};
+use std::fmt;
+
+/// A mark drawn on a point, as in the SGF `TR`, `CR`, `SQ`, `MA` and `LB`
+/// properties
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mark {
+	Triangle,
+	Circle,
+	Square,
+	Cross,
+	Label(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Markup {
+	pub pos: Pos,
+	pub mark: Mark,
+}
+
+/// What is thought of the move played, as in the SGF `TE`, `BM`, `DO` and
+/// `IT` properties
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveQuality {
+	Good,
+	Bad,
+	Doubtful,
+	Interesting,
+}
+
+impl fmt::Display for MoveQuality {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			MoveQuality::Good => write!(f, "Good move"),
+			MoveQuality::Bad => write!(f, "Bad move"),
+			MoveQuality::Doubtful => write!(f, "Doubtful move"),
+			MoveQuality::Interesting => write!(f, "Interesting move"),
+		}
+	}
+}
+
+/// Notes on a position of a game, for teaching: a comment, marks on the
+/// board, and what is thought of the move that led to it
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Annotations {
+	pub comment: Option<String>,
+	pub markup: Vec<Markup>,
+	pub quality: Option<MoveQuality>,
+}
+
+impl Annotations {
+	pub fn is_empty(&self) -> bool {
+		self.comment.is_none() && self.markup.is_empty() && self.quality.is_none()
+	}
+
+	/// the mark on `pos`, if there is one
+	pub fn mark(&self, pos: Pos) -> Option<&Mark> {
+		self
+			.markup
+			.iter()
+			.find(|markup| markup.pos == pos)
+			.map(|markup| &markup.mark)
+	}
+
+	/// Put `mark` on `pos`, replacing any mark there. `None` clears it.
+	pub fn set_mark(&mut self, pos: Pos, mark: Option<Mark>) {
+		self.markup.retain(|markup| markup.pos != pos);
+		if let Some(mark) = mark {
+			self.markup.push(Markup { pos, mark });
+		}
+	}
+
+	/// the same notes with the marks moved by `symmetry`
+	pub fn transform(&self, symmetry: Symmetry, size: u8) -> Self {
+		Self {
+			comment: self.comment.clone(),
+			markup: self
+				.markup
+				.iter()
+				.map(|markup| Markup {
+					pos: symmetry.apply(markup.pos, size),
+					mark: markup.mark.clone(),
+				})
+				.collect(),
+			quality: self.quality,
+		}
+	}
+}
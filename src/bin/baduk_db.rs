@@ -0,0 +1,137 @@
+use bevy_baduk::{
+	db::*,
+	error::*,
+	joseki::Corner,
+	sgf,
+};
+use std::{
+	env,
+	fs,
+	process,
+};
+
+const USAGE: &str = "Usage:
+  baduk_db index <sgf directory> <database>
+  baduk_db position <database> <sgf file> [move number]
+  baduk_db corner <database> <sgf file> <bl|br|tl|tr> [move number]";
+
+fn main() {
+	let args: Vec<String> = env::args().skip(1).collect();
+	let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+	let res = match args.as_slice() {
+		["index", dir, db] => index(dir, db),
+		["position", db, file] => search_position(db, file, None),
+		["position", db, file, n] => search_position(db, file, Some(n)),
+		["corner", db, file, corner] => search_corner(db, file, corner, None),
+		["corner", db, file, corner, n] => search_corner(db, file, corner, Some(n)),
+		_ => {
+			eprintln!("{}", USAGE);
+			process::exit(2);
+		}
+	};
+	if let Err(err) = res {
+		eprintln!("{}", err);
+		process::exit(1);
+	}
+}
+
+fn index(dir: &str, db_path: &str) -> Result<()> {
+	let mut db = Database::new();
+	let report = db.index_dir(dir)?;
+	for (path, err) in report.errors.iter() {
+		eprintln!("skipped {}: {}", path.display(), err);
+	}
+	db.save(db_path)?;
+	println!(
+		"Indexed {} games from {} files, {} distinct positions",
+		report.games,
+		report.files,
+		db.num_positions()
+	);
+	Ok(())
+}
+
+/// the first game of an SGF file, and the board after `move_number` moves
+/// (the end of the game by default)
+fn query_board(
+	file: &str,
+	move_number: Option<&str>,
+) -> Result<bevy_baduk::board::Board> {
+	let record = sgf::parse(&fs::read_to_string(file)?)?
+		.first()
+		.map(|root| GameRecord::from_sgf(file, root))
+		.unwrap()?;
+	let i = match move_number {
+		Some(n) => n.parse().map_err(|_| BadukError::MoveIndexOutOfBounds {
+			history_size: record.moves.len(),
+		})?,
+		None => record.moves.len(),
+	};
+	record.board_at_move(i)
+}
+
+fn search_position(
+	db_path: &str,
+	file: &str,
+	move_number: Option<&str>,
+) -> Result<()> {
+	let db = Database::load(db_path)?;
+	let board = query_board(file, move_number)?;
+	println!("{}", board);
+	print_result(&db, &db.search_position(&board), board.size());
+	Ok(())
+}
+
+fn search_corner(
+	db_path: &str,
+	file: &str,
+	corner: &str,
+	move_number: Option<&str>,
+) -> Result<()> {
+	let corner = match corner {
+		"bl" => Corner::BottomLeft,
+		"br" => Corner::BottomRight,
+		"tl" => Corner::TopLeft,
+		"tr" => Corner::TopRight,
+		_ => {
+			eprintln!("{}", USAGE);
+			process::exit(2);
+		}
+	};
+	let db = Database::load(db_path)?;
+	let board = query_board(file, move_number)?;
+	println!("{}", board);
+	print_result(&db, &db.search_corner(&board, corner), board.size());
+	Ok(())
+}
+
+fn print_result(db: &Database, result: &SearchResult, size: u8) {
+	println!("Found in {} games", result.occurrences.len());
+	for occurrence in result.occurrences.iter().take(20) {
+		let game = &db.games()[occurrence.game as usize];
+		println!(
+			"  {} - {} vs {} {} (move {})",
+			game.path, game.black, game.white, game.result, occurrence.move_number
+		);
+	}
+	if result.occurrences.len() > 20 {
+		println!("  ...");
+	}
+	if result.continuations.is_empty() {
+		return;
+	}
+	println!("Next moves:");
+	for c in result.continuations.iter() {
+		let win_rate = match c.win_rate() {
+			Some(rate) => format!("{:.0}% wins", rate * 100.0),
+			None => "no results".to_string(),
+		};
+		println!(
+			"  {} {}  {} games  {}",
+			c.next.piece.color,
+			sgf::format_point(c.next.pos, size),
+			c.count,
+			win_rate
+		);
+	}
+}
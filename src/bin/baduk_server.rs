@@ -0,0 +1,37 @@
+use bevy_baduk::{
+	net::DEFAULT_PORT,
+	server::Server,
+};
+use std::{
+	env,
+	path::PathBuf,
+	process,
+	sync::atomic::AtomicBool,
+};
+
+const USAGE: &str = "Usage:
+  baduk_server [address] [directory for finished games]";
+
+fn main() {
+	let args: Vec<String> = env::args().skip(1).collect();
+	let default_addr = format!("0.0.0.0:{}", DEFAULT_PORT);
+	let (addr, save_dir) = match args.as_slice() {
+		[] => (default_addr.as_str(), "games"),
+		[addr] => (addr.as_str(), "games"),
+		[addr, dir] => (addr.as_str(), dir.as_str()),
+		_ => {
+			eprintln!("{}", USAGE);
+			process::exit(2);
+		}
+	};
+	let res =
+		Server::bind(addr, Some(PathBuf::from(save_dir))).and_then(|mut server| {
+			println!("Listening on {}", server.local_addr()?);
+			server.run(&AtomicBool::new(false), |line| println!("{}", line));
+			Ok(())
+		});
+	if let Err(err) = res {
+		eprintln!("{}", err);
+		process::exit(1);
+	}
+}
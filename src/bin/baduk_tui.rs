@@ -0,0 +1,163 @@
+use bevy_baduk::{
+	error::*,
+	game::Game,
+	piece::PieceColor,
+	sgf,
+	tui::Tui,
+};
+use crossterm::{
+	cursor,
+	event::{
+		self,
+		Event,
+		KeyCode,
+		KeyEvent,
+		KeyModifiers,
+	},
+	queue,
+	terminal::{
+		self,
+		ClearType,
+	},
+};
+use std::{
+	env,
+	io::{
+		self,
+		Write,
+	},
+	path::PathBuf,
+	process,
+};
+
+const USAGE: &str = "Usage:
+  baduk_tui [--size <n>] [--handicap <n>] [--bot <black|white>] [--sgf <file>]
+
+The game in the SGF file is played on if there is one, and saved to it.";
+
+const DEFAULT_PATH: &str = "game.sgf";
+
+fn main() {
+	let args: Vec<String> = env::args().skip(1).collect();
+	let mut tui = match tui_from_args(&args) {
+		Ok(Some(tui)) => tui,
+		Ok(None) => {
+			eprintln!("{}", USAGE);
+			process::exit(2);
+		}
+		Err(err) => {
+			eprintln!("{}", err);
+			process::exit(1);
+		}
+	};
+	let res = terminal::enable_raw_mode().and_then(|_| {
+		let res = run(&mut tui);
+		terminal::disable_raw_mode().and(res)
+	});
+	// leave the last board on the screen
+	println!();
+	if let Err(err) = res {
+		eprintln!("{}", err);
+		process::exit(1);
+	}
+}
+
+/// `None` when the arguments make no sense
+fn tui_from_args(args: &[String]) -> Result<Option<Tui>> {
+	let mut size = 19;
+	let mut handicap = 0;
+	let mut bot = None;
+	let mut path = PathBuf::from(DEFAULT_PATH);
+	for pair in args.chunks(2) {
+		let (flag, value) = match pair {
+			[flag, value] => (flag.as_str(), value.as_str()),
+			_ => return Ok(None),
+		};
+		match flag {
+			"--size" => match value.parse() {
+				Ok(n) => size = n,
+				Err(_) => return Ok(None),
+			},
+			"--handicap" => match value.parse() {
+				Ok(n) => handicap = n,
+				Err(_) => return Ok(None),
+			},
+			"--bot" => match value {
+				"black" => bot = Some(PieceColor::Black),
+				"white" => bot = Some(PieceColor::White),
+				_ => return Ok(None),
+			},
+			"--sgf" => path = PathBuf::from(value),
+			_ => return Ok(None),
+		}
+	}
+	let game = if path.exists() {
+		sgf::load_game(&path)?
+	} else {
+		Game::with_size(size, handicap)?
+	};
+	Ok(Some(Tui::new(game, bot, path)))
+}
+
+/// Draw the game and read keys until the player quits. The arrow keys move
+/// the cursor, `PageUp` / `PageDown` / `Home` / `End` step through the game,
+/// anything else is typed at the prompt.
+fn run(tui: &mut Tui) -> crossterm::Result<()> {
+	let mut stdout = io::stdout();
+	let mut line = String::new();
+	tui.message = Some("Type `help` for the commands".to_string());
+	while !tui.quit {
+		draw(&mut stdout, tui, &line)?;
+		// drawn before the bot thinks, so the player sees their move
+		if tui.bot_move() {
+			continue;
+		}
+		let (code, modifiers) = match event::read()? {
+			Event::Key(KeyEvent { code, modifiers }) => (code, modifiers),
+			_ => continue,
+		};
+		match code {
+			KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+				tui.quit = true;
+			}
+			KeyCode::Up => tui.move_cursor(0, 1),
+			KeyCode::Down => tui.move_cursor(0, -1),
+			KeyCode::Left => tui.move_cursor(-1, 0),
+			KeyCode::Right => tui.move_cursor(1, 0),
+			KeyCode::PageUp => tui.enter("back"),
+			KeyCode::PageDown => tui.enter("forward"),
+			KeyCode::Home => tui.enter("start"),
+			KeyCode::End => tui.enter("end"),
+			KeyCode::Esc => line.clear(),
+			KeyCode::Backspace => {
+				line.pop();
+			}
+			KeyCode::Enter => tui.enter(&std::mem::take(&mut line)),
+			KeyCode::Char(c) => line.push(c),
+			_ => {}
+		}
+	}
+	Ok(())
+}
+
+fn draw(
+	stdout: &mut io::Stdout,
+	tui: &Tui,
+	line: &str,
+) -> crossterm::Result<()> {
+	let text = tui.render();
+	queue!(
+		stdout,
+		terminal::Clear(ClearType::All),
+		cursor::MoveTo(0, 0)
+	)?;
+	// raw mode doesn't go back to the start of the line on a line feed
+	write!(stdout, "{}\r\n> {}", text.replace('\n', "\r\n"), line)?;
+	// the cursor is on the board until something is typed
+	if line.is_empty() {
+		let (column, row) = tui.cursor_cell();
+		queue!(stdout, cursor::MoveTo(column, row))?;
+	}
+	stdout.flush()?;
+	Ok(())
+}
@@ -1,8 +1,11 @@
 use crate::{
 	adjacency::*,
 	error::*,
+	notation,
 	piece::*,
 	pos::Pos,
+	symmetry::Symmetry,
+	zobrist,
 };
 use colored::Colorize;
 use std::{
@@ -10,17 +13,8 @@ use std::{
 	fmt,
 };
 
-const DOT_POSITIONS: [(u8, u8); 9] = [
-	(3, 3),
-	(3, 9),
-	(3, 15),
-	(9, 3),
-	(9, 9),
-	(9, 15),
-	(15, 3),
-	(15, 9),
-	(15, 15),
-];
+/// largest supported board size, which is also the size of the backing arrays
+pub const MAX_SIZE: u8 = 19;
 
 const BLACK_CHAR: &'static str = "○";
 const WHITE_CHAR: &'static str = "●";
@@ -38,65 +32,174 @@ const GRID_BOT: &'static str = "┴";
 const GRID_LEFT: &'static str = "├";
 const GRID_RIGHT: &'static str = "┤";
 
-pub fn pos_in_bounds(pos: (u8, u8)) -> bool {
-	pos.0 <= 18 && pos.1 <= 18
+/// a point of a board diagram, as written by `to_ascii` or `Display`
+fn diagram_point(c: char) -> Option<Option<PieceColor>> {
+	Some(match c {
+		'X' | 'x' | '○' => Some(Black),
+		'O' | 'o' | '●' => Some(White),
+		'.' | '+' => None,
+		// the grid of `Display`
+		'┼' | '┌' | '┐' | '└' | '┘' | '┬' | '┴' | '├' | '┤' => {
+			None
+		}
+		_ => return None,
+	})
+}
+
+/// the text without the escape codes that color it in a terminal
+fn strip_colors(s: &str) -> String {
+	let mut text = String::new();
+	let mut chars = s.chars();
+	while let Some(c) = chars.next() {
+		if c == '\u{1b}' {
+			// up to the letter that ends the code
+			chars.find(|c| c.is_ascii_alphabetic());
+		} else {
+			text.push(c);
+		}
+	}
+	text
+}
+
+pub fn pos_in_bounds(pos: Pos) -> bool {
+	pos.x < MAX_SIZE && pos.y < MAX_SIZE
+}
+
+/// star point ("hoshi") positions for a board size
+pub fn star_points(size: u8) -> Vec<Pos> {
+	let edge = match size {
+		13..=MAX_SIZE => 3,
+		9..=12 => 2,
+		_ => return vec![],
+	};
+	let far = size - 1 - edge;
+	let mut points = vec![(edge, edge), (edge, far), (far, edge), (far, far)];
+	if size % 2 == 1 {
+		let mid = size / 2;
+		points.push((mid, mid));
+		if size >= 15 {
+			points.extend(vec![(edge, mid), (far, mid), (mid, edge), (mid, far)]);
+		}
+	}
+	points.into_iter().map(|(x, y)| Pos { x, y }).collect()
 }
 
-pub type MoveResult = (PlacedPiece, HashSet<(u8, u8)>);
+pub type MoveResult = (PlacedPiece, HashSet<Pos>);
 
 #[derive(Clone)]
 pub struct Board {
+	size: u8,
 	display_board: [[Option<Piece>; 19]; 19],
 	// for checking Ko
 	ko_board: [[Option<Piece>; 19]; 19],
+	// zobrist hash of display_board, kept up to date by set / remove
+	hash: u64,
+}
+
+impl Default for Board {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl Board {
 	pub fn new() -> Self {
 		Self {
+			size: MAX_SIZE,
 			display_board: [[None; 19]; 19],
 			ko_board: [[None; 19]; 19],
+			hash: zobrist::size_key(MAX_SIZE),
 		}
 	}
 
-	pub fn get(&self, pos: (u8, u8)) -> Option<PlacedPieceRef> {
-		match self.display_board[pos.0 as usize][pos.1 as usize].as_ref() {
-			Some(piece) => Some(PlacedPieceRef { piece, pos }),
-			None => None,
+	/// create an empty board of `size` x `size` intersections
+	pub fn with_size(size: u8) -> Result<Self> {
+		if size == 0 || size > MAX_SIZE {
+			return Err(BadukError::InvalidBoardSize { size });
 		}
+		Ok(Self {
+			size,
+			hash: zobrist::size_key(size),
+			..Self::new()
+		})
 	}
 
-	pub fn get_color(&self, pos: (u8, u8)) -> Option<PieceColor> {
-		self.get(pos).map(|p| p.piece.color)
+	pub fn size(&self) -> u8 {
+		self.size
 	}
 
-	pub fn adjacents(&self, pos: (u8, u8)) -> Adjacency<PieceAdjacency> {
-		fn offset(s: &Board, pos: (u8, u8), offset: (i8, i8)) -> PieceAdjacency {
-			let newpos = (pos.0 as i8 + offset.0, pos.1 as i8 + offset.1);
-			if newpos.0 < 0 || newpos.0 > 18 || newpos.1 < 0 || newpos.1 > 18 {
-				return PieceAdjacency::Edge;
-			};
-			let newpos = (newpos.0 as u8, newpos.1 as u8);
+	pub fn in_bounds(&self, pos: Pos) -> bool {
+		pos.x < self.size && pos.y < self.size
+	}
 
-			match s.display_board[newpos.0 as usize][newpos.1 as usize].as_ref() {
-				Some(piece) => PieceAdjacency::Piece(PlacedPieceRef {
-					piece,
-					pos: (newpos.0 as u8, newpos.1 as u8),
-				}),
-				None => PieceAdjacency::Empty(newpos),
-			}
+	fn check_bounds(&self, pos: Pos) -> Result<()> {
+		if self.in_bounds(pos) {
+			Ok(())
+		} else {
+			Err(BadukError::PosOutOfBounds { pos })
 		}
+	}
+
+	/// the piece at a point known to be on the board
+	fn at(&self, pos: Pos) -> Option<PlacedPieceRef> {
+		self.display_board[pos.x as usize][pos.y as usize]
+			.as_ref()
+			.map(|piece| PlacedPieceRef { piece, pos })
+	}
+
+	/// zobrist hash of the stones on the board
+	pub fn hash(&self) -> u64 {
+		self.hash
+	}
+
+	pub fn get(&self, pos: Pos) -> Result<Option<PlacedPieceRef>> {
+		self.check_bounds(pos)?;
+		Ok(self.at(pos))
+	}
+
+	pub fn get_color(&self, pos: Pos) -> Result<Option<PieceColor>> {
+		Ok(self.get(pos)?.map(|p| p.piece.color))
+	}
+
+	fn color_at(&self, pos: Pos) -> Option<PieceColor> {
+		self.at(pos).map(|p| p.piece.color)
+	}
+
+	/// what is found at `offset` from `pos`. Anything outside of the board is
+	/// `PieceAdjacency::Edge`, however far away it is.
+	pub fn offset(&self, pos: Pos, offset: (i8, i8)) -> PieceAdjacency {
+		let newpos = (
+			pos.x as i16 + offset.0 as i16,
+			pos.y as i16 + offset.1 as i16,
+		);
+		let max = self.size as i16 - 1;
+		if newpos.0 < 0 || newpos.0 > max || newpos.1 < 0 || newpos.1 > max {
+			return PieceAdjacency::Edge;
+		};
+		let newpos = Pos {
+			x: newpos.0 as u8,
+			y: newpos.1 as u8,
+		};
+
+		match self.at(newpos) {
+			Some(piece) => PieceAdjacency::Piece(piece),
+			None => PieceAdjacency::Empty(newpos),
+		}
+	}
+
+	pub fn adjacents(&self, pos: Pos) -> Adjacency<PieceAdjacency> {
 		Adjacency::new(
-			offset(self, pos, (0, 1)),
-			offset(self, pos, (0, -1)),
-			offset(self, pos, (-1, 0)),
-			offset(self, pos, (1, 0)),
+			self.offset(pos, (0, 1)),
+			self.offset(pos, (0, -1)),
+			self.offset(pos, (-1, 0)),
+			self.offset(pos, (1, 0)),
 		)
 	}
 
-	pub fn shape<'a>(&'a self, pos: (u8, u8)) -> HashSet<PlacedPieceRef> {
+	/// the stones connected to the one at `pos`, empty if there is none
+	pub fn shape<'a>(&'a self, pos: Pos) -> HashSet<PlacedPieceRef> {
 		let mut s: HashSet<PlacedPieceRef> = HashSet::new();
-		let root = self.get(pos);
+		let root = self.get(pos).ok().flatten();
 		if root.is_none() {
 			return s;
 		}
@@ -125,8 +228,8 @@ impl Board {
 		s
 	}
 
-	pub fn liberties(&self, pos: (u8, u8)) -> HashSet<(u8, u8)> {
-		let mut libs: HashSet<(u8, u8)> = HashSet::new();
+	pub fn liberties(&self, pos: Pos) -> HashSet<Pos> {
+		let mut libs: HashSet<Pos> = HashSet::new();
 		for adj_pos in self.adjacents(pos).iter() {
 			if let PieceAdjacency::Empty(pos) = adj_pos {
 				libs.insert(*pos);
@@ -136,10 +239,10 @@ impl Board {
 	}
 
 	/// Get liberties for shape
-	pub fn liberties_shape(&self, pos: (u8, u8)) -> HashSet<(u8, u8)> {
-		let mut libs: HashSet<(u8, u8)> = HashSet::new();
+	pub fn liberties_shape(&self, pos: Pos) -> HashSet<Pos> {
+		let mut libs: HashSet<Pos> = HashSet::new();
 		let shape = self.shape(pos);
-		if self.get_color(pos).is_none() {
+		if shape.is_empty() {
 			return libs;
 		}
 
@@ -150,38 +253,58 @@ impl Board {
 		libs
 	}
 
-	pub fn set(&mut self, m: PlacedPiece) -> PlacedPieceRef {
-		self.display_board[m.pos.0 as usize][m.pos.1 as usize] = Some(m.piece);
-		self.get(m.pos).unwrap()
+	/// place a stone, replacing whatever is there, without playing a move
+	pub fn set(&mut self, m: PlacedPiece) -> Result<PlacedPieceRef> {
+		self.check_bounds(m.pos)?;
+		self.put(m);
+		Ok(self.at(m.pos).unwrap())
+	}
+
+	fn put(&mut self, m: PlacedPiece) {
+		self.take(m.pos);
+		self.display_board[m.pos.x as usize][m.pos.y as usize] = Some(m.piece);
+		self.hash ^= zobrist::piece_key(m.pos, m.piece.color);
+	}
+
+	pub fn remove(&mut self, pos: Pos) -> Result<()> {
+		self.check_bounds(pos)?;
+		self.take(pos);
+		Ok(())
 	}
 
-	pub fn remove(&mut self, pos: (u8, u8)) {
-		self.display_board[pos.0 as usize][pos.1 as usize] = None;
+	fn take(&mut self, pos: Pos) {
+		if let Some(color) = self.color_at(pos) {
+			self.hash ^= zobrist::piece_key(pos, color);
+		}
+		self.display_board[pos.x as usize][pos.y as usize] = None;
 	}
 
-	pub fn remove_shape(&mut self, pos: (u8, u8)) -> HashSet<(u8, u8)> {
+	pub fn remove_shape(&mut self, pos: Pos) -> HashSet<Pos> {
 		let shape = self.shape(pos);
-		let positions: HashSet<(u8, u8)> =
+		let positions: HashSet<Pos> =
 			{ shape.into_iter().map(|p| p.pos).collect() };
 		for p in positions.clone() {
-			self.remove(p);
+			self.take(p);
 		}
 		positions
 	}
 
 	pub fn valid_move(&self, m: PlacedPiece) -> Result<()> {
-		if self.get_color(m.pos).is_some() {
+		if m.is_pass() {
+			return Ok(());
+		}
+		if self.get_color(m.pos)?.is_some() {
 			return Err(BadukError::InvalidMove {
 				source: InvalidMoveError::AlreadyOccupied,
 			});
 		}
 
 		let mut future_board = self.clone();
-		future_board.set(m);
+		future_board.put(m);
 
 		// allow for self-captures if they would immediately capture something.
 
-		let adj: Vec<(u8, u8)> = future_board
+		let adj: Vec<Pos> = future_board
 			.adjacents(m.pos)
 			.iter()
 			.filter(|a| {
@@ -224,15 +347,20 @@ impl Board {
 	}
 
 	pub fn do_move(&mut self, m: PlacedPiece) -> Result<MoveResult> {
+		// a pass changes nothing but the ko
+		if m.is_pass() {
+			self.ko_board = self.display_board;
+			return Ok((m, HashSet::new()));
+		}
 		self.valid_move(m)?;
 		self.ko_board = self.display_board;
-		self.set(m);
+		self.put(m);
 
 		let adjacents = self
 			.adjacents(m.pos)
 			.map(|m| m.piece().map(|p| PlacedPiece::from(p)));
 
-		let mut removed_positions: HashSet<(u8, u8)> = HashSet::new();
+		let mut removed_positions: HashSet<Pos> = HashSet::new();
 
 		for adj in adjacents.iter() {
 			if let Some(pr) = adj {
@@ -244,7 +372,7 @@ impl Board {
 			}
 		}
 
-		Ok((self.get(m.pos).unwrap().into(), removed_positions))
+		Ok((self.at(m.pos).unwrap().into(), removed_positions))
 	}
 
 	pub fn do_moves(&mut self, moves: Vec<PlacedPiece>) -> Result<()> {
@@ -262,7 +390,7 @@ impl Board {
 		for (color, x, y) in moves {
 			self.do_move(PlacedPiece {
 				piece: Piece { color },
-				pos: (x, y),
+				pos: Pos { x, y },
 			})?;
 		}
 		Ok(())
@@ -270,9 +398,9 @@ impl Board {
 
 	pub fn num_pieces(&self, color: PieceColor) -> u16 {
 		let mut count: u16 = 0;
-		for i in 0..18u8 {
-			for j in 0..18u8 {
-				if let Some(c) = self.get_color((j, i)) {
+		for i in 0..self.size {
+			for j in 0..self.size {
+				if let Some(c) = self.color_at(Pos { x: j, y: i }) {
 					if c == color {
 						count += 1;
 					}
@@ -283,9 +411,9 @@ impl Board {
 	}
 	pub fn num_pieces_all(&self) -> u16 {
 		let mut count: u16 = 0;
-		for i in 0..18u8 {
-			for j in 0..18u8 {
-				if self.get_color((j, i)).is_some() {
+		for i in 0..self.size {
+			for j in 0..self.size {
+				if self.color_at(Pos { x: j, y: i }).is_some() {
 					count += 1;
 				}
 			}
@@ -293,6 +421,129 @@ impl Board {
 		count
 	}
 
+	/// apply a rotation or reflection to the board, including its ko state
+	pub fn transform(&self, symmetry: Symmetry) -> Board {
+		let size = self.size;
+		self.map_pieces(|pos, piece| (symmetry.apply(pos, size), piece))
+	}
+
+	/// swap the color of every stone, including its ko state
+	pub fn invert_colors(&self) -> Board {
+		self.map_pieces(|pos, piece| {
+			(
+				pos,
+				Piece {
+					color: piece.color.opposite(),
+				},
+			)
+		})
+	}
+
+	fn map_pieces<F: Fn(Pos, Piece) -> (Pos, Piece)>(&self, func: F) -> Board {
+		let mut board = Board::with_size(self.size).unwrap();
+		for i in 0..self.size {
+			for j in 0..self.size {
+				if let Some(piece) = self.display_board[i as usize][j as usize] {
+					let (pos, piece) = func(Pos { x: i, y: j }, piece);
+					board.put(PlacedPiece { piece, pos });
+				}
+				if let Some(piece) = self.ko_board[i as usize][j as usize] {
+					let (pos, piece) = func(Pos { x: i, y: j }, piece);
+					board.ko_board[pos.x as usize][pos.y as usize] = Some(piece);
+				}
+			}
+		}
+		board
+	}
+
+	/// Read a board from a diagram, one row per line from the top, e.g.
+	/// `X` / `O` / `.` points as written by `to_ascii`. Coordinates around
+	/// it, spaces and the grid lines of `Display` are skipped, so its output
+	/// reads back too. Stones are put down as they are, with no captures.
+	pub fn from_ascii(s: &str) -> Result<Self> {
+		let invalid = |reason: String| BadukError::InvalidDiagram { reason };
+		let mut rows: Vec<Vec<Option<PieceColor>>> = vec![];
+		for line in strip_colors(s).lines() {
+			let chars: Vec<char> =
+				line.chars().filter(|c| !c.is_whitespace()).collect();
+			// column letters above or below
+			let letters = (0..chars.len() as u8).map(notation::column_letter);
+			let is_letters =
+				chars.len() <= MAX_SIZE as usize && chars.iter().copied().eq(letters);
+			if chars.is_empty() || is_letters {
+				continue;
+			}
+			let mut row = vec![];
+			// row numbers on the sides, and lines between the points
+			for c in chars.into_iter() {
+				if c.is_ascii_digit() || c == '─' {
+					continue;
+				}
+				match diagram_point(c) {
+					Some(point) => row.push(point),
+					None => return Err(invalid(format!("unknown point `{}`", c))),
+				}
+			}
+			rows.push(row);
+		}
+
+		let size = rows.len();
+		if size == 0 {
+			return Err(invalid("diagram is empty".to_string()));
+		}
+		if size > MAX_SIZE as usize {
+			return Err(invalid(format!("{} rows is more than a board has", size)));
+		}
+		let mut board = Board::with_size(size as u8)?;
+		for (i, row) in rows.iter().enumerate() {
+			if row.len() != size {
+				return Err(invalid(format!(
+					"row {} is {} points wide, expected {}",
+					i + 1,
+					row.len(),
+					size
+				)));
+			}
+			for (x, point) in row.iter().enumerate() {
+				if let Some(color) = point {
+					let pos = Pos {
+						x: x as u8,
+						y: (size - 1 - i) as u8,
+					};
+					board.put(PlacedPiece::new(*color, pos));
+				}
+			}
+		}
+		Ok(board)
+	}
+
+	/// The board as a plain ASCII diagram with coordinates: `X` for black,
+	/// `O` for white, `.` for empty points and `+` for empty star points.
+	pub fn to_ascii(&self) -> String {
+		let max = self.size - 1;
+		let star_points = star_points(self.size);
+		let letters: Vec<String> = (0..self.size)
+			.map(|x| notation::column_letter(x).to_string())
+			.collect();
+		let letters = format!("   {}\n", letters.join(" "));
+		let mut s = letters.clone();
+		for i in 0..self.size {
+			let y = max - i;
+			let points: Vec<&str> = (0..self.size)
+				.map(|x| match self.color_at(Pos { x, y }) {
+					Some(Black) => "X",
+					Some(White) => "O",
+					None if star_points.contains(&Pos { x, y }) => "+",
+					None => ".",
+				})
+				.collect();
+			let row = format!("{:>2} {} {}\n", y + 1, points.join(" "), y + 1);
+			s.push_str(&row);
+		}
+		s.push_str(&letters);
+		s
+	}
+
 	pub fn all_pieces(&self) -> Vec<PlacedPieceRef> {
 		let mut pieces: Vec<PlacedPieceRef> = vec![];
 		for (i, s) in self.display_board.iter().enumerate() {
@@ -300,7 +551,10 @@ impl Board {
 				if let Some(piece) = p {
 					pieces.push(PlacedPieceRef {
 						piece,
-						pos: (i as u8, j as u8),
+						pos: Pos {
+							x: i as u8,
+							y: j as u8,
+						},
 					});
 				}
 			}
@@ -311,37 +565,47 @@ impl Board {
 
 impl fmt::Display for Board {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let max = self.size - 1;
+		let star_points = star_points(self.size);
+		// column letters above and below, row numbers on both sides
+		let letters: Vec<String> = (0..self.size)
+			.map(|x| notation::column_letter(x).to_string())
+			.collect();
+		let letters = format!("   {}\n", letters.join(" ")).bright_black();
 		let mut s: String = String::new();
 		s.push_str("\n");
-		for i in 0..19u8 {
-			for j in 0..19u8 {
-				let pos = (j, 18 - i);
+		s = format!("{}{}", s, letters);
+		for i in 0..self.size {
+			let row = format!("{:>2} ", max - i + 1);
+			s = format!("{}{}", s, row.bright_black());
+			for j in 0..self.size {
+				let pos = Pos { x: j, y: max - i };
 				s = format!(
 					"{}{}",
 					s,
-					match self.get_color(pos) {
+					match self.color_at(pos) {
 						Some(color) => match color {
 							Black => BLACK_CHAR.bold(),
 							White => WHITE_CHAR.bold(),
 						},
-						None => if DOT_POSITIONS.contains(&pos) {
+						None => if star_points.contains(&pos) {
 							DOT_CHAR
 						} else {
-							if pos.1 == 0 && pos.0 == 0 {
+							if pos.y == 0 && pos.x == 0 {
 								GRID_BOT_RIGHT
-							} else if pos.1 == 0 && pos.0 == 18 {
+							} else if pos.y == 0 && pos.x == max {
 								GRID_BOT_LEFT
-							} else if pos.1 == 18 && pos.0 == 0 {
+							} else if pos.y == max && pos.x == 0 {
 								GRID_TOP_LEFT
-							} else if pos.1 == 18 && pos.0 == 18 {
+							} else if pos.y == max && pos.x == max {
 								GRID_TOP_RIGHT
-							} else if pos.1 == 0 {
+							} else if pos.y == 0 {
 								GRID_BOT
-							} else if pos.1 == 18 {
+							} else if pos.y == max {
 								GRID_TOP
-							} else if pos.0 == 0 {
+							} else if pos.x == 0 {
 								GRID_LEFT
-							} else if pos.0 == 18 {
+							} else if pos.x == max {
 								GRID_RIGHT
 							} else {
 								EMPTY_CHAR
@@ -351,12 +615,14 @@ impl fmt::Display for Board {
 					}
 				);
 
-				if pos.0 != 18 {
+				if pos.x != max {
 					s = format!("{}{}", s, "─".bright_black())
 				}
 			}
-			s.push_str("\n");
+			let row = format!(" {}", max - i + 1);
+			s = format!("{}{}\n", s, row.bright_black());
 		}
+		s = format!("{}{}", s, letters);
 		write!(f, "{}", s)
 	}
 }
@@ -0,0 +1,56 @@
+use crate::{
+	board::*,
+	game::Game,
+	piece::*,
+	pos::Pos,
+};
+
+/// Whether every point next to `pos` is a stone of `color` or the edge, an
+/// eye the bot would only hurt itself filling.
+fn is_own_eye(board: &Board, pos: Pos, color: PieceColor) -> bool {
+	board.adjacents(pos).iter().all(|adj| match adj {
+		PieceAdjacency::Piece(p) => p.piece.color == color,
+		PieceAdjacency::Empty(_) => false,
+		PieceAdjacency::Edge => true,
+	})
+}
+
+/// A weak computer player. It plays the legal move that leaves its color
+/// the most of the board by the influence estimate, avoiding self-atari and
+/// its own eyes. `None` when there is nothing left worth playing.
+pub fn choose_move(game: &Game) -> Option<PlacedPiece> {
+	// the game may be looked at from an earlier move
+	let board = game.get_board_at_move(game.len()).ok()?;
+	best_move(&board, game.current_turn_color())
+}
+
+/// The move `choose_move` picks for `color` on `board`. It estimates the
+/// influence after every legal move, which takes a while on a big board, so
+/// a front end can run it away from its frame.
+pub fn best_move(board: &Board, color: PieceColor) -> Option<PlacedPiece> {
+	let sign = match color {
+		Black => 1,
+		White => -1,
+	};
+	let mut best: Option<(i32, PlacedPiece)> = None;
+	for x in 0..board.size() {
+		for y in 0..board.size() {
+			let m = PlacedPiece::new(color, Pos { x, y });
+			if board.valid_move(m).is_err() || is_own_eye(board, m.pos, color) {
+				continue;
+			}
+			let mut after = board.clone();
+			if after.do_move(m).is_err() {
+				continue;
+			}
+			if after.liberties_shape(m.pos).len() < 2 {
+				continue;
+			}
+			let score = sign * after.influence().score();
+			if best.map_or(true, |(best_score, _)| score > best_score) {
+				best = Some((score, m));
+			}
+		}
+	}
+	best.map(|(_, m)| m)
+}
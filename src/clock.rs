@@ -0,0 +1,432 @@
+use crate::{
+	error::*,
+	piece::*,
+};
+use std::{
+	fmt,
+	str::FromStr,
+	sync::{
+		atomic::{
+			AtomicU64,
+			Ordering,
+		},
+		Arc,
+	},
+	time::{
+		Duration,
+		Instant,
+	},
+};
+
+/// Where a clock gets the time from
+pub trait TimeSource: Send + Sync {
+	/// time passed since some fixed point
+	fn now(&self) -> Duration;
+}
+
+/// The time of the system's monotonic clock
+pub struct RealTime(Instant);
+
+impl RealTime {
+	pub fn new() -> Self {
+		RealTime(Instant::now())
+	}
+}
+
+impl Default for RealTime {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl TimeSource for RealTime {
+	fn now(&self) -> Duration {
+		self.0.elapsed()
+	}
+}
+
+/// A time that only moves when told to. Clones share the same time, so one
+/// can be given to a clock and the other kept to move it.
+#[derive(Clone, Default)]
+pub struct ManualTime(Arc<AtomicU64>);
+
+impl ManualTime {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn advance(&self, d: Duration) {
+		self.0.fetch_add(d.as_millis() as u64, Ordering::Relaxed);
+	}
+}
+
+impl TimeSource for ManualTime {
+	fn now(&self) -> Duration {
+		Duration::from_millis(self.0.load(Ordering::Relaxed))
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeControl {
+	/// all moves in `main`
+	Absolute { main: Duration },
+	/// `increment` is added after every move
+	Fischer { main: Duration, increment: Duration },
+	/// After the main time, every move has to be played within `period`.
+	/// Running out of a period uses it up, losing once all `periods` are.
+	ByoYomi {
+		main: Duration,
+		period: Duration,
+		periods: u32,
+	},
+	/// After the main time, `stones` moves have to be played within `period`,
+	/// then the next block of `period` starts.
+	Canadian {
+		main: Duration,
+		period: Duration,
+		stones: u32,
+	},
+}
+
+impl TimeControl {
+	pub fn main(&self) -> Duration {
+		match *self {
+			TimeControl::Absolute { main }
+			| TimeControl::Fischer { main, .. }
+			| TimeControl::ByoYomi { main, .. }
+			| TimeControl::Canadian { main, .. } => main,
+		}
+	}
+}
+
+/// `5m`, `30s`, `1h` or a bare number of seconds
+fn parse_duration(s: &str) -> Option<Duration> {
+	let (number, unit) = match s.char_indices().last()? {
+		(i, 'h') => (&s[..i], 3600),
+		(i, 'm') => (&s[..i], 60),
+		(i, 's') => (&s[..i], 1),
+		_ => (s, 1),
+	};
+	number
+		.parse::<u64>()
+		.ok()
+		.map(|n| Duration::from_secs(n * unit))
+}
+
+/// Parses `absolute:<main>`, `fischer:<main>+<increment>`,
+/// `byoyomi:<main>+<periods>x<period>` and `canadian:<main>+<stones>/<period>`,
+/// e.g. `byoyomi:10m+5x30s`
+impl FromStr for TimeControl {
+	type Err = BadukError;
+
+	fn from_str(s: &str) -> Result<Self> {
+		let invalid = || BadukError::InvalidTimeControl {
+			spec: s.to_string(),
+		};
+		let mut parts = s.splitn(2, ':');
+		let kind = parts.next().ok_or_else(invalid)?;
+		let mut times = parts.next().ok_or_else(invalid)?.splitn(2, '+');
+		let main = times.next().and_then(parse_duration).ok_or_else(invalid)?;
+		let extra = times.next();
+		// the overtime part, split around `x` or `/`
+		let overtime = |sep: char| -> Result<(u32, Duration)> {
+			let mut split = extra.ok_or_else(invalid)?.splitn(2, sep);
+			let count = split.next().and_then(|n| n.parse().ok());
+			let period = split.next().and_then(parse_duration);
+			match (count, period) {
+				(Some(count), Some(period)) if count > 0 => Ok((count, period)),
+				_ => Err(invalid()),
+			}
+		};
+		let control = match kind {
+			"absolute" if extra.is_none() => TimeControl::Absolute { main },
+			"fischer" => TimeControl::Fischer {
+				main,
+				increment: extra.and_then(parse_duration).ok_or_else(invalid)?,
+			},
+			"byoyomi" => {
+				let (periods, period) = overtime('x')?;
+				TimeControl::ByoYomi {
+					main,
+					period,
+					periods,
+				}
+			}
+			"canadian" => {
+				let (stones, period) = overtime('/')?;
+				TimeControl::Canadian {
+					main,
+					period,
+					stones,
+				}
+			}
+			_ => return Err(invalid()),
+		};
+		Ok(control)
+	}
+}
+
+/// the largest unit that writes `d` exactly, the opposite of `parse_duration`
+fn format_spec_duration(d: Duration) -> String {
+	match d.as_secs() {
+		s if s > 0 && s % 3600 == 0 => format!("{}h", s / 3600),
+		s if s > 0 && s % 60 == 0 => format!("{}m", s / 60),
+		s => format!("{}s", s),
+	}
+}
+
+/// Written the way it is parsed, e.g. `byoyomi:10m+5x30s`
+impl fmt::Display for TimeControl {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let main = format_spec_duration(self.main());
+		match *self {
+			TimeControl::Absolute { .. } => write!(f, "absolute:{}", main),
+			TimeControl::Fischer { increment, .. } => {
+				write!(f, "fischer:{}+{}", main, format_spec_duration(increment))
+			}
+			TimeControl::ByoYomi {
+				period, periods, ..
+			} => write!(
+				f,
+				"byoyomi:{}+{}x{}",
+				main,
+				periods,
+				format_spec_duration(period)
+			),
+			TimeControl::Canadian { period, stones, .. } => write!(
+				f,
+				"canadian:{}+{}/{}",
+				main,
+				stones,
+				format_spec_duration(period)
+			),
+		}
+	}
+}
+
+/// What is left on one player's clock
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlayerTime {
+	pub main: Duration,
+	/// time left in the current byo-yomi period or Canadian block
+	pub overtime: Duration,
+	/// byo-yomi periods left, counting the current one
+	pub periods: u32,
+	/// moves left to play in the current Canadian block
+	pub stones: u32,
+}
+
+impl PlayerTime {
+	fn new(control: TimeControl) -> Self {
+		let (overtime, periods, stones) = match control {
+			TimeControl::ByoYomi {
+				period, periods, ..
+			} => (period, periods, 0),
+			TimeControl::Canadian { period, stones, .. } => (period, 0, stones),
+			_ => (Duration::from_secs(0), 0, 0),
+		};
+		Self {
+			main: control.main(),
+			overtime,
+			periods,
+			stones,
+		}
+	}
+
+	pub fn in_overtime(&self) -> bool {
+		self.main == Duration::from_secs(0)
+	}
+
+	/// the time left after `elapsed` more, or `None` if it ran out
+	fn spend(&self, control: TimeControl, elapsed: Duration) -> Option<Self> {
+		let mut t = *self;
+		if elapsed < t.main {
+			t.main -= elapsed;
+			return Some(t);
+		}
+		let mut elapsed = elapsed - t.main;
+		t.main = Duration::from_secs(0);
+		match control {
+			TimeControl::Absolute { .. } | TimeControl::Fischer { .. } => None,
+			TimeControl::ByoYomi { period, .. } => {
+				while t.periods > 0 {
+					if elapsed < t.overtime {
+						t.overtime -= elapsed;
+						return Some(t);
+					}
+					elapsed -= t.overtime;
+					t.periods -= 1;
+					t.overtime = period;
+				}
+				None
+			}
+			TimeControl::Canadian { .. } => {
+				if elapsed < t.overtime {
+					t.overtime -= elapsed;
+					Some(t)
+				} else {
+					None
+				}
+			}
+		}
+	}
+
+	/// what a finished move gives back
+	fn moved(&mut self, control: TimeControl) {
+		match control {
+			TimeControl::Absolute { .. } => {}
+			TimeControl::Fischer { increment, .. } => self.main += increment,
+			TimeControl::ByoYomi { period, .. } => {
+				if self.in_overtime() {
+					self.overtime = period;
+				}
+			}
+			TimeControl::Canadian { period, stones, .. } => {
+				if self.in_overtime() {
+					self.stones -= 1;
+					if self.stones == 0 {
+						self.stones = stones;
+						self.overtime = period;
+					}
+				}
+			}
+		}
+	}
+}
+
+/// `h:mm:ss` or `m:ss`, rounded up so a clock only shows `0:00` once it ran
+/// out
+fn format_duration(f: &mut fmt::Formatter<'_>, d: Duration) -> fmt::Result {
+	let mut secs = d.as_secs();
+	if d.subsec_nanos() > 0 {
+		secs += 1;
+	}
+	if secs >= 3600 {
+		write!(f, "{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+	} else {
+		write!(f, "{}:{:02}", secs / 60, secs % 60)
+	}
+}
+
+/// Main time while there is some, then `0:25 (3)` for byo-yomi with 3 periods
+/// left, or `1:40 /4` for 4 Canadian stones left to play
+impl fmt::Display for PlayerTime {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if !self.in_overtime() {
+			return format_duration(f, self.main);
+		}
+		format_duration(f, self.overtime)?;
+		if self.periods > 0 {
+			write!(f, " ({})", self.periods)
+		} else if self.stones > 0 {
+			write!(f, " /{}", self.stones)
+		} else {
+			Ok(())
+		}
+	}
+}
+
+/// A game clock, running for one player at a time
+pub struct Clock {
+	control: TimeControl,
+	source: Box<dyn TimeSource>,
+	black: PlayerTime,
+	white: PlayerTime,
+	/// whose clock is running, and since when
+	running: Option<(PieceColor, Duration)>,
+	flagged: Option<PieceColor>,
+}
+
+impl Clock {
+	pub fn new(control: TimeControl, source: Box<dyn TimeSource>) -> Self {
+		Self {
+			control,
+			source,
+			black: PlayerTime::new(control),
+			white: PlayerTime::new(control),
+			running: None,
+			flagged: None,
+		}
+	}
+
+	/// a clock going by the system's time
+	pub fn real(control: TimeControl) -> Self {
+		Self::new(control, Box::new(RealTime::new()))
+	}
+
+	pub fn control(&self) -> TimeControl {
+		self.control
+	}
+
+	pub fn running(&self) -> Option<PieceColor> {
+		self.running.map(|(color, _)| color)
+	}
+
+	fn time_mut(&mut self, color: PieceColor) -> &mut PlayerTime {
+		match color {
+			Black => &mut self.black,
+			White => &mut self.white,
+		}
+	}
+
+	/// Time left for `color`, `None` once it ran out
+	pub fn remaining(&self, color: PieceColor) -> Option<PlayerTime> {
+		if self.flagged == Some(color) {
+			return None;
+		}
+		let time = match color {
+			Black => self.black,
+			White => self.white,
+		};
+		match self.running {
+			Some((running, since)) if running == color => {
+				time.spend(self.control, self.source.now() - since)
+			}
+			_ => Some(time),
+		}
+	}
+
+	/// the player who ran out of time, if one did
+	pub fn flagged(&mut self) -> Option<PieceColor> {
+		if self.flagged.is_none() {
+			if let Some(color) = self.running() {
+				if self.remaining(color).is_none() {
+					self.flagged = Some(color);
+					self.running = None;
+				}
+			}
+		}
+		self.flagged
+	}
+
+	/// stop the clock, keeping the time used so far
+	pub fn stop(&mut self) {
+		if let Some((color, _)) = self.running {
+			match self.remaining(color) {
+				Some(time) => *self.time_mut(color) = time,
+				None => self.flagged = Some(color),
+			}
+		}
+		self.running = None;
+	}
+
+	/// stop whichever clock runs and start `color`'s
+	pub fn start(&mut self, color: PieceColor) {
+		self.stop();
+		if self.flagged.is_none() {
+			self.running = Some((color, self.source.now()));
+		}
+	}
+
+	/// `color` finished a move: their time stops, any overtime or increment is
+	/// given back, and the opponent's clock starts
+	pub fn press(&mut self, color: PieceColor) {
+		self.stop();
+		if self.flagged.is_some() {
+			return;
+		}
+		let control = self.control;
+		self.time_mut(color).moved(control);
+		self.start(color.opposite());
+	}
+}
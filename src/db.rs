@@ -0,0 +1,527 @@
+use crate::{
+	board::*,
+	error::*,
+	game_result::GameResult,
+	joseki::Corner,
+	piece::*,
+	sgf::{
+		self,
+		SgfNode,
+	},
+	symmetry::Symmetry,
+	zobrist,
+};
+use std::{
+	collections::{
+		HashMap,
+		HashSet,
+	},
+	fs,
+	io::{
+		BufReader,
+		BufWriter,
+		Read,
+		Write,
+	},
+	path::{
+		Path,
+		PathBuf,
+	},
+};
+
+const MAGIC: &[u8; 8] = b"BADUKDB\0";
+const VERSION: u8 = 1;
+
+/// A game as found in an SGF file: its setup stones and main line.
+#[derive(Clone)]
+pub struct GameRecord {
+	pub info: GameInfo,
+	pub setup: Board,
+	pub moves: Vec<PlacedPiece>,
+}
+
+impl GameRecord {
+	pub fn from_sgf(path: &str, root: &SgfNode) -> Result<Self> {
+		let size = match root.get("SZ") {
+			Some(sz) => sz.parse().map_err(|_| BadukError::SgfParse {
+				reason: format!("invalid board size `{}`", sz),
+			})?,
+			None => MAX_SIZE,
+		};
+		let mut setup = Board::with_size(size)?;
+		for (ident, color) in [("AB", Black), ("AW", White)].iter() {
+			if let Some(values) = root.get_all(ident) {
+				for pos in sgf::parse_point_list(values, size) {
+					setup.set(PlacedPiece::new(*color, pos))?;
+				}
+			}
+		}
+		let moves = root
+			.main_line()
+			.iter()
+			.filter_map(|node| node.get_move(size))
+			.collect();
+		let text = |ident: &str| root.get(ident).unwrap_or("").to_string();
+		Ok(Self {
+			info: GameInfo {
+				path: path.to_string(),
+				black: text("PB"),
+				white: text("PW"),
+				result: text("RE"),
+				size,
+			},
+			setup,
+			moves,
+		})
+	}
+
+	/// every game in an SGF collection
+	pub fn parse(path: &str, sgf: &str) -> Result<Vec<Self>> {
+		sgf::parse(sgf)?
+			.iter()
+			.map(|root| Self::from_sgf(path, root))
+			.collect()
+	}
+
+	/// the board after `i` moves, see `Game::get_board_at_move`
+	pub fn board_at_move(&self, i: usize) -> Result<Board> {
+		if i > self.moves.len() {
+			return Err(BadukError::MoveIndexOutOfBounds {
+				history_size: self.moves.len(),
+			});
+		}
+		let mut board = self.setup.clone();
+		for m in self.moves[..i].iter() {
+			board.do_move(*m)?;
+		}
+		Ok(board)
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameInfo {
+	pub path: String,
+	pub black: String,
+	pub white: String,
+	/// the SGF `RE` property
+	pub result: String,
+	pub size: u8,
+}
+
+impl GameInfo {
+	pub fn winner(&self) -> Option<PieceColor> {
+		self
+			.result
+			.parse::<GameResult>()
+			.ok()
+			.and_then(|result| result.winner())
+	}
+}
+
+/// A position reached in one of the indexed games.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Occurrence {
+	/// index into `Database::games`
+	pub game: u32,
+	/// number of moves played to reach the position
+	pub move_number: u16,
+	/// the move played next, in the orientation it was indexed in
+	pub next: Option<PlacedPiece>,
+}
+
+/// How often a move was played from a position, and how it went.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Continuation {
+	pub next: PlacedPiece,
+	pub count: usize,
+	/// games won by the player making the move
+	pub wins: usize,
+	/// games with a known winner
+	pub decided: usize,
+}
+
+impl Continuation {
+	pub fn win_rate(&self) -> Option<f32> {
+		if self.decided == 0 {
+			None
+		} else {
+			Some(self.wins as f32 / self.decided as f32)
+		}
+	}
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SearchResult {
+	pub occurrences: Vec<Occurrence>,
+	/// most played first, in the orientation of the searched board
+	pub continuations: Vec<Continuation>,
+}
+
+#[derive(Debug, Default)]
+pub struct IndexReport {
+	pub files: usize,
+	pub games: usize,
+	/// files that could not be read or parsed. Games with illegal moves are
+	/// indexed up to the first illegal move and are not reported here.
+	pub errors: Vec<(PathBuf, BadukError)>,
+}
+
+/// An index of every position reached in a set of games, and of every corner
+/// shape. Positions are stored under the hash of their canonical form, so
+/// they are found in any orientation.
+#[derive(Default)]
+pub struct Database {
+	games: Vec<GameInfo>,
+	positions: HashMap<u64, Vec<Occurrence>>,
+	corners: HashMap<u64, Vec<Occurrence>>,
+}
+
+/// hash of the stones in a corner moved to the bottom left, ignoring the board
+/// size. Mirror images along the diagonal share a hash; the symmetries that
+/// give it are returned as well.
+fn corner_hash(board: &Board, corner: Corner) -> Option<(u64, Vec<Symmetry>)> {
+	let size = board.size();
+	let pieces: Vec<PlacedPiece> = board
+		.all_pieces()
+		.into_iter()
+		.map(PlacedPiece::from)
+		.filter(|p| Corner::of(p.pos, size) == Some(corner))
+		.collect();
+	if pieces.is_empty() {
+		return None;
+	}
+	let to_corner = corner.to_bottom_left();
+	let mut best: Option<(u64, Vec<Symmetry>)> = None;
+	for symmetry in [to_corner, to_corner.then(Symmetry::FlipDiagonal)].iter() {
+		let hash = pieces.iter().fold(0, |hash, p| {
+			hash ^ zobrist::piece_key(symmetry.apply(p.pos, size), p.piece.color)
+		});
+		match &mut best {
+			Some((best_hash, symmetries)) if hash == *best_hash => {
+				symmetries.push(*symmetry)
+			}
+			Some((best_hash, _)) if hash > *best_hash => {}
+			_ => best = Some((hash, vec![*symmetry])),
+		}
+	}
+	best
+}
+
+/// the smallest image of a move under several equivalent symmetries, so the
+/// same continuation is always stored the same way
+fn orient_move(
+	m: PlacedPiece,
+	symmetries: &[Symmetry],
+	size: u8,
+) -> PlacedPiece {
+	symmetries
+		.iter()
+		.map(|s| m.transform(*s, size))
+		.min_by_key(|m| m.pos)
+		.unwrap()
+}
+
+impl Database {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn games(&self) -> &[GameInfo] {
+		&self.games
+	}
+
+	/// number of distinct positions indexed
+	pub fn num_positions(&self) -> usize {
+		self.positions.len()
+	}
+
+	pub fn add_game(&mut self, record: &GameRecord) {
+		let game = self.games.len() as u32;
+		let size = record.info.size;
+		self.games.push(record.info.clone());
+
+		let mut board = record.setup.clone();
+		let mut seen_positions: HashSet<u64> = HashSet::new();
+		let mut seen_corners: HashSet<u64> = HashSet::new();
+		for move_number in 0..=record.moves.len() {
+			let next = record.moves.get(move_number).copied();
+
+			let canonical = board.canonical();
+			if seen_positions.insert(canonical.hash) {
+				self
+					.positions
+					.entry(canonical.hash)
+					.or_default()
+					.push(Occurrence {
+						game,
+						move_number: move_number as u16,
+						next: next.map(|m| orient_move(m, &canonical.symmetries, size)),
+					});
+			}
+
+			for corner in Corner::ALL.iter() {
+				if let Some((hash, symmetries)) = corner_hash(&board, *corner) {
+					if seen_corners.insert(hash) {
+						let next = next
+							.filter(|m| Corner::of(m.pos, size) == Some(*corner))
+							.map(|m| orient_move(m, &symmetries, size));
+						self.corners.entry(hash).or_default().push(Occurrence {
+							game,
+							move_number: move_number as u16,
+							next,
+						});
+					}
+				}
+			}
+
+			match next {
+				Some(m) => {
+					if board.do_move(m).is_err() {
+						break;
+					}
+				}
+				None => break,
+			}
+		}
+	}
+
+	/// add every game of an SGF collection, returning how many were added
+	pub fn add_sgf(&mut self, path: &str, sgf: &str) -> Result<usize> {
+		let records = GameRecord::parse(path, sgf)?;
+		for record in records.iter() {
+			self.add_game(record);
+		}
+		Ok(records.len())
+	}
+
+	/// add every `.sgf` file in a directory and its subdirectories
+	pub fn index_dir<P: AsRef<Path>>(&mut self, dir: P) -> Result<IndexReport> {
+		let mut report = IndexReport::default();
+		let mut dirs: Vec<PathBuf> = vec![dir.as_ref().to_path_buf()];
+		while let Some(dir) = dirs.pop() {
+			let mut entries: Vec<PathBuf> = fs::read_dir(&dir)?
+				.filter_map(|entry| entry.ok().map(|e| e.path()))
+				.collect();
+			entries.sort();
+			for path in entries {
+				if path.is_dir() {
+					dirs.push(path);
+					continue;
+				}
+				let is_sgf = path
+					.extension()
+					.and_then(|ext| ext.to_str())
+					.map_or(false, |ext| ext.eq_ignore_ascii_case("sgf"));
+				if !is_sgf {
+					continue;
+				}
+				report.files += 1;
+				let added = fs::read_to_string(&path)
+					.map_err(BadukError::from)
+					.and_then(|s| self.add_sgf(&path.to_string_lossy(), &s));
+				match added {
+					Ok(games) => report.games += games,
+					Err(err) => report.errors.push((path, err)),
+				}
+			}
+		}
+		Ok(report)
+	}
+
+	/// every game that reached `board`, in any orientation
+	pub fn search_position(&self, board: &Board) -> SearchResult {
+		let canonical = board.canonical();
+		let back = canonical.symmetry.inverse();
+		self.search(self.positions.get(&canonical.hash), |m| {
+			m.transform(back, board.size())
+		})
+	}
+
+	/// every game that had the same stones in a corner as `board` has in
+	/// `corner`, in any corner and orientation
+	pub fn search_corner(&self, board: &Board, corner: Corner) -> SearchResult {
+		match corner_hash(board, corner) {
+			Some((hash, symmetries)) => {
+				let back = symmetries[0].inverse();
+				self
+					.search(self.corners.get(&hash), |m| m.transform(back, board.size()))
+			}
+			None => SearchResult::default(),
+		}
+	}
+
+	fn search<F: Fn(PlacedPiece) -> PlacedPiece>(
+		&self,
+		occurrences: Option<&Vec<Occurrence>>,
+		orient: F,
+	) -> SearchResult {
+		let occurrences = match occurrences {
+			Some(occurrences) => occurrences.clone(),
+			None => return SearchResult::default(),
+		};
+		let mut continuations: Vec<Continuation> = vec![];
+		for occurrence in occurrences.iter() {
+			let next = match occurrence.next {
+				Some(next) => orient(next),
+				None => continue,
+			};
+			let winner = self.games[occurrence.game as usize].winner();
+			let i = match continuations.iter().position(|c| c.next == next) {
+				Some(i) => i,
+				None => {
+					continuations.push(Continuation {
+						next,
+						count: 0,
+						wins: 0,
+						decided: 0,
+					});
+					continuations.len() - 1
+				}
+			};
+			let continuation = &mut continuations[i];
+			continuation.count += 1;
+			if let Some(winner) = winner {
+				continuation.decided += 1;
+				if winner == next.piece.color {
+					continuation.wins += 1;
+				}
+			}
+		}
+		continuations.sort_by_key(|c| std::cmp::Reverse(c.count));
+		SearchResult {
+			occurrences,
+			continuations,
+		}
+	}
+
+	pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+		let mut w = BufWriter::new(fs::File::create(path)?);
+		w.write_all(MAGIC)?;
+		w.write_all(&[VERSION])?;
+		w.write_all(&(self.games.len() as u32).to_le_bytes())?;
+		for game in self.games.iter() {
+			for s in [&game.path, &game.black, &game.white, &game.result].iter() {
+				w.write_all(&(s.len() as u32).to_le_bytes())?;
+				w.write_all(s.as_bytes())?;
+			}
+			w.write_all(&[game.size])?;
+		}
+		for table in [&self.positions, &self.corners].iter() {
+			w.write_all(&(table.len() as u32).to_le_bytes())?;
+			for (hash, occurrences) in table.iter() {
+				w.write_all(&hash.to_le_bytes())?;
+				w.write_all(&(occurrences.len() as u32).to_le_bytes())?;
+				for o in occurrences.iter() {
+					w.write_all(&o.game.to_le_bytes())?;
+					w.write_all(&o.move_number.to_le_bytes())?;
+					w.write_all(&match o.next {
+						None => [0, 0, 0],
+						Some(m) => [
+							match m.piece.color {
+								Black => 1,
+								White => 2,
+							},
+							m.pos.x,
+							m.pos.y,
+						],
+					})?;
+				}
+			}
+		}
+		w.flush()?;
+		Ok(())
+	}
+
+	pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+		let mut r = BufReader::new(fs::File::open(path)?);
+		let invalid = |reason: &str| BadukError::InvalidDatabase {
+			reason: reason.to_string(),
+		};
+
+		let mut magic = [0u8; 8];
+		r.read_exact(&mut magic)?;
+		if &magic != MAGIC {
+			return Err(invalid("not a baduk database"));
+		}
+		if read_u8(&mut r)? != VERSION {
+			return Err(invalid("unsupported version"));
+		}
+
+		let mut db = Database::new();
+		for _ in 0..read_u32(&mut r)? {
+			let mut strings: Vec<String> = vec![];
+			for _ in 0..4 {
+				// read what is there, the length could be anything in a bad file
+				let len = read_u32(&mut r)? as u64;
+				let mut bytes: Vec<u8> = vec![];
+				(&mut r).take(len).read_to_end(&mut bytes)?;
+				if bytes.len() as u64 != len {
+					return Err(invalid("text cut short"));
+				}
+				strings
+					.push(String::from_utf8(bytes).map_err(|_| invalid("invalid text"))?);
+			}
+			let mut strings = strings.into_iter();
+			db.games.push(GameInfo {
+				path: strings.next().unwrap(),
+				black: strings.next().unwrap(),
+				white: strings.next().unwrap(),
+				result: strings.next().unwrap(),
+				size: read_u8(&mut r)?,
+			});
+		}
+		for table in [&mut db.positions, &mut db.corners].iter_mut() {
+			for _ in 0..read_u32(&mut r)? {
+				let hash = read_u64(&mut r)?;
+				let mut occurrences: Vec<Occurrence> = vec![];
+				for _ in 0..read_u32(&mut r)? {
+					let game = read_u32(&mut r)?;
+					let move_number = read_u16(&mut r)?;
+					let mut next = [0u8; 3];
+					r.read_exact(&mut next)?;
+					let color = match next[0] {
+						0 => None,
+						1 => Some(Black),
+						2 => Some(White),
+						_ => return Err(invalid("invalid move")),
+					};
+					let next = match color {
+						Some(c) => Some(PlacedPiece::from_coords(c, next[1], next[2])?),
+						None => None,
+					};
+					occurrences.push(Occurrence {
+						game,
+						move_number,
+						next,
+					});
+				}
+				table.insert(hash, occurrences);
+			}
+		}
+		Ok(db)
+	}
+}
+
+fn read_u8<R: Read>(r: &mut R) -> Result<u8> {
+	let mut buf = [0u8; 1];
+	r.read_exact(&mut buf)?;
+	Ok(buf[0])
+}
+
+fn read_u16<R: Read>(r: &mut R) -> Result<u16> {
+	let mut buf = [0u8; 2];
+	r.read_exact(&mut buf)?;
+	Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32<R: Read>(r: &mut R) -> Result<u32> {
+	let mut buf = [0u8; 4];
+	r.read_exact(&mut buf)?;
+	Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(r: &mut R) -> Result<u64> {
+	let mut buf = [0u8; 8];
+	r.read_exact(&mut buf)?;
+	Ok(u64::from_le_bytes(buf))
+}
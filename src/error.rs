@@ -1,4 +1,4 @@
-use crate::{piece::*, pos::*};
+use crate::{game_result::*, piece::*, pos::*};
 use snafu::{ResultExt, Snafu};
 
 #[derive(Debug, Snafu)]
@@ -7,6 +7,36 @@ pub enum BadukError {
 	InvalidMove { source: InvalidMoveError },
 	#[snafu(display("Position: {:?} is out of bounds", pos))]
 	PosOutOfBounds { pos: Pos },
+	#[snafu(display("Invalid coordinate `{}`", text))]
+	InvalidCoordinate { text: String },
+	#[snafu(display("Board size {} is not supported", size))]
+	InvalidBoardSize { size: u8 },
+	#[snafu(display("Handicap {} is not supported on a {} board", handicap, size))]
+	InvalidHandicap { handicap: u8, size: u8 },
+	#[snafu(display("Invalid pattern: {}", reason))]
+	InvalidPattern { reason: String },
+	#[snafu(display("Invalid board diagram: {}", reason))]
+	InvalidDiagram { reason: String },
+	#[snafu(display("Could not parse SGF: {}", reason))]
+	SgfParse { reason: String },
+	#[snafu(display("IO error: {}", source))]
+	Io { source: std::io::Error },
+	#[snafu(display("Invalid database: {}", reason))]
+	InvalidDatabase { reason: String },
+	#[snafu(display("Network protocol error: {}", reason))]
+	Protocol { reason: String },
+	#[snafu(display("Connection closed by the other side"))]
+	Disconnected,
+	#[snafu(display("There is no move to undo"))]
+	NothingToUndo,
+	#[snafu(display("The game has no such variation"))]
+	NoSuchVariation,
+	#[snafu(display("Unknown command `{}`", text))]
+	UnknownCommand { text: String },
+	#[snafu(display("Unknown ruleset `{}`", name))]
+	InvalidRuleset { name: String },
+	#[snafu(display("Invalid time control `{}`", spec))]
+	InvalidTimeControl { spec: String },
 	#[snafu(display(
 		"Index out of bounds: game is only {} moves long",
 		history_size
@@ -24,6 +54,14 @@ pub enum InvalidMoveError {
 	Ko,
 	#[snafu(display("it is {}'s turn", turn))]
 	NotYourTurn { turn: PieceColor },
+	#[snafu(display("the game is over ({})", result))]
+	GameOver { result: GameResult },
+}
+
+impl From<std::io::Error> for BadukError {
+	fn from(source: std::io::Error) -> Self {
+		BadukError::Io { source }
+	}
 }
 
 pub type Result<T> = std::result::Result<T, BadukError>;
@@ -1,7 +1,14 @@
 use crate::{
+	annotation::Annotations,
 	board::*,
+	clock::Clock,
 	error::*,
+	game_result::GameResult,
 	piece::*,
+	pos::Pos,
+	rules::Ruleset,
+	symmetry::Symmetry,
+	variations::Variations,
 };
 use colored::Colorize;
 use std::fmt;
@@ -39,37 +46,204 @@ fn handicap_stones() -> [Vec<(u8, u8)>; 9] {
 	]
 }
 
+/// Place the handicap stones of the 19x19 table on a board of any size,
+/// moving them to the star points along the edge, middle and far side.
+fn place_handicap(board: &mut Board, handicap: u8) -> Result<()> {
+	if handicap == 0 {
+		return Ok(());
+	}
+	let size = board.size();
+	let invalid = || BadukError::InvalidHandicap { handicap, size };
+	let edge = match size {
+		13..=MAX_SIZE => 3,
+		9..=12 => 2,
+		_ => return Err(invalid()),
+	};
+	let stones = handicap_stones();
+	let stones = stones.get(handicap as usize - 1).ok_or_else(invalid)?;
+	let place = |c: u8| match c {
+		3 => Some(edge),
+		9 if size % 2 == 1 => Some(size / 2),
+		15 => Some(size - 1 - edge),
+		_ => None,
+	};
+	for &(x, y) in stones {
+		match (place(x), place(y)) {
+			(Some(x), Some(y)) => {
+				board.set(PlacedPiece::new(PieceColor::Black, Pos { x, y }))?;
+			}
+			_ => return Err(invalid()),
+		}
+	}
+	Ok(())
+}
+
 pub struct Game {
 	pub board: Board,
+	/// the line of play being looked at, to its end
 	pub moves: Vec<PlacedPiece>,
+	/// every line played or loaded, including `moves`
+	variations: Variations,
 	pub handicap: u8,
+	/// the stones the game started from when it was set up by hand, instead
+	/// of the handicap stones
+	setup: Option<Vec<PlacedPiece>>,
+	/// who moves first: black, white after handicap stones, or whoever the
+	/// game was set up with
+	first_turn: PieceColor,
+	/// points white gets for moving second
+	pub komi: f32,
+	pub ruleset: Ruleset,
 	position: GamePosition,
+	/// the time control, if the game is played on time
+	pub clock: Option<Clock>,
+	result: Option<GameResult>,
+	/// who offered a draw since the last move
+	draw_offer: Option<PieceColor>,
 }
 
 impl Game {
 	pub fn new(handicap: u8) -> Self {
-		let mut board = Board::new();
-		if handicap > 0 {
-			let handicap_stones = &handicap_stones()[handicap as usize - 1];
-
-			for pos in handicap_stones {
-				board.set(PlacedPiece {
-					piece: Piece {
-						color: PieceColor::Black,
-					},
-					pos: *pos,
-				});
+		Self::with_size(MAX_SIZE, handicap).unwrap()
+	}
+
+	/// A new game on a `size` board, with `handicap` stones for black. The
+	/// komi is the ruleset's, or half a point with handicap stones, and white
+	/// moves first after any of them.
+	pub fn with_size(size: u8, handicap: u8) -> Result<Self> {
+		let mut game = Self::without_stones(size, handicap)?;
+		place_handicap(&mut game.board, handicap)?;
+		Ok(game)
+	}
+
+	/// A game as it was started elsewhere: from the `setup` stones if it was
+	/// set up, which may stand for handicap stones that have no usual place on
+	/// this board, or else from the `handicap` stones, with `first_turn` to
+	/// move.
+	pub fn with_start(
+		size: u8,
+		handicap: u8,
+		setup: Option<Vec<PlacedPiece>>,
+		first_turn: PieceColor,
+	) -> Result<Self> {
+		let mut game = match setup {
+			Some(stones) => {
+				let mut game = Self::without_stones(size, handicap)?;
+				game.set_up(stones, first_turn)?;
+				game
 			}
+			None => Self::with_size(size, handicap)?,
+		};
+		if game.first_turn != first_turn {
+			let stones = game.board.all_pieces().iter().map(|&p| p.into()).collect();
+			game.set_up(stones, first_turn)?;
 		}
-		Self {
+		Ok(game)
+	}
+
+	/// a new game with `handicap` stones that aren't on the board yet
+	fn without_stones(size: u8, handicap: u8) -> Result<Self> {
+		let board = Board::with_size(size)?;
+		let ruleset = Ruleset::default();
+		Ok(Self {
 			board,
 			moves: vec![],
+			variations: Variations::default(),
 			handicap,
+			setup: None,
+			first_turn: if handicap > 0 {
+				PieceColor::White
+			} else {
+				PieceColor::Black
+			},
+			komi: if handicap > 0 {
+				0.5
+			} else {
+				ruleset.default_komi()
+			},
+			ruleset,
 			position: GamePosition::Current,
+			clock: None,
+			result: None,
+			draw_offer: None,
+		})
+	}
+
+	/// play on a clock, starting the time of whoever is to move
+	pub fn set_clock(&mut self, mut clock: Clock) {
+		clock.start(self.current_turn_color());
+		self.clock = Some(clock);
+	}
+
+	/// how the game ended, if it has
+	pub fn result(&self) -> Option<GameResult> {
+		self.result
+	}
+
+	/// end the game if someone ran out of time
+	pub fn check_time(&mut self) -> Option<GameResult> {
+		if self.result.is_none() {
+			let flagged = self.clock.as_mut().and_then(|clock| clock.flagged());
+			if let Some(color) = flagged {
+				self.result = Some(GameResult::Time {
+					winner: color.opposite(),
+				});
+			}
+		}
+		self.result
+	}
+
+	/// fail if the game is over
+	fn check_playing(&mut self) -> Result<()> {
+		match self.check_time() {
+			Some(result) => Err(BadukError::InvalidMove {
+				source: InvalidMoveError::GameOver { result },
+			}),
+			None => Ok(()),
+		}
+	}
+
+	/// end the game, e.g. once it has been counted
+	pub fn end(&mut self, result: GameResult) -> Result<GameResult> {
+		self.check_playing()?;
+		self.result = Some(result);
+		self.draw_offer = None;
+		if let Some(clock) = &mut self.clock {
+			clock.stop();
+		}
+		Ok(result)
+	}
+
+	/// `color` gives up the game
+	pub fn resign(&mut self, color: PieceColor) -> Result<GameResult> {
+		self.end(GameResult::Resignation {
+			winner: color.opposite(),
+		})
+	}
+
+	/// Offer a draw, which is agreed once the other player offers one too.
+	/// Offers are withdrawn by the next move.
+	pub fn offer_draw(
+		&mut self,
+		color: PieceColor,
+	) -> Result<Option<GameResult>> {
+		self.check_playing()?;
+		match self.draw_offer {
+			Some(offered) if offered != color => self.end(GameResult::Draw).map(Some),
+			_ => {
+				self.draw_offer = Some(color);
+				Ok(None)
+			}
 		}
 	}
 
+	/// who offered a draw, if nobody moved since
+	pub fn draw_offer(&self) -> Option<PieceColor> {
+		self.draw_offer
+	}
+
 	pub fn do_move(&mut self, m: PlacedPiece) -> Result<MoveResult> {
+		self.check_playing()?;
 		if self.current_turn_color() != m.piece.color {
 			return Err(BadukError::InvalidMove {
 				source: InvalidMoveError::NotYourTurn {
@@ -87,6 +261,11 @@ impl Game {
 			}
 		};
 		self.moves.push(m);
+		self.variations.add(&self.moves);
+		self.draw_offer = None;
+		if let Some(clock) = &mut self.clock {
+			clock.press(m.piece.color);
+		}
 		Ok(res)
 	}
 
@@ -104,12 +283,88 @@ impl Game {
 		for (color, x, y) in moves {
 			self.do_move(PlacedPiece {
 				piece: Piece { color },
-				pos: (x, y),
+				pos: Pos { x, y },
 			})?;
 		}
 		Ok(())
 	}
 
+	/// Take back the last move. The board is played again up to the move
+	/// before, so captured stones come back and ko is as it was. The move
+	/// stays in the variations, see `remove_variation`.
+	pub fn undo(&mut self) -> Result<PlacedPiece> {
+		self.check_playing()?;
+		let m = self.moves.pop().ok_or(BadukError::NothingToUndo)?;
+		if let GamePosition::Past(pos) = self.position {
+			if pos >= self.len() {
+				self.position = GamePosition::Current;
+			}
+		}
+		self.set_position(self.position)?;
+		self.draw_offer = None;
+		if let Some(clock) = &mut self.clock {
+			clock.start(m.piece.color);
+		}
+		Ok(m)
+	}
+
+	/// Start again from a position set up by hand, as with the SGF `AB`, `AW`
+	/// and `PL` properties, with `turn` to move. The moves played so far are
+	/// forgotten, and the stones take the place of any handicap stones.
+	pub fn set_up(
+		&mut self,
+		stones: Vec<PlacedPiece>,
+		turn: PieceColor,
+	) -> Result<()> {
+		let mut board = Board::with_size(self.board.size())?;
+		for &stone in stones.iter() {
+			board.set(stone)?;
+		}
+		self.setup = Some(
+			board
+				.all_pieces()
+				.iter()
+				.map(|p| PlacedPiece::new(p.piece.color, p.pos))
+				.collect(),
+		);
+		self.first_turn = turn;
+		self.board = board;
+		self.moves.clear();
+		self.variations = Variations::default();
+		self.position = GamePosition::Current;
+		self.result = None;
+		self.draw_offer = None;
+		if let Some(clock) = &mut self.clock {
+			clock.start(turn);
+		}
+		Ok(())
+	}
+
+	/// the stones set up by hand, if the game didn't start from an empty
+	/// board or handicap stones
+	pub fn setup(&self) -> Option<&[PlacedPiece]> {
+		self.setup.as_deref()
+	}
+
+	/// who moved first, or moves first if nobody has yet
+	pub fn first_turn(&self) -> PieceColor {
+		self.first_turn
+	}
+
+	/// the board before the first move
+	fn start_board(&self) -> Result<Board> {
+		let mut board = Board::with_size(self.board.size())?;
+		match &self.setup {
+			Some(stones) => {
+				for &stone in stones.iter() {
+					board.set(stone)?;
+				}
+			}
+			None => place_handicap(&mut board, self.handicap)?,
+		}
+		Ok(board)
+	}
+
 	/// get the historical state of the board at a specific move
 	/// (Starts at 1, not at 0)
 	pub fn get_board_at_move(&self, i: usize) -> Result<Board> {
@@ -118,17 +373,27 @@ impl Game {
 				history_size: self.moves.len(),
 			});
 		}
-		let mut board = Board::new();
+		let mut board = self.start_board()?;
 		let moves_slice = self.moves.split_at(i).0;
 		for m in moves_slice {
-			board.do_move(m.clone())?;
+			board.do_move(*m)?;
 		}
 		Ok(board)
 	}
 
+	/// move `i` (starting at 1) played again, with the stones it captured
+	pub fn move_result(&self, i: usize) -> Result<MoveResult> {
+		if i == 0 || i > self.moves.len() {
+			return Err(BadukError::MoveIndexOutOfBounds {
+				history_size: self.moves.len(),
+			});
+		}
+		self.get_board_at_move(i - 1)?.do_move(self.moves[i - 1])
+	}
+
 	pub fn current_turn_color(&self) -> PieceColor {
 		if self.moves.len() == 0 {
-			PieceColor::Black
+			self.first_turn
 		} else {
 			self.moves.last().unwrap().piece.color.opposite()
 		}
@@ -144,6 +409,10 @@ impl Game {
 		self.moves.len()
 	}
 
+	pub fn is_empty(&self) -> bool {
+		self.moves.is_empty()
+	}
+
 	pub fn position(&self) -> GamePosition {
 		self.position
 	}
@@ -161,27 +430,172 @@ impl Game {
 		Ok(())
 	}
 
-	/// adjust the current turn by an amount, clamped to always be valid.
-	/// If set to the most recent turn, set it to `GamePosition::Current`
-	pub fn offset_turn(&mut self, amt: i32) {
-		match self.position {
-			GamePosition::Current => {
-				if amt < 0 {
-					self.position = GamePosition::Past(
-						(self.len() as i32 + amt).clamp(1, self.len() as i32) as usize,
-					);
-				}
+	/// apply a rotation or reflection to the board and every move
+	pub fn transform(&self, symmetry: Symmetry) -> Game {
+		let size = self.board.size();
+		Game {
+			board: self.board.transform(symmetry),
+			moves: self
+				.moves
+				.iter()
+				.map(|m| m.transform(symmetry, size))
+				.collect(),
+			variations: self.variations.transform(symmetry, size),
+			handicap: self.handicap,
+			setup: self.setup.as_ref().map(|stones| {
+				stones.iter().map(|s| s.transform(symmetry, size)).collect()
+			}),
+			first_turn: self.first_turn,
+			komi: self.komi,
+			ruleset: self.ruleset,
+			position: self.position,
+			clock: None,
+			result: self.result,
+			draw_offer: None,
+		}
+	}
+
+	/// swap the colors of the board and every move
+	pub fn invert_colors(&self) -> Game {
+		Game {
+			board: self.board.invert_colors(),
+			moves: self.moves.iter().map(|m| m.invert_color()).collect(),
+			variations: self.variations.invert_colors(),
+			handicap: self.handicap,
+			setup: self
+				.setup
+				.as_ref()
+				.map(|stones| stones.iter().map(|s| s.invert_color()).collect()),
+			first_turn: self.first_turn.opposite(),
+			komi: self.komi,
+			ruleset: self.ruleset,
+			position: self.position,
+			clock: None,
+			result: self.result.map(|result| result.invert_color()),
+			draw_offer: None,
+		}
+	}
+
+	/// every line of the game, with the one being looked at
+	pub fn variations(&self) -> &Variations {
+		&self.variations
+	}
+
+	/// Add a line to the game's variations without looking at it. It is
+	/// played out from the start to check every move is legal.
+	pub fn add_variation(&mut self, line: &[PlacedPiece]) -> Result<()> {
+		let mut board = self.get_board_at_move(0)?;
+		let mut turn = self.first_turn;
+		for &m in line {
+			if m.piece.color != turn {
+				return Err(BadukError::InvalidMove {
+					source: InvalidMoveError::NotYourTurn { turn },
+				});
 			}
-			GamePosition::Past(current_turn) => {
-				let new_turn =
-					(current_turn as i32 + amt).clamp(1, self.len() as i32) as usize;
-				self.position = if new_turn == self.len() {
-					GamePosition::Current
-				} else {
-					GamePosition::Past(new_turn)
-				};
+			board.do_move(m)?;
+			turn = turn.opposite();
+		}
+		self.variations.add(line);
+		Ok(())
+	}
+
+	/// Remove the last move of `line` from the variations, with everything
+	/// played after it. If the game was following it, the moves are taken
+	/// back to before that move.
+	pub fn remove_variation(&mut self, line: &[PlacedPiece]) -> Result<()> {
+		if line.is_empty() || self.variations.find(line).is_none() {
+			return Err(BadukError::NoSuchVariation);
+		}
+		if self.moves.starts_with(line) {
+			self.check_playing()?;
+			let m = line[line.len() - 1];
+			self.moves.truncate(line.len() - 1);
+			if let Some(clock) = &mut self.clock {
+				clock.start(m.piece.color);
 			}
+			self.draw_offer = None;
+			self.set_turn(self.current_turn())?;
+		}
+		self.variations.remove(line);
+		Ok(())
+	}
+
+	/// Look at the position after `line`, one of the game's variations. The
+	/// moves after it follow its main line.
+	pub fn go_to_variation(&mut self, line: &[PlacedPiece]) -> Result<()> {
+		let node = self
+			.variations
+			.find(line)
+			.ok_or(BadukError::NoSuchVariation)?;
+		let mut moves = line.to_vec();
+		moves.extend(node.main_line());
+		self.moves = moves;
+		self.draw_offer = None;
+		self.set_turn(line.len())
+	}
+
+	/// the notes on the position being looked at
+	pub fn annotations(&self) -> &Annotations {
+		let turn = self.current_turn();
+		let line = &self.moves[..turn];
+		// the line being looked at is always in the tree
+		&self.variations.find(line).unwrap().annotations
+	}
+
+	pub fn annotations_mut(&mut self) -> &mut Annotations {
+		let turn = self.current_turn();
+		let line = &self.moves[..turn];
+		&mut self.variations.find_mut(line).unwrap().annotations
+	}
+
+	/// Set the notes on the position after `line`, one of the game's
+	/// variations
+	pub fn annotate(
+		&mut self,
+		line: &[PlacedPiece],
+		annotations: Annotations,
+	) -> Result<()> {
+		let node = self
+			.variations
+			.find_mut(line)
+			.ok_or(BadukError::NoSuchVariation)?;
+		node.annotations = annotations;
+		Ok(())
+	}
+
+	/// the moves that were played from the position being looked at, the
+	/// one played next in this line first
+	pub fn alternatives(&self) -> Vec<PlacedPiece> {
+		let turn = self.current_turn();
+		let mut moves = match self.variations.find(&self.moves[..turn]) {
+			Some(node) => node.next_moves(),
+			None => return vec![],
 		};
+		if let Some(next) = self.moves.get(turn) {
+			if let Some(i) = moves.iter().position(|m| m == next) {
+				let next = moves.remove(i);
+				moves.insert(0, next);
+			}
+		}
+		moves
+	}
+
+	/// Look at the game after `turn` moves, clamped to the end of the game.
+	/// The most recent turn is `GamePosition::Current`.
+	pub fn set_turn(&mut self, turn: usize) -> Result<()> {
+		if turn >= self.len() {
+			self.set_position(GamePosition::Current)
+		} else {
+			self.set_position(GamePosition::Past(turn))
+		}
+	}
+
+	/// adjust the current turn by an amount, clamped to always be valid.
+	/// If set to the most recent turn, set it to `GamePosition::Current`
+	pub fn offset_turn(&mut self, amt: i32) {
+		let turn = (self.current_turn() as i64 + amt as i64).max(0) as usize;
+		// the moves played so far always play again
+		self.set_turn(turn).unwrap();
 	}
 }
 
@@ -0,0 +1,145 @@
+use crate::{
+	error::*,
+	piece::*,
+};
+use std::{
+	fmt,
+	str::FromStr,
+};
+
+/// How a game ended, written like the SGF `RE` property
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameResult {
+	/// won by counting, `B+3.5`. The margin is `None` when it isn't known,
+	/// written as just `B+`.
+	Score {
+		winner: PieceColor,
+		margin: Option<f32>,
+	},
+	/// the loser resigned, `B+R`
+	Resignation { winner: PieceColor },
+	/// the loser ran out of time, `B+T`
+	Time { winner: PieceColor },
+	/// the loser forfeited, e.g. by leaving, `B+F`
+	Forfeit { winner: PieceColor },
+	/// jigo, or a draw both players agreed to, `0`
+	Draw,
+	/// no result, e.g. a game abandoned before it began, `Void`
+	Void,
+}
+
+impl GameResult {
+	/// the result of counting, by how many points black is ahead of white
+	pub fn score(black_ahead_by: f32) -> Self {
+		if black_ahead_by > 0.0 {
+			GameResult::Score {
+				winner: Black,
+				margin: Some(black_ahead_by),
+			}
+		} else if black_ahead_by < 0.0 {
+			GameResult::Score {
+				winner: White,
+				margin: Some(-black_ahead_by),
+			}
+		} else {
+			GameResult::Draw
+		}
+	}
+
+	pub fn winner(&self) -> Option<PieceColor> {
+		match *self {
+			GameResult::Score { winner, .. }
+			| GameResult::Resignation { winner }
+			| GameResult::Time { winner }
+			| GameResult::Forfeit { winner } => Some(winner),
+			GameResult::Draw | GameResult::Void => None,
+		}
+	}
+
+	/// the same result with black and white swapped
+	pub fn invert_color(&self) -> Self {
+		match *self {
+			GameResult::Score { winner, margin } => GameResult::Score {
+				winner: winner.opposite(),
+				margin,
+			},
+			GameResult::Resignation { winner } => GameResult::Resignation {
+				winner: winner.opposite(),
+			},
+			GameResult::Time { winner } => GameResult::Time {
+				winner: winner.opposite(),
+			},
+			GameResult::Forfeit { winner } => GameResult::Forfeit {
+				winner: winner.opposite(),
+			},
+			result => result,
+		}
+	}
+}
+
+fn color_letter(color: PieceColor) -> char {
+	match color {
+		Black => 'B',
+		White => 'W',
+	}
+}
+
+impl fmt::Display for GameResult {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match *self {
+			GameResult::Score { winner, margin } => {
+				write!(f, "{}+", color_letter(winner))?;
+				match margin {
+					Some(margin) => write!(f, "{}", margin),
+					None => Ok(()),
+				}
+			}
+			GameResult::Resignation { winner } => {
+				write!(f, "{}+R", color_letter(winner))
+			}
+			GameResult::Time { winner } => write!(f, "{}+T", color_letter(winner)),
+			GameResult::Forfeit { winner } => {
+				write!(f, "{}+F", color_letter(winner))
+			}
+			GameResult::Draw => write!(f, "0"),
+			GameResult::Void => write!(f, "Void"),
+		}
+	}
+}
+
+/// Parses an SGF `RE` value, including the long forms like `W+Resign` and
+/// `Draw`. An unknown result, `?`, is an error.
+impl FromStr for GameResult {
+	type Err = BadukError;
+
+	fn from_str(s: &str) -> Result<Self> {
+		let invalid = || BadukError::SgfParse {
+			reason: format!("invalid result `{}`", s),
+		};
+		let s = s.trim();
+		match s {
+			"0" | "Draw" | "Jigo" => return Ok(GameResult::Draw),
+			"Void" => return Ok(GameResult::Void),
+			_ => {}
+		}
+		let winner = match s.get(..2) {
+			Some("B+") => Black,
+			Some("W+") => White,
+			_ => return Err(invalid()),
+		};
+		let result = match &s[2..] {
+			"" => GameResult::Score {
+				winner,
+				margin: None,
+			},
+			"R" | "Resign" => GameResult::Resignation { winner },
+			"T" | "Time" => GameResult::Time { winner },
+			"F" | "Forfeit" => GameResult::Forfeit { winner },
+			margin => GameResult::Score {
+				winner,
+				margin: Some(margin.parse().map_err(|_| invalid())?),
+			},
+		};
+		Ok(result)
+	}
+}
@@ -0,0 +1,156 @@
+use crate::{
+	board::*,
+	piece::*,
+	pos::Pos,
+};
+use std::fmt;
+
+/// starting value of an intersection holding a stone
+const STONE_VALUE: i32 = 128;
+
+/// Bouzy's "5/10" parameters (moyo estimation): dilations first, then
+/// erosions. "5/21" only keeps settled territory, which says little mid-game.
+const DILATIONS: usize = 5;
+const EROSIONS: usize = 10;
+
+/// Influence map of a board, computed with Bouzy's dilation / erosion
+/// algorithm. Positive values belong to Black, negative values to White.
+#[derive(Clone)]
+pub struct Influence {
+	size: u8,
+	values: [[i32; 19]; 19],
+}
+
+impl Influence {
+	pub fn new(board: &Board) -> Self {
+		let mut values = [[0i32; 19]; 19];
+		for piece in board.all_pieces() {
+			values[piece.pos.x as usize][piece.pos.y as usize] =
+				match piece.piece.color {
+					Black => STONE_VALUE,
+					White => -STONE_VALUE,
+				};
+		}
+		let mut influence = Self {
+			size: board.size(),
+			values,
+		};
+		for _ in 0..DILATIONS {
+			influence.dilate();
+		}
+		for _ in 0..EROSIONS {
+			influence.erode();
+		}
+		influence
+	}
+
+	/// ownership score of an intersection, `None` off the board.
+	/// `> 0` is controlled by Black, `< 0` by White, `0` is neutral.
+	pub fn get(&self, pos: Pos) -> Option<i32> {
+		if pos.x >= self.size || pos.y >= self.size {
+			return None;
+		}
+		Some(self.values[pos.x as usize][pos.y as usize])
+	}
+
+	/// who controls an intersection, `None` if it is neutral or off the board
+	pub fn owner(&self, pos: Pos) -> Option<PieceColor> {
+		match self.get(pos)? {
+			v if v > 0 => Some(Black),
+			v if v < 0 => Some(White),
+			_ => None,
+		}
+	}
+
+	/// number of intersections (stones and territory) controlled by a color
+	pub fn area(&self, color: PieceColor) -> u16 {
+		let mut count: u16 = 0;
+		for i in 0..self.size {
+			for j in 0..self.size {
+				if self.owner(Pos { x: i, y: j }) == Some(color) {
+					count += 1;
+				}
+			}
+		}
+		count
+	}
+
+	/// estimated area score, positive if Black is ahead.
+	pub fn score(&self) -> i32 {
+		self.area(Black) as i32 - self.area(White) as i32
+	}
+
+	fn neighbors(&self, pos: (u8, u8)) -> Vec<(u8, u8)> {
+		let (x, y) = (pos.0 as i8, pos.1 as i8);
+		let max = self.size as i8 - 1;
+		[(x, y + 1), (x, y - 1), (x - 1, y), (x + 1, y)]
+			.iter()
+			.filter(|p| p.0 >= 0 && p.1 >= 0 && p.0 <= max && p.1 <= max)
+			.map(|p| (p.0 as u8, p.1 as u8))
+			.collect()
+	}
+
+	fn dilate(&mut self) {
+		let old = self.values;
+		for i in 0..self.size {
+			for j in 0..self.size {
+				let value = old[i as usize][j as usize];
+				let neighbors: Vec<i32> = self
+					.neighbors((i, j))
+					.iter()
+					.map(|p| old[p.0 as usize][p.1 as usize])
+					.collect();
+				let positive = neighbors.iter().filter(|v| **v > 0).count() as i32;
+				let negative = neighbors.iter().filter(|v| **v < 0).count() as i32;
+
+				if value >= 0 && negative == 0 {
+					self.values[i as usize][j as usize] += positive;
+				} else if value <= 0 && positive == 0 {
+					self.values[i as usize][j as usize] -= negative;
+				}
+			}
+		}
+	}
+
+	fn erode(&mut self) {
+		let old = self.values;
+		for i in 0..self.size {
+			for j in 0..self.size {
+				let value = old[i as usize][j as usize];
+				let neighbors: Vec<i32> = self
+					.neighbors((i, j))
+					.iter()
+					.map(|p| old[p.0 as usize][p.1 as usize])
+					.collect();
+
+				let cell = &mut self.values[i as usize][j as usize];
+				if value > 0 {
+					let opposed = neighbors.iter().filter(|v| **v <= 0).count() as i32;
+					*cell = (value - opposed).max(0);
+				} else if value < 0 {
+					let opposed = neighbors.iter().filter(|v| **v >= 0).count() as i32;
+					*cell = (value + opposed).min(0);
+				}
+			}
+		}
+	}
+}
+
+impl fmt::Display for Influence {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let score = self.score();
+		if score > 0 {
+			write!(f, "B+{}", score)
+		} else if score < 0 {
+			write!(f, "W+{}", -score)
+		} else {
+			write!(f, "Even")
+		}
+	}
+}
+
+impl Board {
+	pub fn influence(&self) -> Influence {
+		Influence::new(self)
+	}
+}
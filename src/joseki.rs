@@ -0,0 +1,253 @@
+use crate::{
+	board::MAX_SIZE,
+	error::*,
+	piece::*,
+	pos::Pos,
+	sgf::{
+		self,
+		SgfNode,
+	},
+	symmetry::Symmetry,
+};
+use std::{
+	fmt,
+	fs,
+	path::Path,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Corner {
+	BottomLeft,
+	BottomRight,
+	TopLeft,
+	TopRight,
+}
+
+impl Corner {
+	pub const ALL: [Corner; 4] = [
+		Corner::BottomLeft,
+		Corner::BottomRight,
+		Corner::TopLeft,
+		Corner::TopRight,
+	];
+
+	/// the corner whose quadrant contains `pos`.
+	/// Points on the center lines don't belong to any corner.
+	pub fn of(pos: Pos, size: u8) -> Option<Corner> {
+		// passes are played off the board
+		if pos.x >= size || pos.y >= size {
+			return None;
+		}
+		let half = size / 2;
+		let side = |c: u8| {
+			if c < half {
+				Some(false)
+			} else if c > size - 1 - half {
+				Some(true)
+			} else {
+				None
+			}
+		};
+		Some(match (side(pos.x)?, side(pos.y)?) {
+			(false, false) => Corner::BottomLeft,
+			(true, false) => Corner::BottomRight,
+			(false, true) => Corner::TopLeft,
+			(true, true) => Corner::TopRight,
+		})
+	}
+
+	/// the symmetry that moves this corner onto the bottom left one
+	pub fn to_bottom_left(self) -> Symmetry {
+		match self {
+			Corner::BottomLeft => Symmetry::Identity,
+			Corner::BottomRight => Symmetry::FlipHorizontal,
+			Corner::TopLeft => Symmetry::FlipVertical,
+			Corner::TopRight => Symmetry::Rotate180,
+		}
+	}
+
+	pub fn abbreviation(self) -> &'static str {
+		match self {
+			Corner::BottomLeft => "BL",
+			Corner::BottomRight => "BR",
+			Corner::TopLeft => "TL",
+			Corner::TopRight => "TR",
+		}
+	}
+}
+
+impl fmt::Display for Corner {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Corner::BottomLeft => write!(f, "Bottom left"),
+			Corner::BottomRight => write!(f, "Bottom right"),
+			Corner::TopLeft => write!(f, "Top left"),
+			Corner::TopRight => write!(f, "Top right"),
+		}
+	}
+}
+
+#[derive(Clone, Debug, Default)]
+struct JosekiNode {
+	children: Vec<(PlacedPiece, JosekiNode)>,
+}
+
+impl JosekiNode {
+	fn child_mut(&mut self, m: PlacedPiece) -> &mut JosekiNode {
+		let i = match self.children.iter().position(|(c, _)| *c == m) {
+			Some(i) => i,
+			None => {
+				self.children.push((m, JosekiNode::default()));
+				self.children.len() - 1
+			}
+		};
+		&mut self.children[i].1
+	}
+
+	fn find(&self, moves: &[PlacedPiece]) -> Option<&JosekiNode> {
+		match moves.split_first() {
+			None => Some(self),
+			Some((m, rest)) => self
+				.children
+				.iter()
+				.find(|(c, _)| c == m)
+				.and_then(|(_, node)| node.find(rest)),
+		}
+	}
+
+	fn count_leaves(&self) -> usize {
+		if self.children.is_empty() {
+			1
+		} else {
+			self.children.iter().map(|(_, n)| n.count_leaves()).sum()
+		}
+	}
+}
+
+/// A known corner sequence found in a game
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CornerJoseki {
+	pub corner: Corner,
+	/// how many moves have been played in the corner
+	pub moves: usize,
+	/// book moves that can follow, in board coordinates
+	pub continuations: Vec<PlacedPiece>,
+}
+
+/// A tree of corner sequences, stored as if they were all played in the
+/// bottom left corner.
+#[derive(Clone, Debug, Default)]
+pub struct JosekiBook {
+	root: JosekiNode,
+}
+
+impl JosekiBook {
+	/// Build a book from an SGF tree, where every variation is a joseki.
+	/// Sequences may be in any corner, moves outside of that corner (tenuki)
+	/// are skipped.
+	pub fn from_sgf(s: &str) -> Result<Self> {
+		fn add(
+			node: &SgfNode,
+			book_node: &mut JosekiNode,
+			corner: Option<Corner>,
+			size: u8,
+		) {
+			let mut corner = corner;
+			let mut book_node = book_node;
+			if let Some(m) = node.get_move(size) {
+				let move_corner = Corner::of(m.pos, size);
+				if corner.is_none() {
+					corner = move_corner;
+				}
+				if move_corner.is_some() && move_corner == corner {
+					let m = m.transform(corner.unwrap().to_bottom_left(), size);
+					book_node = book_node.child_mut(m);
+				}
+			}
+			for child in node.children.iter() {
+				add(child, book_node, corner, size);
+			}
+		}
+
+		let mut book = JosekiBook::default();
+		for tree in sgf::parse(s)? {
+			let size = tree
+				.get("SZ")
+				.and_then(|sz| sz.parse().ok())
+				.unwrap_or(MAX_SIZE);
+			add(&tree, &mut book.root, None, size);
+		}
+		Ok(book)
+	}
+
+	pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+		let s =
+			fs::read_to_string(path).map_err(|source| BadukError::Io { source })?;
+		Self::from_sgf(&s)
+	}
+
+	/// number of distinct sequences in the book
+	pub fn len(&self) -> usize {
+		if self.root.children.is_empty() {
+			0
+		} else {
+			self.root.count_leaves()
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.root.children.is_empty()
+	}
+
+	/// Find the corners of a game whose sequence of moves is in the book, in any
+	/// orientation and with either color starting.
+	pub fn lookup(&self, moves: &[PlacedPiece], size: u8) -> Vec<CornerJoseki> {
+		let mut found: Vec<CornerJoseki> = vec![];
+		for corner in Corner::ALL.iter() {
+			let to_corner = corner.to_bottom_left();
+			let corner_moves: Vec<PlacedPiece> = moves
+				.iter()
+				.filter(|m| Corner::of(m.pos, size) == Some(*corner))
+				.map(|m| m.transform(to_corner, size))
+				.collect();
+			if corner_moves.is_empty() {
+				continue;
+			}
+
+			let mut known = false;
+			let mut continuations: Vec<PlacedPiece> = vec![];
+			for mirror in [Symmetry::Identity, Symmetry::FlipDiagonal].iter() {
+				for inverted in [false, true].iter() {
+					let orient = |m: &PlacedPiece| {
+						let m = m.transform(*mirror, size);
+						if *inverted {
+							m.invert_color()
+						} else {
+							m
+						}
+					};
+					let sequence: Vec<PlacedPiece> =
+						corner_moves.iter().map(orient).collect();
+					if let Some(node) = self.root.find(&sequence) {
+						known = true;
+						for (next, _) in node.children.iter() {
+							// orient is its own inverse
+							let next = orient(next).transform(to_corner.inverse(), size);
+							if !continuations.contains(&next) {
+								continuations.push(next);
+							}
+						}
+					}
+				}
+			}
+			if known {
+				found.push(CornerJoseki {
+					corner: *corner,
+					moves: corner_moves.len(),
+					continuations,
+				});
+			}
+		}
+		found
+	}
+}
@@ -0,0 +1,50 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+#![feature(clamp)]
+
+pub mod adjacency;
+pub mod annotation;
+pub mod board;
+pub mod bot;
+pub mod clock;
+pub mod db;
+pub mod error;
+pub mod game;
+pub mod game_result;
+pub mod influence;
+pub mod joseki;
+pub mod net;
+pub mod notation;
+pub mod pattern;
+pub mod piece;
+pub mod pos;
+pub mod rules;
+pub mod server;
+pub mod sgf;
+pub mod symmetry;
+pub mod tui;
+#[cfg(feature = "ui")]
+pub mod ui;
+pub mod variations;
+pub mod zobrist;
+
+#[cfg(test)]
+mod test {
+	mod test_board;
+	mod test_bot;
+	mod test_clock;
+	mod test_db;
+	mod test_game;
+	mod test_game_result;
+	mod test_influence;
+	mod test_joseki;
+	mod test_net;
+	mod test_notation;
+	mod test_pattern;
+	mod test_rules;
+	mod test_server;
+	mod test_sgf;
+	mod test_symmetry;
+	mod test_tui;
+	mod test_variations;
+}
@@ -0,0 +1,1139 @@
+use crate::{
+	board::MoveResult,
+	error::*,
+	game::*,
+	game_result::GameResult,
+	piece::*,
+	zobrist,
+};
+use std::{
+	io::{
+		ErrorKind,
+		Read,
+		Write,
+	},
+	net::{
+		SocketAddr,
+		TcpListener,
+		TcpStream,
+		ToSocketAddrs,
+	},
+	sync::atomic::{
+		AtomicU64,
+		Ordering,
+	},
+	time::{
+		Duration,
+		Instant,
+		SystemTime,
+		UNIX_EPOCH,
+	},
+};
+
+/// bumped whenever the meaning of a message changes
+pub const PROTOCOL_VERSION: u16 = 9;
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// how long the handshake may take before giving up on the other side
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// frames larger than this are rejected instead of buffered
+const MAX_FRAME: usize = 1 << 20;
+
+const TAG_HELLO: u8 = 1;
+const TAG_WELCOME: u8 = 2;
+const TAG_REFUSED: u8 = 3;
+const TAG_MOVE: u8 = 4;
+const TAG_DESYNC: u8 = 5;
+const TAG_CREATE: u8 = 6;
+const TAG_JOIN: u8 = 7;
+const TAG_SPECTATE: u8 = 8;
+const TAG_LIST: u8 = 9;
+const TAG_GAMES: u8 = 10;
+const TAG_JOINED: u8 = 11;
+const TAG_LEAVE: u8 = 12;
+const TAG_GAME_OVER: u8 = 13;
+const TAG_RESUME: u8 = 14;
+const TAG_RESUMED: u8 = 15;
+const TAG_RESIGN: u8 = 16;
+const TAG_OFFER_DRAW: u8 = 17;
+const TAG_UNDO_REQUEST: u8 = 18;
+const TAG_UNDO_REPLY: u8 = 19;
+
+/// A game hosted by a server, as listed to clients
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameSummary {
+	pub id: u32,
+	/// how many of the two seats are taken
+	pub players: u8,
+	pub moves: u32,
+}
+
+/// Everything the two sides of a game, or a server and its clients, send each
+/// other. On the wire every message is a little endian `u32` length, a tag
+/// byte, then its fields.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+	/// sent by the joining side as soon as it connects. A server answers with
+	/// its own `Hello`, a host waits for `Join` or `Resume`.
+	Hello { version: u16 },
+	/// the host's answer to `Join`: the game so far, the color the guest plays
+	/// and the token to resume the session with. `setup` is the position the
+	/// game was set up from, if it didn't start from the handicap stones.
+	Welcome {
+		version: u16,
+		size: u8,
+		handicap: u8,
+		komi: f32,
+		setup: Option<Vec<PlacedPiece>>,
+		first_turn: PieceColor,
+		color: PieceColor,
+		moves: Vec<PlacedPiece>,
+		token: u64,
+	},
+	/// the host won't play, e.g. because of a version mismatch, or a server
+	/// turned down a request
+	Refused { reason: String },
+	/// move `number` (starting at 1), and the position hash after it
+	Move {
+		number: u32,
+		m: PlacedPiece,
+		hash: u64,
+	},
+	/// the sender's position after move `number` doesn't match ours
+	Desync { number: u32, hash: u64 },
+	/// ask a server for a new game, the creator plays black
+	Create { size: u8, handicap: u8, komi: f32 },
+	/// take the free seat of a server game, or of a hosted game (where `game`
+	/// is ignored)
+	Join { game: u32 },
+	/// watch a server game
+	Spectate { game: u32 },
+	/// ask a server for its games
+	List,
+	/// a server's games
+	Games { games: Vec<GameSummary> },
+	/// a server's answer to `Create`, `Join` or `Spectate`, with the game so
+	/// far, started as in `Welcome`. `color` is `None` for spectators, who get
+	/// no token.
+	Joined {
+		game: u32,
+		color: Option<PieceColor>,
+		size: u8,
+		handicap: u8,
+		komi: f32,
+		setup: Option<Vec<PlacedPiece>>,
+		first_turn: PieceColor,
+		moves: Vec<PlacedPiece>,
+		token: u64,
+	},
+	/// stop playing or watching a server game
+	Leave,
+	/// a server game ended, with its SGF result, e.g. `B+F`
+	GameOver { result: String },
+	/// take a seat back after the connection dropped. `acked` is the number
+	/// of moves already received, as in `Game::get_board_at_move`.
+	Resume { token: u64, acked: u32 },
+	/// the answer to `Resume`: the moves after the first `from`, and the
+	/// position hash once they are played
+	Resumed {
+		game: u32,
+		color: PieceColor,
+		from: u32,
+		moves: Vec<PlacedPiece>,
+		hash: u64,
+	},
+	/// the sender resigns the game
+	Resign,
+	/// the sender offers a draw, or accepts one offered since the last move
+	OfferDraw,
+	/// ask to take back move `number`, the last one played
+	UndoRequest { number: u32 },
+	/// the answer to `UndoRequest`. A server also sends an accepted undo to
+	/// everyone in the game.
+	UndoReply { number: u32, accepted: bool },
+}
+
+fn color_byte(color: PieceColor) -> u8 {
+	match color {
+		Black => 1,
+		White => 2,
+	}
+}
+
+fn write_move(buf: &mut Vec<u8>, m: &PlacedPiece) {
+	buf.extend_from_slice(&[color_byte(m.piece.color), m.pos.x, m.pos.y]);
+}
+
+fn write_moves(buf: &mut Vec<u8>, moves: &[PlacedPiece]) {
+	buf.extend_from_slice(&(moves.len() as u32).to_le_bytes());
+	for m in moves.iter() {
+		write_move(buf, m);
+	}
+}
+
+/// a byte for whether the game was set up, then the stones if it was
+fn write_setup(buf: &mut Vec<u8>, setup: &Option<Vec<PlacedPiece>>) {
+	match setup {
+		Some(stones) => {
+			buf.push(1);
+			write_moves(buf, stones);
+		}
+		None => buf.push(0),
+	}
+}
+
+impl Message {
+	pub fn encode(&self) -> Vec<u8> {
+		let mut body: Vec<u8> = vec![];
+		match self {
+			Message::Hello { version } => {
+				body.push(TAG_HELLO);
+				body.extend_from_slice(&version.to_le_bytes());
+			}
+			Message::Welcome {
+				version,
+				size,
+				handicap,
+				komi,
+				setup,
+				first_turn,
+				color,
+				moves,
+				token,
+			} => {
+				body.push(TAG_WELCOME);
+				body.extend_from_slice(&version.to_le_bytes());
+				body.push(*size);
+				body.push(*handicap);
+				body.extend_from_slice(&komi.to_le_bytes());
+				write_setup(&mut body, setup);
+				body.push(color_byte(*first_turn));
+				body.push(color_byte(*color));
+				write_moves(&mut body, moves);
+				body.extend_from_slice(&token.to_le_bytes());
+			}
+			Message::Refused { reason } => {
+				body.push(TAG_REFUSED);
+				body.extend_from_slice(reason.as_bytes());
+			}
+			Message::Move { number, m, hash } => {
+				body.push(TAG_MOVE);
+				body.extend_from_slice(&number.to_le_bytes());
+				write_move(&mut body, m);
+				body.extend_from_slice(&hash.to_le_bytes());
+			}
+			Message::Desync { number, hash } => {
+				body.push(TAG_DESYNC);
+				body.extend_from_slice(&number.to_le_bytes());
+				body.extend_from_slice(&hash.to_le_bytes());
+			}
+			Message::Create {
+				size,
+				handicap,
+				komi,
+			} => {
+				body.push(TAG_CREATE);
+				body.push(*size);
+				body.push(*handicap);
+				body.extend_from_slice(&komi.to_le_bytes());
+			}
+			Message::Join { game } => {
+				body.push(TAG_JOIN);
+				body.extend_from_slice(&game.to_le_bytes());
+			}
+			Message::Spectate { game } => {
+				body.push(TAG_SPECTATE);
+				body.extend_from_slice(&game.to_le_bytes());
+			}
+			Message::List => body.push(TAG_LIST),
+			Message::Games { games } => {
+				body.push(TAG_GAMES);
+				body.extend_from_slice(&(games.len() as u32).to_le_bytes());
+				for game in games.iter() {
+					body.extend_from_slice(&game.id.to_le_bytes());
+					body.push(game.players);
+					body.extend_from_slice(&game.moves.to_le_bytes());
+				}
+			}
+			Message::Joined {
+				game,
+				color,
+				size,
+				handicap,
+				komi,
+				setup,
+				first_turn,
+				moves,
+				token,
+			} => {
+				body.push(TAG_JOINED);
+				body.extend_from_slice(&game.to_le_bytes());
+				body.push(color.map_or(0, color_byte));
+				body.push(*size);
+				body.push(*handicap);
+				body.extend_from_slice(&komi.to_le_bytes());
+				write_setup(&mut body, setup);
+				body.push(color_byte(*first_turn));
+				write_moves(&mut body, moves);
+				body.extend_from_slice(&token.to_le_bytes());
+			}
+			Message::Leave => body.push(TAG_LEAVE),
+			Message::GameOver { result } => {
+				body.push(TAG_GAME_OVER);
+				body.extend_from_slice(result.as_bytes());
+			}
+			Message::Resume { token, acked } => {
+				body.push(TAG_RESUME);
+				body.extend_from_slice(&token.to_le_bytes());
+				body.extend_from_slice(&acked.to_le_bytes());
+			}
+			Message::Resumed {
+				game,
+				color,
+				from,
+				moves,
+				hash,
+			} => {
+				body.push(TAG_RESUMED);
+				body.extend_from_slice(&game.to_le_bytes());
+				body.push(color_byte(*color));
+				body.extend_from_slice(&from.to_le_bytes());
+				write_moves(&mut body, moves);
+				body.extend_from_slice(&hash.to_le_bytes());
+			}
+			Message::Resign => body.push(TAG_RESIGN),
+			Message::OfferDraw => body.push(TAG_OFFER_DRAW),
+			Message::UndoRequest { number } => {
+				body.push(TAG_UNDO_REQUEST);
+				body.extend_from_slice(&number.to_le_bytes());
+			}
+			Message::UndoReply { number, accepted } => {
+				body.push(TAG_UNDO_REPLY);
+				body.extend_from_slice(&number.to_le_bytes());
+				body.push(*accepted as u8);
+			}
+		}
+		let mut frame = (body.len() as u32).to_le_bytes().to_vec();
+		frame.extend(body);
+		frame
+	}
+
+	/// decode the body of a frame, without its length prefix
+	pub fn decode(body: &[u8]) -> Result<Self> {
+		let mut r = FrameReader { body, i: 0 };
+		let message = match r.u8()? {
+			TAG_HELLO => Message::Hello { version: r.u16()? },
+			TAG_WELCOME => {
+				let version = r.u16()?;
+				let size = r.u8()?;
+				let handicap = r.u8()?;
+				let komi = r.f32()?;
+				let setup = r.setup()?;
+				let first_turn = r.color()?;
+				let color = r.color()?;
+				Message::Welcome {
+					version,
+					size,
+					handicap,
+					komi,
+					setup,
+					first_turn,
+					color,
+					moves: r.moves()?,
+					token: r.u64()?,
+				}
+			}
+			TAG_REFUSED => Message::Refused { reason: r.text()? },
+			TAG_MOVE => Message::Move {
+				number: r.u32()?,
+				m: r.placed_piece()?,
+				hash: r.u64()?,
+			},
+			TAG_DESYNC => Message::Desync {
+				number: r.u32()?,
+				hash: r.u64()?,
+			},
+			TAG_CREATE => Message::Create {
+				size: r.u8()?,
+				handicap: r.u8()?,
+				komi: r.f32()?,
+			},
+			TAG_JOIN => Message::Join { game: r.u32()? },
+			TAG_SPECTATE => Message::Spectate { game: r.u32()? },
+			TAG_LIST => Message::List,
+			TAG_GAMES => {
+				let mut games: Vec<GameSummary> = vec![];
+				for _ in 0..r.u32()? {
+					games.push(GameSummary {
+						id: r.u32()?,
+						players: r.u8()?,
+						moves: r.u32()?,
+					});
+				}
+				Message::Games { games }
+			}
+			TAG_JOINED => {
+				let game = r.u32()?;
+				Message::Joined {
+					game,
+					color: r.optional_color()?,
+					size: r.u8()?,
+					handicap: r.u8()?,
+					komi: r.f32()?,
+					setup: r.setup()?,
+					first_turn: r.color()?,
+					moves: r.moves()?,
+					token: r.u64()?,
+				}
+			}
+			TAG_LEAVE => Message::Leave,
+			TAG_GAME_OVER => Message::GameOver { result: r.text()? },
+			TAG_RESUME => Message::Resume {
+				token: r.u64()?,
+				acked: r.u32()?,
+			},
+			TAG_RESUMED => Message::Resumed {
+				game: r.u32()?,
+				color: r.color()?,
+				from: r.u32()?,
+				moves: r.moves()?,
+				hash: r.u64()?,
+			},
+			TAG_RESIGN => Message::Resign,
+			TAG_OFFER_DRAW => Message::OfferDraw,
+			TAG_UNDO_REQUEST => Message::UndoRequest { number: r.u32()? },
+			TAG_UNDO_REPLY => Message::UndoReply {
+				number: r.u32()?,
+				accepted: r.u8()? != 0,
+			},
+			tag => return Err(protocol_error(&format!("unknown message {}", tag))),
+		};
+		if r.i != body.len() {
+			return Err(protocol_error("trailing bytes after message"));
+		}
+		Ok(message)
+	}
+}
+
+pub(crate) fn protocol_error(reason: &str) -> BadukError {
+	BadukError::Protocol {
+		reason: reason.to_string(),
+	}
+}
+
+struct FrameReader<'a> {
+	body: &'a [u8],
+	i: usize,
+}
+
+impl<'a> FrameReader<'a> {
+	fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+		if self.i + n > self.body.len() {
+			return Err(protocol_error("message is too short"));
+		}
+		let bytes = &self.body[self.i..self.i + n];
+		self.i += n;
+		Ok(bytes)
+	}
+
+	fn rest(&mut self) -> &'a [u8] {
+		let bytes = &self.body[self.i..];
+		self.i = self.body.len();
+		bytes
+	}
+
+	fn u8(&mut self) -> Result<u8> {
+		Ok(self.take(1)?[0])
+	}
+
+	fn u16(&mut self) -> Result<u16> {
+		let mut buf = [0u8; 2];
+		buf.copy_from_slice(self.take(2)?);
+		Ok(u16::from_le_bytes(buf))
+	}
+
+	fn u32(&mut self) -> Result<u32> {
+		let mut buf = [0u8; 4];
+		buf.copy_from_slice(self.take(4)?);
+		Ok(u32::from_le_bytes(buf))
+	}
+
+	fn u64(&mut self) -> Result<u64> {
+		let mut buf = [0u8; 8];
+		buf.copy_from_slice(self.take(8)?);
+		Ok(u64::from_le_bytes(buf))
+	}
+
+	fn f32(&mut self) -> Result<f32> {
+		Ok(f32::from_bits(self.u32()?))
+	}
+
+	fn optional_color(&mut self) -> Result<Option<PieceColor>> {
+		match self.u8()? {
+			0 => Ok(None),
+			1 => Ok(Some(Black)),
+			2 => Ok(Some(White)),
+			_ => Err(protocol_error("invalid color")),
+		}
+	}
+
+	fn color(&mut self) -> Result<PieceColor> {
+		self
+			.optional_color()?
+			.ok_or_else(|| protocol_error("invalid color"))
+	}
+
+	fn placed_piece(&mut self) -> Result<PlacedPiece> {
+		let color = self.color()?;
+		PlacedPiece::from_coords(color, self.u8()?, self.u8()?)
+	}
+
+	fn moves(&mut self) -> Result<Vec<PlacedPiece>> {
+		let mut moves: Vec<PlacedPiece> = vec![];
+		for _ in 0..self.u32()? {
+			moves.push(self.placed_piece()?);
+		}
+		Ok(moves)
+	}
+
+	fn setup(&mut self) -> Result<Option<Vec<PlacedPiece>>> {
+		match self.u8()? {
+			0 => Ok(None),
+			1 => Ok(Some(self.moves()?)),
+			_ => Err(protocol_error("invalid setup")),
+		}
+	}
+
+	/// the rest of the message as text
+	fn text(&mut self) -> Result<String> {
+		String::from_utf8(self.rest().to_vec())
+			.map_err(|_| protocol_error("invalid text"))
+	}
+}
+
+/// the other side going away is `Disconnected`, however it happened
+fn disconnected(err: std::io::Error) -> BadukError {
+	match err.kind() {
+		ErrorKind::ConnectionReset
+		| ErrorKind::ConnectionAborted
+		| ErrorKind::BrokenPipe
+		| ErrorKind::UnexpectedEof => BadukError::Disconnected,
+		_ => err.into(),
+	}
+}
+
+/// A TCP stream that sends and receives whole messages
+pub struct Connection {
+	stream: TcpStream,
+	buf: Vec<u8>,
+}
+
+impl Connection {
+	pub fn new(stream: TcpStream) -> Result<Self> {
+		stream.set_nodelay(true)?;
+		Ok(Self {
+			stream,
+			buf: vec![],
+		})
+	}
+
+	pub fn peer_addr(&self) -> Result<SocketAddr> {
+		Ok(self.stream.peer_addr()?)
+	}
+
+	pub fn send(&mut self, message: &Message) -> Result<()> {
+		self
+			.stream
+			.write_all(&message.encode())
+			.map_err(disconnected)
+	}
+
+	/// the next message, if a whole one has arrived. Never blocks once the
+	/// connection is non-blocking.
+	pub fn poll(&mut self) -> Result<Option<Message>> {
+		loop {
+			if let Some(message) = self.take_frame()? {
+				return Ok(Some(message));
+			}
+			if !self.fill()? {
+				return Ok(None);
+			}
+		}
+	}
+
+	/// wait for the next message, up to the stream's read timeout
+	pub fn recv(&mut self) -> Result<Message> {
+		match self.poll()? {
+			Some(message) => Ok(message),
+			None => Err(protocol_error("timed out waiting for a message")),
+		}
+	}
+
+	/// `None` makes the connection non-blocking, otherwise reads wait up to
+	/// `timeout`
+	pub fn set_blocking(&self, timeout: Option<Duration>) -> Result<()> {
+		self.stream.set_nonblocking(timeout.is_none())?;
+		self.stream.set_read_timeout(timeout)?;
+		Ok(())
+	}
+
+	/// read whatever is available, returns false if nothing was
+	fn fill(&mut self) -> Result<bool> {
+		let mut chunk = [0u8; 4096];
+		match self.stream.read(&mut chunk) {
+			Ok(0) => Err(BadukError::Disconnected),
+			Ok(n) => {
+				self.buf.extend_from_slice(&chunk[..n]);
+				Ok(true)
+			}
+			Err(err)
+				if err.kind() == ErrorKind::WouldBlock
+					|| err.kind() == ErrorKind::TimedOut =>
+			{
+				Ok(false)
+			}
+			Err(err) if err.kind() == ErrorKind::Interrupted => Ok(true),
+			Err(err) => Err(disconnected(err)),
+		}
+	}
+
+	fn take_frame(&mut self) -> Result<Option<Message>> {
+		if self.buf.len() < 4 {
+			return Ok(None);
+		}
+		let mut len = [0u8; 4];
+		len.copy_from_slice(&self.buf[..4]);
+		let len = u32::from_le_bytes(len) as usize;
+		if len > MAX_FRAME {
+			return Err(protocol_error("message is too long"));
+		}
+		if self.buf.len() < 4 + len {
+			return Ok(None);
+		}
+		let message = Message::decode(&self.buf[4..4 + len]);
+		self.buf.drain(..4 + len);
+		message.map(Some)
+	}
+}
+
+/// Something that happened to a networked game
+#[derive(Clone, Debug, PartialEq)]
+pub enum NetEvent {
+	/// the other player moved, the move has been played on our side
+	Move(PlacedPiece),
+	/// the two sides disagree about the position after move `number`
+	Desync {
+		number: u32,
+		local: u64,
+		remote: u64,
+	},
+	/// the other player offered a draw
+	DrawOffered,
+	/// the game ended, by resignation or an agreed draw
+	Ended(GameResult),
+	/// the other player asks to take back the last move
+	UndoRequested,
+	/// a move was taken back
+	Undone(PlacedPiece),
+	/// the other player turned down our undo request
+	UndoDeclined,
+}
+
+/// hash of the latest position, even while looking at an older one
+pub fn position_hash(game: &Game) -> Result<u64> {
+	Ok(match game.position() {
+		GamePosition::Current => game.board.hash(),
+		GamePosition::Past(_) => game.get_board_at_move(game.len())?.hash(),
+	})
+}
+
+/// a new, unguessable enough session token
+pub fn new_token() -> u64 {
+	static COUNTER: AtomicU64 = AtomicU64::new(0);
+	let nanos = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|d| d.as_nanos() as u64)
+		.unwrap_or(0);
+	zobrist::mix(nanos ^ COUNTER.fetch_add(1, Ordering::Relaxed).rotate_left(32))
+}
+
+/// The answer to `Resume` from a side that has been following `game`, for
+/// the other side that has received the first `acked` moves of it.
+pub fn resumed(
+	game_id: u32,
+	game: &Game,
+	color: PieceColor,
+	acked: u32,
+) -> Result<Message> {
+	if acked as usize > game.len() {
+		return Err(protocol_error(&format!(
+			"resuming after move {}, but only {} were played",
+			acked,
+			game.len()
+		)));
+	}
+	Ok(Message::Resumed {
+		game: game_id,
+		color,
+		from: acked,
+		moves: game.moves[acked as usize..].to_vec(),
+		hash: position_hash(game)?,
+	})
+}
+
+/// Play the moves from a `Resumed` answer, then check the position hash
+pub(crate) fn replay(
+	game: &mut Game,
+	from: u32,
+	moves: &[PlacedPiece],
+	hash: u64,
+) -> Result<Vec<NetEvent>> {
+	if from as usize != game.len() {
+		return Err(protocol_error(&format!(
+			"resumed after move {}, but {} were received",
+			from,
+			game.len()
+		)));
+	}
+	let mut events: Vec<NetEvent> = vec![];
+	for m in moves.iter() {
+		game.do_move(*m)?;
+		events.push(NetEvent::Move(*m));
+	}
+	let local = position_hash(game)?;
+	if local != hash {
+		events.push(NetEvent::Desync {
+			number: game.len() as u32,
+			local,
+			remote: hash,
+		});
+	}
+	Ok(events)
+}
+
+/// Connect and send `Hello`
+pub(crate) fn handshake<A: ToSocketAddrs>(addr: A) -> Result<Connection> {
+	let mut conn = Connection::new(TcpStream::connect(addr)?)?;
+	conn.set_blocking(Some(HANDSHAKE_TIMEOUT))?;
+	conn.send(&Message::Hello {
+		version: PROTOCOL_VERSION,
+	})?;
+	Ok(conn)
+}
+
+/// someone connecting to a host, partway through the handshake
+struct Handshake {
+	conn: Connection,
+	started: Instant,
+	/// whether their `Hello` has arrived
+	greeted: bool,
+}
+
+/// A port waiting for someone to join, or for the guest to come back
+pub struct Host {
+	listener: TcpListener,
+	/// the guest's session, once someone joined
+	token: Option<u64>,
+	handshake: Option<Handshake>,
+}
+
+impl Host {
+	pub fn listen<A: ToSocketAddrs>(addr: A) -> Result<Self> {
+		let listener = TcpListener::bind(addr)?;
+		listener.set_nonblocking(true)?;
+		Ok(Self {
+			listener,
+			token: None,
+			handshake: None,
+		})
+	}
+
+	pub fn local_addr(&self) -> Result<SocketAddr> {
+		Ok(self.listener.local_addr()?)
+	}
+
+	/// has someone joined, so that only they can resume the game
+	pub fn has_guest(&self) -> bool {
+		self.token.is_some()
+	}
+
+	/// Accept a player if one is connecting, and send them the game so far.
+	/// The host plays `color`, the guest the other one. Once someone joined,
+	/// only they can connect again, by resuming their session. Never blocks:
+	/// the handshake is read over as many calls as it takes, and given up
+	/// after `HANDSHAKE_TIMEOUT`.
+	pub fn accept(
+		&mut self,
+		game: &Game,
+		color: PieceColor,
+	) -> Result<Option<NetSession>> {
+		// a handshake that fails is dropped, to wait for the next one
+		let mut handshake = match self.handshake.take() {
+			Some(handshake) => handshake,
+			None => match self.listener.accept() {
+				Ok((stream, _)) => {
+					let conn = Connection::new(stream)?;
+					conn.set_blocking(None)?;
+					Handshake {
+						conn,
+						started: Instant::now(),
+						greeted: false,
+					}
+				}
+				Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(None),
+				Err(err) => return Err(err.into()),
+			},
+		};
+		while let Some(message) = handshake.conn.poll()? {
+			if handshake.greeted {
+				let conn = handshake.conn;
+				return self.welcome(conn, message, game, color).map(Some);
+			}
+			greet(&mut handshake.conn, message)?;
+			handshake.greeted = true;
+		}
+		if handshake.started.elapsed() > HANDSHAKE_TIMEOUT {
+			return Err(protocol_error("timed out waiting for a message"));
+		}
+		self.handshake = Some(handshake);
+		Ok(None)
+	}
+
+	/// answer the `Join` or `Resume` that follows a guest's `Hello`
+	fn welcome(
+		&mut self,
+		mut conn: Connection,
+		message: Message,
+		game: &Game,
+		color: PieceColor,
+	) -> Result<NetSession> {
+		let token = match (message, self.token) {
+			(Message::Join { .. }, None) => {
+				let token = new_token();
+				conn.send(&Message::Welcome {
+					version: PROTOCOL_VERSION,
+					size: game.board.size(),
+					handicap: game.handicap,
+					komi: game.komi,
+					setup: game.setup().map(|stones| stones.to_vec()),
+					first_turn: game.first_turn(),
+					color: color.opposite(),
+					moves: game.moves.clone(),
+					token,
+				})?;
+				token
+			}
+			(Message::Resume { token, acked }, Some(expected))
+				if token == expected =>
+			{
+				conn.send(&resumed(0, game, color.opposite(), acked)?)?;
+				token
+			}
+			(message, _) => {
+				let reason = match message {
+					Message::Join { .. } => "the game already has a guest",
+					Message::Resume { .. } => "unknown session",
+					_ => "expected Join or Resume",
+				};
+				conn.send(&Message::Refused {
+					reason: reason.to_string(),
+				})?;
+				return Err(protocol_error(reason));
+			}
+		};
+		self.token = Some(token);
+		Ok(NetSession {
+			addr: conn.peer_addr()?,
+			conn,
+			color,
+			token,
+			undo_sent: None,
+			undo_received: None,
+		})
+	}
+}
+
+/// check the `Hello` a guest starts with, refusing other protocol versions
+fn greet(conn: &mut Connection, message: Message) -> Result<()> {
+	match message {
+		Message::Hello { version } if version == PROTOCOL_VERSION => Ok(()),
+		Message::Hello { version } => {
+			conn.send(&Message::Refused {
+				reason: format!(
+					"protocol version {} is not supported, the host uses {}",
+					version, PROTOCOL_VERSION
+				),
+			})?;
+			Err(protocol_error("guest uses another protocol version"))
+		}
+		_ => Err(protocol_error("expected Hello")),
+	}
+}
+
+/// One side of a game played over the network
+pub struct NetSession {
+	conn: Connection,
+	/// where the other side is
+	addr: SocketAddr,
+	/// the color played on this side
+	pub color: PieceColor,
+	/// identifies the guest when resuming
+	pub token: u64,
+	/// the move we asked to undo, while waiting for the answer
+	undo_sent: Option<u32>,
+	/// the move the other side asked to undo
+	undo_received: Option<u32>,
+}
+
+impl NetSession {
+	/// Connect to a host, returning the session and the game it is playing
+	pub fn join<A: ToSocketAddrs>(addr: A) -> Result<(Self, Game)> {
+		let mut conn = handshake(addr)?;
+		conn.send(&Message::Join { game: 0 })?;
+		let (mut game, color, moves, token) = match conn.recv()? {
+			Message::Welcome {
+				version,
+				size,
+				handicap,
+				komi,
+				setup,
+				first_turn,
+				color,
+				moves,
+				token,
+			} if version == PROTOCOL_VERSION => {
+				let mut game = Game::with_start(size, handicap, setup, first_turn)?;
+				game.komi = komi;
+				(game, color, moves, token)
+			}
+			Message::Welcome { .. } => {
+				return Err(protocol_error("host uses another protocol version"));
+			}
+			Message::Refused { reason } => {
+				return Err(BadukError::Protocol { reason })
+			}
+			_ => return Err(protocol_error("expected Welcome")),
+		};
+		for m in moves {
+			game.do_move(m)?;
+		}
+		conn.set_blocking(None)?;
+		let session = Self {
+			addr: conn.peer_addr()?,
+			conn,
+			color,
+			token,
+			undo_sent: None,
+			undo_received: None,
+		};
+		Ok((session, game))
+	}
+
+	/// Connect to a host again after the connection dropped, catching `game`
+	/// up with the moves it missed. The events are those moves, and a desync
+	/// if the positions don't match afterwards.
+	pub fn resume<A: ToSocketAddrs>(
+		addr: A,
+		token: u64,
+		game: &mut Game,
+	) -> Result<(Self, Vec<NetEvent>)> {
+		let mut conn = handshake(addr)?;
+		conn.send(&Message::Resume {
+			token,
+			acked: game.len() as u32,
+		})?;
+		let (color, events) = match conn.recv()? {
+			Message::Resumed {
+				color,
+				from,
+				moves,
+				hash,
+				..
+			} => (color, replay(game, from, &moves, hash)?),
+			Message::Refused { reason } => {
+				return Err(BadukError::Protocol { reason })
+			}
+			_ => return Err(protocol_error("expected Resumed")),
+		};
+		conn.set_blocking(None)?;
+		let mut session = Self {
+			addr: conn.peer_addr()?,
+			conn,
+			color,
+			token,
+			undo_sent: None,
+			undo_received: None,
+		};
+		for event in events.iter() {
+			if let NetEvent::Desync { number, local, .. } = event {
+				session.conn.send(&Message::Desync {
+					number: *number,
+					hash: *local,
+				})?;
+			}
+		}
+		Ok((session, events))
+	}
+
+	pub fn peer_addr(&self) -> SocketAddr {
+		self.addr
+	}
+	/// Play a move on this side, then send it. Fails without sending anything
+	/// if the move isn't valid, or it isn't this side's turn.
+	pub fn play(
+		&mut self,
+		game: &mut Game,
+		m: PlacedPiece,
+	) -> Result<MoveResult> {
+		if m.piece.color != self.color {
+			return Err(BadukError::InvalidMove {
+				source: InvalidMoveError::NotYourTurn {
+					turn: game.current_turn_color(),
+				},
+			});
+		}
+		let res = game.do_move(m)?;
+		// a move turns down any undo still asked for
+		self.undo_sent = None;
+		self.undo_received = None;
+		self.conn.send(&Message::Move {
+			number: game.len() as u32,
+			m,
+			hash: position_hash(game)?,
+		})?;
+		Ok(res)
+	}
+
+	/// Ask the other side to take back the last move. It is taken back once
+	/// they accept, as `NetEvent::Undone`.
+	pub fn request_undo(&mut self, game: &Game) -> Result<()> {
+		if game.is_empty() {
+			return Err(BadukError::NothingToUndo);
+		}
+		let number = game.len() as u32;
+		self.conn.send(&Message::UndoRequest { number })?;
+		self.undo_sent = Some(number);
+		Ok(())
+	}
+
+	/// is the other side asking to take back the last move
+	pub fn undo_requested(&self) -> bool {
+		self.undo_received.is_some()
+	}
+
+	/// Answer the other side's undo request, taking the move back if
+	/// `accept`ed. Returns the move taken back. An undo that fails is
+	/// declined, so the other side isn't left waiting.
+	pub fn reply_undo(
+		&mut self,
+		game: &mut Game,
+		accept: bool,
+	) -> Result<Option<PlacedPiece>> {
+		let number = self
+			.undo_received
+			.take()
+			.ok_or_else(|| protocol_error("no undo was requested"))?;
+		let undone = if accept { game.undo().map(Some) } else { Ok(None) };
+		self.conn.send(&Message::UndoReply {
+			number,
+			accepted: matches!(undone, Ok(Some(_))),
+		})?;
+		undone
+	}
+
+	/// resign on this side, and tell the other side
+	pub fn resign(&mut self, game: &mut Game) -> Result<GameResult> {
+		let result = game.resign(self.color)?;
+		self.conn.send(&Message::Resign)?;
+		Ok(result)
+	}
+
+	/// Offer a draw, or accept the one the other side offered. The result is
+	/// the draw, once both sides agreed.
+	pub fn offer_draw(&mut self, game: &mut Game) -> Result<Option<GameResult>> {
+		let result = game.offer_draw(self.color)?;
+		self.conn.send(&Message::OfferDraw)?;
+		Ok(result)
+	}
+
+	/// Handle everything the other side sent since the last update. Their
+	/// moves are validated and played on `game`, and their position hash is
+	/// checked against ours.
+	pub fn update(&mut self, game: &mut Game) -> Result<Vec<NetEvent>> {
+		let mut events: Vec<NetEvent> = vec![];
+		while let Some(message) = self.conn.poll()? {
+			match message {
+				Message::Move { number, m, hash } => {
+					if m.piece.color == self.color {
+						return Err(protocol_error("other side played our color"));
+					}
+					let in_order = number as usize == game.len() + 1;
+					if in_order && game.do_move(m).is_ok() {
+						self.undo_sent = None;
+						self.undo_received = None;
+						events.push(NetEvent::Move(m));
+					}
+					let local = position_hash(game)?;
+					if !in_order || local != hash {
+						self.conn.send(&Message::Desync {
+							number,
+							hash: local,
+						})?;
+						events.push(NetEvent::Desync {
+							number,
+							local,
+							remote: hash,
+						});
+					}
+				}
+				Message::Desync { number, hash } => {
+					events.push(NetEvent::Desync {
+						number,
+						local: position_hash(game)?,
+						remote: hash,
+					});
+				}
+				// the game may already be over on this side, e.g. on time
+				Message::Resign => {
+					if let Ok(result) = game.resign(self.color.opposite()) {
+						events.push(NetEvent::Ended(result));
+					}
+				}
+				Message::OfferDraw => match game.offer_draw(self.color.opposite()) {
+					Ok(Some(result)) => events.push(NetEvent::Ended(result)),
+					Ok(None) => events.push(NetEvent::DrawOffered),
+					Err(_) => {}
+				},
+				// only the last move can be taken back, anything older was
+				// asked before a move crossed the request
+				Message::UndoRequest { number } => {
+					if number as usize == game.len() && game.result().is_none() {
+						self.undo_received = Some(number);
+						events.push(NetEvent::UndoRequested);
+					} else {
+						self.conn.send(&Message::UndoReply {
+							number,
+							accepted: false,
+						})?;
+					}
+				}
+				Message::UndoReply { number, accepted } => {
+					if self.undo_sent.take() != Some(number) {
+						continue;
+					}
+					let undone = if accepted && number as usize == game.len() {
+						game.undo().ok()
+					} else {
+						None
+					};
+					events.push(match undone {
+						Some(m) => NetEvent::Undone(m),
+						None => NetEvent::UndoDeclined,
+					});
+				}
+				_ => return Err(protocol_error("unexpected message during a game")),
+			}
+		}
+		Ok(events)
+	}
+}
@@ -0,0 +1,127 @@
+use crate::{
+	board::MAX_SIZE,
+	error::*,
+	piece::{
+		PlacedPiece,
+		PASS_POS,
+	},
+	pos::Pos,
+	sgf,
+};
+use std::{
+	fmt,
+	str::FromStr,
+};
+
+/// Column letters of Go coordinates. `I` is left out, so it can't be taken
+/// for `J` or `1`.
+const COLUMNS: &str = "ABCDEFGHJKLMNOPQRST";
+
+/// the letter of column `x`, counted from the left, or `?` past the last one
+pub fn column_letter(x: u8) -> char {
+	COLUMNS.as_bytes().get(x as usize).map_or('?', |&c| c as char)
+}
+
+fn invalid(text: &str) -> BadukError {
+	BadukError::InvalidCoordinate {
+		text: text.to_string(),
+	}
+}
+
+fn check_size(pos: Pos, size: u8, text: &str) -> Result<Pos> {
+	if pos.x < size && pos.y < size {
+		Ok(pos)
+	} else {
+		Err(invalid(text))
+	}
+}
+
+/// Go coordinates, like `D4`: a column letter from the left and a row number
+/// from the bottom. These are the same on any board size.
+pub fn to_go(pos: Pos) -> String {
+	format!("{}{}", column_letter(pos.x), pos.y + 1)
+}
+
+/// read Go coordinates, in either case
+pub fn from_go(text: &str) -> Result<Pos> {
+	let mut chars = text.chars();
+	let letter = chars.next().ok_or_else(|| invalid(text))?;
+	let x = COLUMNS
+		.find(letter.to_ascii_uppercase())
+		.ok_or_else(|| invalid(text))?;
+	let row: u8 = chars.as_str().parse().map_err(|_| invalid(text))?;
+	if row == 0 {
+		return Err(invalid(text));
+	}
+	Pos::new(x as u8, row - 1)
+}
+
+/// SGF letters, like `dp`, counted from the top left
+pub fn to_sgf(pos: Pos, size: u8) -> String {
+	sgf::format_point(pos, size)
+}
+
+pub fn from_sgf(text: &str, size: u8) -> Result<Pos> {
+	sgf::parse_point(text, size).ok_or_else(|| invalid(text))
+}
+
+/// A GTP vertex: Go coordinates, or a pass
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Vertex {
+	Point(Pos),
+	Pass,
+}
+
+impl Vertex {
+	/// read a vertex, checking it is on a board of `size`
+	pub fn parse(text: &str, size: u8) -> Result<Self> {
+		if text.eq_ignore_ascii_case("pass") {
+			return Ok(Vertex::Pass);
+		}
+		let pos = check_size(from_go(text)?, size, text)?;
+		Ok(Vertex::Point(pos))
+	}
+}
+
+impl PlacedPiece {
+	/// where the move was played, or a pass
+	pub fn vertex(&self) -> Vertex {
+		if self.is_pass() {
+			Vertex::Pass
+		} else {
+			Vertex::Point(self.pos)
+		}
+	}
+}
+
+impl fmt::Display for Vertex {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Vertex::Point(pos) => write!(f, "{}", to_go(*pos)),
+			Vertex::Pass => write!(f, "pass"),
+		}
+	}
+}
+
+/// Written in Go coordinates. A pass is written `pass`, and any other point
+/// off the board as its raw coordinates, like `(20, 3)`.
+impl fmt::Display for Pos {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if *self == PASS_POS {
+			write!(f, "pass")
+		} else if self.x < MAX_SIZE && self.y < MAX_SIZE {
+			write!(f, "{}", to_go(*self))
+		} else {
+			write!(f, "({}, {})", self.x, self.y)
+		}
+	}
+}
+
+/// read from Go coordinates
+impl FromStr for Pos {
+	type Err = BadukError;
+
+	fn from_str(s: &str) -> Result<Self> {
+		from_go(s)
+	}
+}
@@ -0,0 +1,296 @@
+use crate::{
+	board::*,
+	error::*,
+	piece::*,
+	pos::Pos,
+	symmetry::Symmetry,
+};
+use std::{
+	fmt,
+	str::FromStr,
+};
+
+const EMPTY_BIT: u8 = 1;
+const BLACK_BIT: u8 = 2;
+const WHITE_BIT: u8 = 4;
+const EDGE_BIT: u8 = 8;
+
+/// One cell of a pattern, and which board points it accepts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PatternCell {
+	/// `.` or `*`
+	Empty,
+	/// `X`
+	Black,
+	/// `O`
+	White,
+	/// `#`, outside of the board (`PieceAdjacency::Edge`)
+	Edge,
+	/// `?`, anything, including the edge
+	Any,
+	/// `x`, black stone or empty
+	BlackOrEmpty,
+	/// `o`, white stone or empty
+	WhiteOrEmpty,
+}
+
+impl PatternCell {
+	fn from_char(c: char) -> Option<Self> {
+		Some(match c {
+			'.' | '*' => PatternCell::Empty,
+			'X' => PatternCell::Black,
+			'O' => PatternCell::White,
+			'#' => PatternCell::Edge,
+			'?' => PatternCell::Any,
+			'x' => PatternCell::BlackOrEmpty,
+			'o' => PatternCell::WhiteOrEmpty,
+			_ => return None,
+		})
+	}
+
+	fn to_char(self) -> char {
+		match self {
+			PatternCell::Empty => '.',
+			PatternCell::Black => 'X',
+			PatternCell::White => 'O',
+			PatternCell::Edge => '#',
+			PatternCell::Any => '?',
+			PatternCell::BlackOrEmpty => 'x',
+			PatternCell::WhiteOrEmpty => 'o',
+		}
+	}
+
+	/// bitmask of the point states this cell accepts
+	fn mask(self, inverted: bool) -> u8 {
+		let (black, white) = if inverted {
+			(WHITE_BIT, BLACK_BIT)
+		} else {
+			(BLACK_BIT, WHITE_BIT)
+		};
+		match self {
+			PatternCell::Empty => EMPTY_BIT,
+			PatternCell::Black => black,
+			PatternCell::White => white,
+			PatternCell::Edge => EDGE_BIT,
+			PatternCell::Any => EMPTY_BIT | BLACK_BIT | WHITE_BIT | EDGE_BIT,
+			PatternCell::BlackOrEmpty => black | EMPTY_BIT,
+			PatternCell::WhiteOrEmpty => white | EMPTY_BIT,
+		}
+	}
+}
+
+/// One orientation of a pattern, compiled to a list of
+/// `(offset from the anchor, accepted states)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Variant {
+	cells: Vec<((i8, i8), u8)>,
+	symmetry: Symmetry,
+	inverted: bool,
+}
+
+/// A local board shape, written as a diagram with the top row first:
+///
+/// ```text
+/// # # # #
+/// . X O ?
+/// . * O ?
+/// ```
+///
+/// `.` empty, `X` black, `O` white, `#` off the board, `?` anything,
+/// `x` black or empty, `o` white or empty. `*` is an empty point that marks
+/// the anchor, the position reported for a match; without one the anchor is
+/// the center of the pattern. Whitespace between cells is ignored.
+///
+/// Patterns match in all 8 orientations and with colors swapped.
+#[derive(Clone, Debug)]
+pub struct Pattern {
+	width: u8,
+	height: u8,
+	/// row by row, top row first
+	cells: Vec<PatternCell>,
+	/// column / row of the anchor, counted from the top left
+	anchor: (u8, u8),
+	variants: Vec<Variant>,
+}
+
+impl Pattern {
+	pub fn width(&self) -> u8 {
+		self.width
+	}
+
+	pub fn height(&self) -> u8 {
+		self.height
+	}
+
+	/// cell at column `x`, row `y`, counted from the top left
+	pub fn get(&self, x: u8, y: u8) -> PatternCell {
+		self.cells[y as usize * self.width as usize + x as usize]
+	}
+
+	/// Build the lookup tables for every distinct orientation and coloring.
+	/// Symmetric patterns produce the same table several times, those are
+	/// only kept once so each match is only reported once.
+	fn compile(&mut self) {
+		let mut variants: Vec<Variant> = vec![];
+		for inverted in [false, true].iter() {
+			for symmetry in Symmetry::ALL.iter() {
+				let mut cells: Vec<((i8, i8), u8)> = vec![];
+				for y in 0..self.height {
+					for x in 0..self.width {
+						let mask = self.get(x, y).mask(*inverted);
+						if mask == PatternCell::Any.mask(false) {
+							continue;
+						}
+						// rows are written top to bottom, the board's y axis points up
+						let offset =
+							(x as i8 - self.anchor.0 as i8, self.anchor.1 as i8 - y as i8);
+						cells.push((symmetry.apply_offset(offset), mask));
+					}
+				}
+				cells.sort();
+				if !variants.iter().any(|v| v.cells == cells) {
+					variants.push(Variant {
+						cells,
+						symmetry: *symmetry,
+						inverted: *inverted,
+					});
+				}
+			}
+		}
+		self.variants = variants;
+	}
+}
+
+impl FromStr for Pattern {
+	type Err = BadukError;
+
+	fn from_str(s: &str) -> Result<Self> {
+		let rows: Vec<Vec<char>> = s
+			.lines()
+			.map(|line| line.chars().filter(|c| !c.is_whitespace()).collect())
+			.filter(|row: &Vec<char>| !row.is_empty())
+			.collect();
+
+		let invalid = |reason: String| BadukError::InvalidPattern { reason };
+		if rows.is_empty() {
+			return Err(invalid("pattern is empty".to_string()));
+		}
+		let width = rows[0].len();
+		if width > i8::MAX as usize || rows.len() > i8::MAX as usize {
+			return Err(invalid("pattern is too large".to_string()));
+		}
+
+		let mut cells: Vec<PatternCell> = vec![];
+		let mut anchor: Option<(u8, u8)> = None;
+		for (y, row) in rows.iter().enumerate() {
+			if row.len() != width {
+				return Err(invalid(format!(
+					"row {} is {} cells wide, expected {}",
+					y + 1,
+					row.len(),
+					width
+				)));
+			}
+			for (x, c) in row.iter().enumerate() {
+				if *c == '*' {
+					if anchor.is_some() {
+						return Err(invalid("more than one anchor `*`".to_string()));
+					}
+					anchor = Some((x as u8, y as u8));
+				}
+				match PatternCell::from_char(*c) {
+					Some(cell) => cells.push(cell),
+					None => {
+						return Err(invalid(format!("unknown cell `{}`", c)));
+					}
+				}
+			}
+		}
+
+		let mut pattern = Pattern {
+			width: width as u8,
+			height: rows.len() as u8,
+			cells,
+			anchor: anchor.unwrap_or((width as u8 / 2, rows.len() as u8 / 2)),
+			variants: vec![],
+		};
+		pattern.compile();
+		Ok(pattern)
+	}
+}
+
+impl fmt::Display for Pattern {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for y in 0..self.height {
+			for x in 0..self.width {
+				let c = if (x, y) == self.anchor && self.get(x, y) == PatternCell::Empty
+				{
+					'*'
+				} else {
+					self.get(x, y).to_char()
+				};
+				write!(f, "{}", c)?;
+			}
+			writeln!(f)?;
+		}
+		Ok(())
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PatternMatch {
+	/// where the pattern's anchor is on the board
+	pub pos: Pos,
+	/// orientation the pattern matched in
+	pub symmetry: Symmetry,
+	/// whether it matched with black and white swapped
+	pub inverted: bool,
+}
+
+impl Board {
+	/// state bit of the point at `offset` from `pos`
+	fn pattern_bit(&self, pos: Pos, offset: (i8, i8)) -> u8 {
+		match self.offset(pos, offset) {
+			PieceAdjacency::Edge => EDGE_BIT,
+			PieceAdjacency::Empty(_) => EMPTY_BIT,
+			PieceAdjacency::Piece(p) => match p.piece.color {
+				Black => BLACK_BIT,
+				White => WHITE_BIT,
+			},
+		}
+	}
+
+	/// does `pattern` match with its anchor at `pos`, in any orientation?
+	pub fn match_pattern_at(
+		&self,
+		pattern: &Pattern,
+		pos: Pos,
+	) -> Vec<PatternMatch> {
+		pattern
+			.variants
+			.iter()
+			.filter(|variant| {
+				variant
+					.cells
+					.iter()
+					.all(|(offset, mask)| self.pattern_bit(pos, *offset) & mask != 0)
+			})
+			.map(|variant| PatternMatch {
+				pos,
+				symmetry: variant.symmetry,
+				inverted: variant.inverted,
+			})
+			.collect()
+	}
+
+	/// every place `pattern` matches on the board, in any orientation
+	pub fn find_pattern(&self, pattern: &Pattern) -> Vec<PatternMatch> {
+		let mut matches: Vec<PatternMatch> = vec![];
+		for i in 0..self.size() {
+			for j in 0..self.size() {
+				matches.extend(self.match_pattern_at(pattern, Pos { x: i, y: j }));
+			}
+		}
+		matches
+	}
+}
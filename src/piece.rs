@@ -1,3 +1,8 @@
+use crate::{
+	board::MAX_SIZE,
+	error::*,
+	pos::Pos,
+};
 use colored::Colorize;
 use std::fmt;
 
@@ -32,19 +37,44 @@ pub struct Piece {
 	pub color: PieceColor,
 }
 
+/// where a pass is played, off any board
+pub(crate) const PASS_POS: Pos = Pos {
+	x: MAX_SIZE,
+	y: MAX_SIZE,
+};
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 pub struct PlacedPiece {
 	pub piece: Piece,
-	pub pos: (u8, u8),
+	pub pos: Pos,
 }
 
 impl PlacedPiece {
-	pub fn new(color: PieceColor, pos: (u8, u8)) -> Self {
+	pub fn new(color: PieceColor, pos: Pos) -> Self {
 		Self {
 			piece: Piece { color },
 			pos,
 		}
 	}
+
+	/// A pass by `color`. It is kept with the moves like a stone played off
+	/// the board.
+	pub fn pass(color: PieceColor) -> Self {
+		Self::new(color, PASS_POS)
+	}
+
+	pub fn is_pass(&self) -> bool {
+		self.pos == PASS_POS
+	}
+
+	/// a move read back from the coordinates it was stored with, a pass
+	/// included
+	pub fn from_coords(color: PieceColor, x: u8, y: u8) -> Result<Self> {
+		if (Pos { x, y }) == PASS_POS {
+			return Ok(Self::pass(color));
+		}
+		Ok(Self::new(color, Pos::new(x, y)?))
+	}
 }
 
 impl From<PlacedPieceRef<'_>> for PlacedPiece {
@@ -59,13 +89,13 @@ impl From<PlacedPieceRef<'_>> for PlacedPiece {
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 pub struct PlacedPieceRef<'a> {
 	pub piece: &'a Piece,
-	pub pos: (u8, u8),
+	pub pos: Pos,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 pub enum PieceAdjacency<'a> {
 	Piece(PlacedPieceRef<'a>),
-	Empty((u8, u8)),
+	Empty(Pos),
 	Edge,
 }
 
@@ -1,6 +1,10 @@
-use crate::error::*;
+use crate::{
+	board::MAX_SIZE,
+	error::*,
+};
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+/// A point on the board, counted from the bottom left
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub struct Pos {
 	pub x: u8,
 	pub y: u8,
@@ -9,7 +13,7 @@ pub struct Pos {
 impl Pos {
 	pub fn new(x: u8, y: u8) -> Result<Self> {
 		let p = Self { x, y };
-		if x > 18 || y > 18 {
+		if x >= MAX_SIZE || y >= MAX_SIZE {
 			Err(BadukError::PosOutOfBounds { pos: p })
 		} else {
 			Ok(p)
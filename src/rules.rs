@@ -0,0 +1,77 @@
+use crate::error::*;
+use std::{
+	fmt,
+	str::FromStr,
+};
+
+/// The rules a game is counted under, named like the SGF `RU` property
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ruleset {
+	Japanese,
+	Chinese,
+	Korean,
+	Aga,
+	NewZealand,
+}
+
+impl Ruleset {
+	pub const ALL: [Ruleset; 5] = [
+		Ruleset::Japanese,
+		Ruleset::Chinese,
+		Ruleset::Korean,
+		Ruleset::Aga,
+		Ruleset::NewZealand,
+	];
+
+	/// the usual komi of an even game
+	pub fn default_komi(&self) -> f32 {
+		match self {
+			Ruleset::Japanese | Ruleset::Korean => 6.5,
+			Ruleset::Chinese | Ruleset::Aga | Ruleset::NewZealand => 7.5,
+		}
+	}
+
+	/// whether stones on the board count, or only territory
+	pub fn counts_area(&self) -> bool {
+		match self {
+			Ruleset::Japanese | Ruleset::Korean => false,
+			Ruleset::Chinese | Ruleset::Aga | Ruleset::NewZealand => true,
+		}
+	}
+}
+
+impl Default for Ruleset {
+	fn default() -> Self {
+		Ruleset::Japanese
+	}
+}
+
+impl fmt::Display for Ruleset {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Ruleset::Japanese => write!(f, "Japanese"),
+			Ruleset::Chinese => write!(f, "Chinese"),
+			Ruleset::Korean => write!(f, "Korean"),
+			Ruleset::Aga => write!(f, "AGA"),
+			Ruleset::NewZealand => write!(f, "NZ"),
+		}
+	}
+}
+
+/// Parses the names SGF files use, in any case
+impl FromStr for Ruleset {
+	type Err = BadukError;
+
+	fn from_str(s: &str) -> Result<Self> {
+		match s.trim().to_lowercase().as_str() {
+			"japanese" | "jp" => Ok(Ruleset::Japanese),
+			"chinese" | "cn" => Ok(Ruleset::Chinese),
+			"korean" | "kr" => Ok(Ruleset::Korean),
+			"aga" => Ok(Ruleset::Aga),
+			"nz" | "new zealand" => Ok(Ruleset::NewZealand),
+			_ => Err(BadukError::InvalidRuleset {
+				name: s.to_string(),
+			}),
+		}
+	}
+}
@@ -0,0 +1,912 @@
+use crate::{
+	error::*,
+	game::Game,
+	game_result::GameResult,
+	net::*,
+	piece::*,
+	pos::Pos,
+	sgf::SgfNode,
+};
+use std::{
+	collections::BTreeMap,
+	fs,
+	io::ErrorKind,
+	net::{
+		SocketAddr,
+		TcpListener,
+		ToSocketAddrs,
+	},
+	path::PathBuf,
+	sync::atomic::{
+		AtomicBool,
+		Ordering,
+	},
+	thread,
+	time::{
+		Duration,
+		Instant,
+	},
+};
+
+/// how long a player whose connection dropped has to come back, before
+/// forfeiting the game
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(300);
+/// pause between polls when the server is idle
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+struct ServerClient {
+	conn: Connection,
+	/// has the version handshake been done
+	greeted: bool,
+	/// the game being played or watched, and the color played in it
+	seat: Option<(u32, Option<PieceColor>)>,
+}
+
+/// A player's place in a game, kept while their connection is down
+struct Seat {
+	/// `None` while disconnected
+	client: Option<usize>,
+	token: u64,
+	dropped_at: Option<Instant>,
+}
+
+impl Seat {
+	fn new(client: usize) -> Self {
+		Self {
+			client: Some(client),
+			token: new_token(),
+			dropped_at: None,
+		}
+	}
+}
+
+struct ServerGame {
+	game: Game,
+	black: Option<Seat>,
+	white: Option<Seat>,
+	spectators: Vec<usize>,
+	/// who asked to take back which move, until the opponent answers
+	undo_request: Option<(PieceColor, u32)>,
+}
+
+impl ServerGame {
+	fn player(&self, color: PieceColor) -> Option<&Seat> {
+		match color {
+			Black => self.black.as_ref(),
+			White => self.white.as_ref(),
+		}
+	}
+
+	fn player_mut(&mut self, color: PieceColor) -> Option<&mut Seat> {
+		match color {
+			Black => self.black.as_mut(),
+			White => self.white.as_mut(),
+		}
+	}
+
+	/// everyone playing or watching who is connected
+	fn participants(&self) -> Vec<usize> {
+		let mut ids: Vec<usize> = self
+			.black
+			.iter()
+			.chain(self.white.iter())
+			.filter_map(|seat| seat.client)
+			.collect();
+		ids.extend(self.spectators.iter());
+		ids
+	}
+}
+
+/// A server hosting any number of games. Clients create a game, which they
+/// play as black, or join one as white, or watch one. The server checks every
+/// move and sends it to everyone in the game, including the player who made
+/// it. Finished games are saved as SGF.
+///
+/// Players whose connection drops keep their seat, and can resume the game
+/// with their session token until the reconnect timeout runs out.
+pub struct Server {
+	listener: TcpListener,
+	clients: BTreeMap<usize, ServerClient>,
+	next_client: usize,
+	games: BTreeMap<u32, ServerGame>,
+	next_game: u32,
+	save_dir: Option<PathBuf>,
+	reconnect_timeout: Duration,
+	/// what happened since `take_log`, for whoever runs the server to print
+	log: Vec<String>,
+}
+
+impl Server {
+	/// listen on `addr`, saving finished games in `save_dir` if there is one
+	pub fn bind<A: ToSocketAddrs>(
+		addr: A,
+		save_dir: Option<PathBuf>,
+	) -> Result<Self> {
+		let listener = TcpListener::bind(addr)?;
+		listener.set_nonblocking(true)?;
+		if let Some(dir) = &save_dir {
+			fs::create_dir_all(dir)?;
+		}
+		Ok(Self {
+			listener,
+			clients: BTreeMap::new(),
+			next_client: 0,
+			games: BTreeMap::new(),
+			next_game: 1,
+			save_dir,
+			reconnect_timeout: RECONNECT_TIMEOUT,
+			log: vec![],
+		})
+	}
+
+	/// how long players have to resume a game after their connection drops
+	pub fn with_reconnect_timeout(mut self, timeout: Duration) -> Self {
+		self.reconnect_timeout = timeout;
+		self
+	}
+
+	pub fn local_addr(&self) -> Result<SocketAddr> {
+		Ok(self.listener.local_addr()?)
+	}
+
+	pub fn games(&self) -> Vec<GameSummary> {
+		self
+			.games
+			.iter()
+			.map(|(id, g)| GameSummary {
+				id: *id,
+				players: g.black.iter().chain(g.white.iter()).count() as u8,
+				moves: g.game.len() as u32,
+			})
+			.collect()
+	}
+
+	/// poll until `stop` is set, passing each line of the log to `log`
+	pub fn run<F: FnMut(&str)>(&mut self, stop: &AtomicBool, mut log: F) {
+		while !stop.load(Ordering::Relaxed) {
+			self.poll();
+			for line in self.take_log() {
+				log(&line);
+			}
+			thread::sleep(POLL_INTERVAL);
+		}
+	}
+
+	/// what happened since the last call: clients coming and going, games
+	/// ending, and errors that didn't stop the server
+	pub fn take_log(&mut self) -> Vec<String> {
+		std::mem::take(&mut self.log)
+	}
+
+	/// Accept new clients and handle everything sent since the last poll.
+	/// Never blocks.
+	pub fn poll(&mut self) {
+		loop {
+			match self.accept() {
+				Ok(true) => {}
+				Ok(false) => break,
+				// e.g. out of file descriptors, try again next time
+				Err(err) => {
+					self.log(format!("could not accept a client: {}", err));
+					break;
+				}
+			}
+		}
+
+		let ids: Vec<usize> = self.clients.keys().copied().collect();
+		for id in ids {
+			// the client may be dropped while handling its messages
+			while let Some(client) = self.clients.get_mut(&id) {
+				match client.conn.poll() {
+					Ok(Some(message)) => self.handle(id, message),
+					Ok(None) => break,
+					Err(err) => {
+						self.log(format!("client {} dropped: {}", id, err));
+						self.drop_client(id);
+						break;
+					}
+				}
+			}
+		}
+		self.expire_seats();
+	}
+
+	/// take the next client waiting to connect, if there is one
+	fn accept(&mut self) -> Result<bool> {
+		let (stream, addr) = match self.listener.accept() {
+			Ok(accepted) => accepted,
+			Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(false),
+			Err(err) => return Err(err.into()),
+		};
+		let conn = Connection::new(stream)?;
+		conn.set_blocking(None)?;
+		self.log(format!("{} connected as client {}", addr, self.next_client));
+		self.clients.insert(
+			self.next_client,
+			ServerClient {
+				conn,
+				greeted: false,
+				seat: None,
+			},
+		);
+		self.next_client += 1;
+		Ok(true)
+	}
+
+	fn log(&mut self, line: String) {
+		self.log.push(line);
+	}
+
+	/// forfeit the games of players who didn't come back in time
+	fn expire_seats(&mut self) {
+		let mut expired: Vec<(u32, PieceColor)> = vec![];
+		for (game_id, g) in self.games.iter() {
+			for color in [Black, White].iter() {
+				if let Some(dropped_at) = g.player(*color).and_then(|s| s.dropped_at) {
+					if dropped_at.elapsed() >= self.reconnect_timeout {
+						expired.push((*game_id, *color));
+					}
+				}
+			}
+		}
+		for (game_id, color) in expired {
+			self.log(format!("{} didn't come back to game {}", color, game_id));
+			self.forfeit(game_id, color);
+		}
+	}
+
+	/// send to a client, dropping it if it can't be reached
+	fn send(&mut self, id: usize, message: &Message) {
+		let res = match self.clients.get_mut(&id) {
+			Some(client) => client.conn.send(message),
+			None => return,
+		};
+		if let Err(err) = res {
+			self.log(format!("client {} dropped: {}", id, err));
+			self.drop_client(id);
+		}
+	}
+
+	fn refuse(&mut self, id: usize, reason: String) {
+		self.send(id, &Message::Refused { reason });
+	}
+
+	/// Forget a client whose connection is gone. Players keep their seat, so
+	/// they can resume.
+	fn drop_client(&mut self, id: usize) {
+		let seat = match self.clients.remove(&id) {
+			Some(client) => client.seat,
+			None => return,
+		};
+		match seat {
+			Some((game_id, Some(color))) => {
+				let seat = self
+					.games
+					.get_mut(&game_id)
+					.and_then(|g| g.player_mut(color));
+				if let Some(seat) = seat {
+					seat.client = None;
+					seat.dropped_at = Some(Instant::now());
+				}
+			}
+			Some((game_id, None)) => {
+				if let Some(g) = self.games.get_mut(&game_id) {
+					g.spectators.retain(|s| *s != id);
+				}
+			}
+			None => {}
+		}
+	}
+
+	fn handle(&mut self, id: usize, message: Message) {
+		let client = &self.clients[&id];
+		if !client.greeted {
+			match message {
+				Message::Hello { version } if version == PROTOCOL_VERSION => {
+					self.clients.get_mut(&id).unwrap().greeted = true;
+					self.send(
+						id,
+						&Message::Hello {
+							version: PROTOCOL_VERSION,
+						},
+					);
+				}
+				_ => {
+					self.refuse(
+						id,
+						format!("expected Hello with version {}", PROTOCOL_VERSION),
+					);
+					self.drop_client(id);
+				}
+			}
+			return;
+		}
+		let seat = client.seat;
+
+		match message {
+			Message::Create {
+				size,
+				handicap,
+				komi,
+			} => {
+				if seat.is_some() {
+					return self.refuse(id, "already in a game".to_string());
+				}
+				let mut game = match Game::with_size(size, handicap) {
+					Ok(game) => game,
+					Err(err) => return self.refuse(id, err.to_string()),
+				};
+				if !komi.is_finite() {
+					return self.refuse(id, format!("invalid komi {}", komi));
+				}
+				game.komi = komi;
+				let game_id = self.next_game;
+				self.next_game += 1;
+				self.games.insert(
+					game_id,
+					ServerGame {
+						game,
+						black: Some(Seat::new(id)),
+						white: None,
+						spectators: vec![],
+						undo_request: None,
+					},
+				);
+				self.sit(id, game_id, Some(Black));
+			}
+			Message::Join { game } => {
+				if seat.is_some() {
+					return self.refuse(id, "already in a game".to_string());
+				}
+				let color = match self.games.get_mut(&game) {
+					None => return self.refuse(id, format!("no game {}", game)),
+					Some(g) if g.black.is_none() => {
+						g.black = Some(Seat::new(id));
+						Black
+					}
+					Some(g) if g.white.is_none() => {
+						g.white = Some(Seat::new(id));
+						White
+					}
+					Some(_) => return self.refuse(id, format!("game {} is full", game)),
+				};
+				self.sit(id, game, Some(color));
+			}
+			Message::Spectate { game } => {
+				if seat.is_some() {
+					return self.refuse(id, "already in a game".to_string());
+				}
+				match self.games.get_mut(&game) {
+					None => return self.refuse(id, format!("no game {}", game)),
+					Some(g) => g.spectators.push(id),
+				}
+				self.sit(id, game, None);
+			}
+			Message::List => {
+				let games = self.games();
+				self.send(id, &Message::Games { games });
+			}
+			Message::Move { number, m, hash } => {
+				let (game_id, color) = match seat {
+					Some((game_id, Some(color))) => (game_id, color),
+					_ => return self.refuse(id, "not playing a game".to_string()),
+				};
+				if let Err(err) = self.play(game_id, color, number, m) {
+					return self.refuse(id, err.to_string());
+				}
+				let g = &self.games[&game_id];
+				let server_hash = g.game.board.hash();
+				let participants = g.participants();
+				if server_hash != hash {
+					self.send(
+						id,
+						&Message::Desync {
+							number,
+							hash: server_hash,
+						},
+					);
+				}
+				let moved = Message::Move {
+					number,
+					m,
+					hash: server_hash,
+				};
+				for other in participants {
+					self.send(other, &moved);
+				}
+			}
+			Message::Desync { number, .. } => {
+				self.log(format!(
+					"client {} is out of sync after move {}",
+					id, number
+				));
+			}
+			Message::Resume { token, acked } => {
+				if seat.is_some() {
+					return self.refuse(id, "already in a game".to_string());
+				}
+				self.resume(id, token, acked);
+			}
+			Message::Leave => self.leave(id),
+			Message::Resign => match seat {
+				Some((game_id, Some(color))) => self.lose(
+					game_id,
+					GameResult::Resignation {
+						winner: color.opposite(),
+					},
+				),
+				_ => self.refuse(id, "not playing a game".to_string()),
+			},
+			Message::UndoRequest { number } => match seat {
+				Some((game_id, Some(color))) => {
+					self.request_undo(id, game_id, color, number)
+				}
+				_ => self.refuse(id, "not playing a game".to_string()),
+			},
+			Message::UndoReply { number, accepted } => match seat {
+				Some((game_id, Some(color))) => {
+					self.reply_undo(game_id, color, number, accepted)
+				}
+				_ => self.refuse(id, "not playing a game".to_string()),
+			},
+			Message::OfferDraw => {
+				let (game_id, color) = match seat {
+					Some((game_id, Some(color))) => (game_id, color),
+					_ => return self.refuse(id, "not playing a game".to_string()),
+				};
+				let g = self.games.get_mut(&game_id).unwrap();
+				let opponent = g.player(color.opposite()).and_then(|s| s.client);
+				match g.game.offer_draw(color) {
+					Ok(Some(result)) => self.finish(game_id, result),
+					Ok(None) => {
+						if let Some(opponent) = opponent {
+							self.send(opponent, &Message::OfferDraw);
+						}
+					}
+					Err(err) => self.refuse(id, err.to_string()),
+				}
+			}
+			_ => self.refuse(id, "unexpected message".to_string()),
+		}
+	}
+
+	/// give a player their seat back, and send them the moves they missed
+	fn resume(&mut self, id: usize, token: u64, acked: u32) {
+		let found = self.games.iter().find_map(|(game_id, g)| {
+			[Black, White]
+				.iter()
+				.find(|c| g.player(**c).map_or(false, |s| s.token == token))
+				.map(|c| (*game_id, *c))
+		});
+		let (game_id, color) = match found {
+			Some(found) => found,
+			None => return self.refuse(id, "unknown session".to_string()),
+		};
+		let g = self.games.get_mut(&game_id).unwrap();
+		let answer = match resumed(game_id, &g.game, color, acked) {
+			Ok(answer) => answer,
+			Err(err) => return self.refuse(id, err.to_string()),
+		};
+		let seat = g.player_mut(color).unwrap();
+		seat.dropped_at = None;
+		// the old connection may not have noticed it is gone yet
+		if let Some(old) = seat.client.replace(id) {
+			self.clients.remove(&old);
+		}
+		self.log(format!(
+			"client {} resumed game {} as {}",
+			id, game_id, color
+		));
+		self.clients.get_mut(&id).unwrap().seat = Some((game_id, Some(color)));
+		self.send(id, &answer);
+	}
+
+	/// give a client its seat, and send it the game so far
+	fn sit(&mut self, id: usize, game_id: u32, color: Option<PieceColor>) {
+		self.clients.get_mut(&id).unwrap().seat = Some((game_id, color));
+		let g = &self.games[&game_id];
+		let joined = Message::Joined {
+			game: game_id,
+			color,
+			size: g.game.board.size(),
+			handicap: g.game.handicap,
+			komi: g.game.komi,
+			setup: g.game.setup().map(|stones| stones.to_vec()),
+			first_turn: g.game.first_turn(),
+			moves: g.game.moves.clone(),
+			token: color.and_then(|c| g.player(c)).map_or(0, |s| s.token),
+		};
+		self.send(id, &joined);
+	}
+
+	fn play(
+		&mut self,
+		game_id: u32,
+		color: PieceColor,
+		number: u32,
+		m: PlacedPiece,
+	) -> Result<()> {
+		let g = self.games.get_mut(&game_id).unwrap();
+		if g.black.is_none() || g.white.is_none() {
+			return Err(protocol_error("waiting for an opponent"));
+		}
+		if m.piece.color != color || color != g.game.current_turn_color() {
+			return Err(BadukError::InvalidMove {
+				source: InvalidMoveError::NotYourTurn {
+					turn: g.game.current_turn_color(),
+				},
+			});
+		}
+		if number as usize != g.game.len() + 1 {
+			return Err(protocol_error(&format!(
+				"expected move {}, got move {}",
+				g.game.len() + 1,
+				number
+			)));
+		}
+		g.game.do_move(m)?;
+		g.undo_request = None;
+		Ok(())
+	}
+
+	/// pass an undo request on to the opponent, if it is for the last move
+	fn request_undo(
+		&mut self,
+		id: usize,
+		game_id: u32,
+		color: PieceColor,
+		number: u32,
+	) {
+		let g = self.games.get_mut(&game_id).unwrap();
+		let opponent = g.player(color.opposite()).and_then(|s| s.client);
+		match opponent {
+			Some(opponent) if number as usize == g.game.len() && number > 0 => {
+				g.undo_request = Some((color, number));
+				self.send(opponent, &Message::UndoRequest { number });
+			}
+			_ => self.send(
+				id,
+				&Message::UndoReply {
+					number,
+					accepted: false,
+				},
+			),
+		}
+	}
+
+	/// Take the move back if the opponent accepted, telling everyone in the
+	/// game. A declined request only goes back to whoever asked.
+	fn reply_undo(
+		&mut self,
+		game_id: u32,
+		color: PieceColor,
+		number: u32,
+		accepted: bool,
+	) {
+		let g = self.games.get_mut(&game_id).unwrap();
+		let requester = color.opposite();
+		if g.undo_request != Some((requester, number)) {
+			return;
+		}
+		g.undo_request = None;
+		let undone = accepted && g.game.undo().is_ok();
+		let reply = Message::UndoReply {
+			number,
+			accepted: undone,
+		};
+		let recipients = if undone {
+			g.participants()
+		} else {
+			g.player(requester)
+				.and_then(|s| s.client)
+				.into_iter()
+				.collect()
+		};
+		for other in recipients {
+			self.send(other, &reply);
+		}
+	}
+
+	/// take a client out of its game, a player leaving forfeits it
+	fn leave(&mut self, id: usize) {
+		let seat = match self.clients.get_mut(&id) {
+			Some(client) => client.seat.take(),
+			None => return,
+		};
+		match seat {
+			Some((game_id, None)) => {
+				if let Some(g) = self.games.get_mut(&game_id) {
+					g.spectators.retain(|s| *s != id);
+				}
+			}
+			Some((game_id, Some(color))) => self.forfeit(game_id, color),
+			None => {}
+		}
+	}
+
+	fn forfeit(&mut self, game_id: u32, color: PieceColor) {
+		self.lose(
+			game_id,
+			GameResult::Forfeit {
+				winner: color.opposite(),
+			},
+		);
+	}
+
+	/// end a game one player lost, or without a result if they were still
+	/// waiting for an opponent
+	fn lose(&mut self, game_id: u32, result: GameResult) {
+		let has_opponent = match self.games.get(&game_id) {
+			Some(g) => g.black.is_some() && g.white.is_some(),
+			None => return,
+		};
+		if has_opponent {
+			self.finish(game_id, result);
+		} else {
+			self.finish(game_id, GameResult::Void);
+		}
+	}
+
+	/// end a game, telling everyone still in it and saving it if any moves
+	/// were played
+	fn finish(&mut self, game_id: u32, result: GameResult) {
+		let g = match self.games.remove(&game_id) {
+			Some(g) => g,
+			None => return,
+		};
+		self.log(format!("game {} ended: {}", game_id, result));
+		for id in g.participants() {
+			if let Some(client) = self.clients.get_mut(&id) {
+				client.seat = None;
+			}
+			self.send(
+				id,
+				&Message::GameOver {
+					result: result.to_string(),
+				},
+			);
+		}
+		if g.game.is_empty() {
+			return;
+		}
+		if let Some(dir) = &self.save_dir {
+			let mut sgf = SgfNode::from_game(&g.game);
+			if sgf.get("RE").is_none() {
+				sgf.push("RE", &result.to_string());
+			}
+			let path = dir.join(format!("game-{}.sgf", game_id));
+			let line = match fs::write(&path, sgf.to_string()) {
+				Ok(()) => format!("saved {}", path.display()),
+				Err(err) => format!("could not save {}: {}", path.display(), err),
+			};
+			self.log(line);
+		}
+	}
+}
+
+/// A connection to a server, following the game it is in
+pub struct Client {
+	conn: Connection,
+	pub game_id: Option<u32>,
+	/// the color played, `None` when watching or not in a game
+	pub color: Option<PieceColor>,
+	pub game: Game,
+	/// false once a position hash from the server didn't match ours
+	pub in_sync: bool,
+	/// to resume the game with if the connection drops
+	pub token: Option<u64>,
+}
+
+impl Client {
+	pub fn connect<A: ToSocketAddrs>(addr: A) -> Result<Self> {
+		let mut conn = handshake(addr)?;
+		match conn.recv()? {
+			Message::Hello { version } if version == PROTOCOL_VERSION => {}
+			Message::Refused { reason } => {
+				return Err(BadukError::Protocol { reason })
+			}
+			_ => return Err(protocol_error("expected Hello")),
+		}
+		Ok(Self {
+			conn,
+			game_id: None,
+			color: None,
+			game: Game::new(0),
+			in_sync: true,
+			token: None,
+		})
+	}
+
+	/// Connect again after the connection dropped, taking back the seat of
+	/// `token`. `game` is caught up with the moves it missed, which are
+	/// returned as events, along with a desync if the positions don't match.
+	pub fn resume<A: ToSocketAddrs>(
+		addr: A,
+		token: u64,
+		game: Game,
+	) -> Result<(Self, Vec<NetEvent>)> {
+		let mut client = Self::connect(addr)?;
+		client.game = game;
+		client.send(&Message::Resume {
+			token,
+			acked: client.game.len() as u32,
+		})?;
+		let events = match client.conn.recv()? {
+			Message::Resumed {
+				game,
+				color,
+				from,
+				moves,
+				hash,
+			} => {
+				client.game_id = Some(game);
+				client.color = Some(color);
+				client.token = Some(token);
+				replay(&mut client.game, from, &moves, hash)?
+			}
+			Message::Refused { reason } => {
+				return Err(BadukError::Protocol { reason })
+			}
+			m => return Err(protocol_error(&format!("unexpected {:?}", m))),
+		};
+		for event in events.iter() {
+			if let NetEvent::Desync { number, local, .. } = event {
+				client.in_sync = false;
+				client.send(&Message::Desync {
+					number: *number,
+					hash: *local,
+				})?;
+			}
+		}
+		Ok((client, events))
+	}
+
+	pub fn send(&mut self, message: &Message) -> Result<()> {
+		self.conn.send(message)
+	}
+
+	/// Wait for the next message from the server, and apply it to the game
+	pub fn recv(&mut self) -> Result<Message> {
+		let message = self.conn.recv()?;
+		match &message {
+			Message::Joined {
+				game,
+				color,
+				size,
+				handicap,
+				komi,
+				setup,
+				first_turn,
+				moves,
+				token,
+			} => {
+				self.game_id = Some(*game);
+				self.color = *color;
+				self.token = color.map(|_| *token);
+				self.game =
+					Game::with_start(*size, *handicap, setup.clone(), *first_turn)?;
+				self.game.komi = *komi;
+				for m in moves.iter() {
+					self.game.do_move(*m)?;
+				}
+				self.in_sync = true;
+			}
+			Message::Move { number, m, hash } => {
+				if *number as usize == self.game.len() + 1 {
+					self.game.do_move(*m)?;
+				}
+				if self.game.board.hash() != *hash {
+					self.in_sync = false;
+					self.conn.send(&Message::Desync {
+						number: *number,
+						hash: self.game.board.hash(),
+					})?;
+				}
+			}
+			Message::UndoReply {
+				number,
+				accepted: true,
+			} if *number as usize == self.game.len() => {
+				self.game.undo()?;
+			}
+			Message::GameOver { result } => {
+				if let Ok(result) = result.parse() {
+					// already over if it ended on our side too
+					let _ = self.game.end(result);
+				}
+				self.game_id = None;
+				self.color = None;
+				self.token = None;
+			}
+			_ => {}
+		}
+		Ok(message)
+	}
+
+	/// wait for a `Joined` answer, or the reason there isn't one
+	fn joined(&mut self) -> Result<u32> {
+		match self.recv()? {
+			Message::Joined { game, .. } => Ok(game),
+			Message::Refused { reason } => Err(BadukError::Protocol { reason }),
+			m => Err(protocol_error(&format!("unexpected {:?}", m))),
+		}
+	}
+
+	/// create a game and play black in it
+	pub fn create(&mut self, size: u8, handicap: u8, komi: f32) -> Result<u32> {
+		self.send(&Message::Create {
+			size,
+			handicap,
+			komi,
+		})?;
+		self.joined()
+	}
+
+	/// join a game, returning the color played
+	pub fn join(&mut self, game: u32) -> Result<PieceColor> {
+		self.send(&Message::Join { game })?;
+		self.joined()?;
+		self
+			.color
+			.ok_or_else(|| protocol_error("joined without a color"))
+	}
+
+	pub fn spectate(&mut self, game: u32) -> Result<()> {
+		self.send(&Message::Spectate { game })?;
+		self.joined().map(|_| ())
+	}
+
+	pub fn list(&mut self) -> Result<Vec<GameSummary>> {
+		self.send(&Message::List)?;
+		match self.recv()? {
+			Message::Games { games } => Ok(games),
+			m => Err(protocol_error(&format!("unexpected {:?}", m))),
+		}
+	}
+
+	/// Send a move, checking it locally first. It is played once the server
+	/// sends it back.
+	pub fn play(&mut self, pos: Pos) -> Result<()> {
+		let color = self
+			.color
+			.ok_or_else(|| protocol_error("not playing a game"))?;
+		let m = PlacedPiece::new(color, pos);
+		let mut board = self.game.board.clone();
+		board.do_move(m)?;
+		self.send(&Message::Move {
+			number: self.game.len() as u32 + 1,
+			m,
+			hash: board.hash(),
+		})
+	}
+
+	pub fn leave(&mut self) -> Result<()> {
+		self.send(&Message::Leave)
+	}
+
+	/// resign the game, which ends once the server sends `GameOver`
+	pub fn resign(&mut self) -> Result<()> {
+		self.send(&Message::Resign)
+	}
+
+	/// offer a draw, or accept the opponent's
+	pub fn offer_draw(&mut self) -> Result<()> {
+		self.send(&Message::OfferDraw)
+	}
+
+	/// ask to take back the last move, which happens once the server sends
+	/// back an accepted `UndoReply`
+	pub fn request_undo(&mut self) -> Result<()> {
+		self.send(&Message::UndoRequest {
+			number: self.game.len() as u32,
+		})
+	}
+
+	/// answer the opponent's request to take back the last move
+	pub fn reply_undo(&mut self, accepted: bool) -> Result<()> {
+		self.send(&Message::UndoReply {
+			number: self.game.len() as u32,
+			accepted,
+		})
+	}
+}
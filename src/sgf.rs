@@ -0,0 +1,658 @@
+use crate::{
+	annotation::*,
+	board::{
+		Board,
+		MAX_SIZE,
+	},
+	clock::{
+		Clock,
+		TimeControl,
+	},
+	error::*,
+	game::*,
+	game_result::GameResult,
+	piece::*,
+	pos::Pos,
+	variations::Variations,
+};
+use std::{
+	fmt,
+	fs,
+	path::Path,
+};
+
+/// Not a standard property: the move the game was being looked at, when it
+/// isn't the last one.
+const VIEWED_MOVE: &str = "XV";
+/// Not a standard property either: how many moves of the main line were
+/// played, when some were taken back.
+const PLAYED_MOVES: &str = "XM";
+
+/// A single SGF property, e.g. `AB[dd][pp]`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SgfProperty {
+	pub ident: String,
+	pub values: Vec<String>,
+}
+
+/// A node of an SGF game tree. The main line is always the first child.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SgfNode {
+	pub properties: Vec<SgfProperty>,
+	pub children: Vec<SgfNode>,
+}
+
+impl SgfNode {
+	/// first value of a property
+	pub fn get(&self, ident: &str) -> Option<&str> {
+		self
+			.get_all(ident)
+			.and_then(|values| values.first())
+			.map(|v| v.as_str())
+	}
+
+	/// every value of a property
+	pub fn get_all(&self, ident: &str) -> Option<&Vec<String>> {
+		self
+			.properties
+			.iter()
+			.find(|p| p.ident == ident)
+			.map(|p| &p.values)
+	}
+
+	/// the `B` / `W` move played in this node, a pass if it isn't on the
+	/// board
+	pub fn get_move(&self, size: u8) -> Option<PlacedPiece> {
+		for (ident, color) in [("B", Black), ("W", White)].iter() {
+			if let Some(value) = self.get(ident) {
+				return Some(match parse_point(value, size) {
+					Some(pos) => PlacedPiece::new(*color, pos),
+					None => PlacedPiece::pass(*color),
+				});
+			}
+		}
+		None
+	}
+
+	/// the comment, markup and move quality of this node
+	pub fn annotations(&self, size: u8) -> Annotations {
+		let mut markup = vec![];
+		let marks = [
+			("TR", Mark::Triangle),
+			("CR", Mark::Circle),
+			("SQ", Mark::Square),
+			("MA", Mark::Cross),
+		];
+		for (ident, mark) in marks.iter() {
+			if let Some(values) = self.get_all(ident) {
+				for pos in parse_point_list(values, size) {
+					markup.push(Markup {
+						pos,
+						mark: mark.clone(),
+					});
+				}
+			}
+		}
+		for value in self.get_all("LB").map(|v| v.as_slice()).unwrap_or(&[]) {
+			let mut parts = value.splitn(2, ':');
+			let pos = parts.next().and_then(|point| parse_point(point, size));
+			if let (Some(pos), Some(text)) = (pos, parts.next()) {
+				markup.push(Markup {
+					pos,
+					mark: Mark::Label(text.to_string()),
+				});
+			}
+		}
+		let qualities = [
+			("TE", MoveQuality::Good),
+			("BM", MoveQuality::Bad),
+			("DO", MoveQuality::Doubtful),
+			("IT", MoveQuality::Interesting),
+		];
+		let quality = qualities
+			.iter()
+			.find(|(ident, _)| self.get(ident).is_some())
+			.map(|(_, quality)| *quality);
+		Annotations {
+			comment: self.get("C").map(|c| c.to_string()),
+			markup,
+			quality,
+		}
+	}
+
+	/// write the comment, markup and move quality into this node
+	pub fn push_annotations(&mut self, annotations: &Annotations, size: u8) {
+		if let Some(comment) = &annotations.comment {
+			self.push("C", comment);
+		}
+		for markup in annotations.markup.iter() {
+			let point = format_point(markup.pos, size);
+			match &markup.mark {
+				Mark::Triangle => self.push("TR", &point),
+				Mark::Circle => self.push("CR", &point),
+				Mark::Square => self.push("SQ", &point),
+				Mark::Cross => self.push("MA", &point),
+				Mark::Label(text) => self.push("LB", &format!("{}:{}", point, text)),
+			}
+		}
+		match annotations.quality {
+			Some(MoveQuality::Good) => self.push("TE", "1"),
+			Some(MoveQuality::Bad) => self.push("BM", "1"),
+			Some(MoveQuality::Doubtful) => self.push("DO", ""),
+			Some(MoveQuality::Interesting) => self.push("IT", ""),
+			None => {}
+		}
+	}
+
+	/// Record a game with all its variations and their annotations, the line
+	/// being followed as the main line. The root holds the game info and the
+	/// handicap or setup stones, every other node a single move.
+	pub fn from_game(game: &Game) -> SgfNode {
+		let size = game.board.size();
+		let mut root = SgfNode::default();
+		root.push("GM", "1");
+		root.push("FF", "4");
+		root.push("SZ", &size.to_string());
+		if game.handicap > 0 {
+			root.push("HA", &game.handicap.to_string());
+		}
+		if game.handicap > 0 || game.setup().is_some() {
+			let start = game.get_board_at_move(0).unwrap();
+			for stone in start.all_pieces() {
+				let ident = match stone.piece.color {
+					Black => "AB",
+					White => "AW",
+				};
+				root.push(ident, &format_point(stone.pos, size));
+			}
+			// handicap stones that were taken off in the setup
+			if let Ok(handicap) = Game::with_size(size, game.handicap) {
+				for stone in handicap.board.all_pieces() {
+					if matches!(start.get_color(stone.pos), Ok(None)) {
+						root.push("AE", &format_point(stone.pos, size));
+					}
+				}
+			}
+		}
+		// white moving first after handicap stones goes without saying
+		let usual_turn = if game.handicap > 0 { White } else { Black };
+		match game.first_turn() {
+			turn if turn == usual_turn => {}
+			Black => root.push("PL", "B"),
+			White => root.push("PL", "W"),
+		}
+		root.push("KM", &game.komi.to_string());
+		root.push("RU", &game.ruleset.to_string());
+		if let Some(clock) = &game.clock {
+			let control = clock.control();
+			root.push("TM", &control.main().as_secs().to_string());
+			root.push("OT", &control.to_string());
+		}
+		if let Some(result) = game.result() {
+			root.push("RE", &result.to_string());
+		}
+		if let GamePosition::Past(turn) = game.position() {
+			root.push(VIEWED_MOVE, &turn.to_string());
+		}
+		root.push_annotations(&game.variations().annotations, size);
+		// moves taken back are still in the tree, after the ones played
+		let next_moves = game
+			.variations()
+			.find(&game.moves)
+			.map_or(0, |node| node.children().len());
+		if next_moves > 0 {
+			root.push(PLAYED_MOVES, &game.len().to_string());
+		}
+		root.children = variation_nodes(game.variations(), &game.moves, size);
+		root
+	}
+
+	/// The game recorded in the main line, as written by `from_game`, with
+	/// the other lines as its variations and the annotations of every node.
+	/// Stones set up in the root are where the game starts from, handicap
+	/// stones among them, and `HA` alone places the handicap stones where they
+	/// usually go. Setup in any other node can't be played through, and is
+	/// refused. Without `PL` the first move says who starts. A time control in `OT` starts again
+	/// with its full main time.
+	pub fn to_game(&self) -> Result<Game> {
+		let invalid = |ident: &str, value: &str| BadukError::SgfParse {
+			reason: format!("invalid {} `{}`", ident, value),
+		};
+		let size = match self.get("SZ") {
+			Some(sz) => sz.parse().map_err(|_| invalid("SZ", sz))?,
+			None => MAX_SIZE,
+		};
+		let handicap = match self.get("HA") {
+			Some(ha) => ha.parse().map_err(|_| invalid("HA", ha))?,
+			None => 0,
+		};
+		let setup = [("AB", Some(Black)), ("AW", Some(White)), ("AE", None)];
+		let has_setup =
+			|node: &SgfNode| setup.iter().any(|(ident, _)| node.get(ident).is_some());
+		let nodes = self.nodes_with_lines(size);
+		if nodes.iter().skip(1).any(|(node, _)| has_setup(node)) {
+			return Err(BadukError::SgfParse {
+				reason: "stones set up after the first node".to_string(),
+			});
+		}
+		let mut start = Board::with_size(size)?;
+		for (ident, color) in setup.iter() {
+			let values = match self.get_all(ident) {
+				Some(values) => values,
+				None => continue,
+			};
+			for pos in parse_point_list(values, size) {
+				match color {
+					Some(color) => {
+						start.set(PlacedPiece::new(*color, pos))?;
+					}
+					None => start.remove(pos)?,
+				}
+			}
+		}
+		let first_move =
+			self.main_line().iter().find_map(|node| node.get_move(size));
+		let first_turn = match (self.get("PL"), first_move) {
+			(Some("B"), _) | (Some("b"), _) => Black,
+			(Some("W"), _) | (Some("w"), _) => White,
+			(None, Some(m)) => m.piece.color,
+			(None, None) if handicap > 0 => White,
+			(None, None) => Black,
+			(Some(pl), _) => return Err(invalid("PL", pl)),
+		};
+		// handicap stones where they usually go aren't a setup
+		let usual = Game::with_size(size, handicap).ok();
+		let set_up = has_setup(self)
+			&& usual.map_or(true, |usual| usual.board.hash() != start.hash());
+		let stones = start.all_pieces().iter().map(|&p| p.into()).collect();
+		let mut game = Game::with_start(
+			size,
+			handicap,
+			if set_up { Some(stones) } else { None },
+			first_turn,
+		)?;
+		if let Some(km) = self.get("KM") {
+			game.komi = km.parse().map_err(|_| invalid("KM", km))?;
+		}
+		// an unknown ruleset is counted with the default one
+		if let Some(ruleset) = self.get("RU").and_then(|ru| ru.parse().ok()) {
+			game.ruleset = ruleset;
+		}
+		if let Some(ot) = self.get("OT") {
+			if let Ok(control) = ot.parse::<TimeControl>() {
+				game.set_clock(Clock::real(control));
+			}
+		}
+		let played = match self.get(PLAYED_MOVES) {
+			Some(n) => n.parse().map_err(|_| invalid(PLAYED_MOVES, n))?,
+			None => usize::MAX,
+		};
+		let main_line = self.main_line();
+		let moves = main_line.iter().filter_map(|node| node.get_move(size));
+		for m in moves.take(played) {
+			game.do_move(m)?;
+		}
+		// the main line too, when it goes on past the moves played
+		for line in self.lines(size) {
+			game.add_variation(&line)?;
+		}
+		for (node, line) in nodes {
+			let annotations = node.annotations(size);
+			if !annotations.is_empty() {
+				game.annotate(&line, annotations)?;
+			}
+		}
+		if let Some(re) = self.get("RE") {
+			// `?` and other results nobody knows are left out
+			if let Ok(result) = re.parse::<GameResult>() {
+				game.end(result)?;
+			}
+		}
+		if let Some(turn) = self.get(VIEWED_MOVE) {
+			let turn: usize = turn.parse().map_err(|_| invalid(VIEWED_MOVE, turn))?;
+			if turn < game.len() {
+				game.set_position(GamePosition::Past(turn))?;
+			}
+		}
+		Ok(game)
+	}
+
+	/// add a value to a property, creating it if needed
+	pub fn push(&mut self, ident: &str, value: &str) {
+		match self.properties.iter_mut().find(|p| p.ident == ident) {
+			Some(p) => p.values.push(value.to_string()),
+			None => self.properties.push(SgfProperty {
+				ident: ident.to_string(),
+				values: vec![value.to_string()],
+			}),
+		}
+	}
+
+	/// this node and every one below it, in the order they are written, with
+	/// the moves played to reach each
+	pub fn nodes_with_lines(
+		&self,
+		size: u8,
+	) -> Vec<(&SgfNode, Vec<PlacedPiece>)> {
+		let mut nodes = vec![];
+		let mut stack: Vec<(&SgfNode, Vec<PlacedPiece>)> = vec![(self, vec![])];
+		while let Some((node, mut line)) = stack.pop() {
+			line.extend(node.get_move(size));
+			// the first child is taken first
+			for child in node.children.iter().rev() {
+				stack.push((child, line.clone()));
+			}
+			nodes.push((node, line));
+		}
+		nodes
+	}
+
+	/// the moves of every line from this node to the end of a variation, the
+	/// main line first
+	pub fn lines(&self, size: u8) -> Vec<Vec<PlacedPiece>> {
+		self
+			.nodes_with_lines(size)
+			.into_iter()
+			.filter(|(node, _)| node.children.is_empty())
+			.map(|(_, line)| line)
+			.collect()
+	}
+
+	/// the nodes of the main line, starting with this one
+	pub fn main_line(&self) -> Vec<&SgfNode> {
+		let mut nodes = vec![self];
+		let mut node = self;
+		while let Some(child) = node.children.first() {
+			nodes.push(child);
+			node = child;
+		}
+		nodes
+	}
+}
+
+/// a node for each move played from `variations`, with the moves after it.
+/// The moves of `line` come first, to be the main line.
+fn variation_nodes(
+	variations: &Variations,
+	line: &[PlacedPiece],
+	size: u8,
+) -> Vec<SgfNode> {
+	let mut children: Vec<&(PlacedPiece, Variations)> =
+		variations.children().iter().collect();
+	if let Some(i) = children.iter().position(|(m, _)| line.first() == Some(m)) {
+		let next = children.remove(i);
+		children.insert(0, next);
+	}
+	children
+		.into_iter()
+		.map(|(m, rest)| {
+			let mut node = SgfNode::default();
+			let ident = match m.piece.color {
+				Black => "B",
+				White => "W",
+			};
+			node.push(ident, &format_point(m.pos, size));
+			node.push_annotations(&rest.annotations, size);
+			let line = match line.split_first() {
+				Some((first, line)) if first == m => line,
+				_ => &[],
+			};
+			node.children = variation_nodes(rest, line, size);
+			node
+		})
+		.collect()
+}
+
+/// Writes the node and everything below it as a game tree, `(;...)`
+impl fmt::Display for SgfNode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fn sequence(node: &SgfNode, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+			write!(f, ";")?;
+			for p in node.properties.iter() {
+				write!(f, "{}", p.ident)?;
+				for value in p.values.iter() {
+					write!(f, "[")?;
+					for c in value.chars() {
+						if c == ']' || c == '\\' {
+							write!(f, "\\")?;
+						}
+						write!(f, "{}", c)?;
+					}
+					write!(f, "]")?;
+				}
+			}
+			match node.children.as_slice() {
+				[] => Ok(()),
+				[child] => sequence(child, f),
+				children => {
+					for child in children {
+						write!(f, "\n{}", child)?;
+					}
+					Ok(())
+				}
+			}
+		}
+		write!(f, "(")?;
+		sequence(self, f)?;
+		write!(f, ")")
+	}
+}
+
+/// write the game to an SGF file
+pub fn save_game<P: AsRef<Path>>(path: P, game: &Game) -> Result<()> {
+	fs::write(path, SgfNode::from_game(game).to_string())?;
+	Ok(())
+}
+
+/// the first game of an SGF file
+pub fn load_game<P: AsRef<Path>>(path: P) -> Result<Game> {
+	let s = fs::read_to_string(path)?;
+	parse(&s)?[0].to_game()
+}
+
+/// Convert SGF point letters to a board position. SGF counts rows from the top,
+/// while positions count from the bottom. Returns `None` for passes.
+pub fn parse_point(value: &str, size: u8) -> Option<Pos> {
+	let bytes = value.as_bytes();
+	if bytes.len() != 2 {
+		return None;
+	}
+	let coord = |c: u8| match c {
+		b'a'..=b'z' => Some(c - b'a'),
+		b'A'..=b'Z' => Some(c - b'A' + 26),
+		_ => None,
+	};
+	let x = coord(bytes[0])?;
+	let row = coord(bytes[1])?;
+	// "tt" is an old way of writing a pass
+	if x >= size || row >= size {
+		return None;
+	}
+	Some(Pos {
+		x,
+		y: size - 1 - row,
+	})
+}
+
+/// Expand a list of points, which may use the compressed `aa:cc` rectangle
+/// form, as in `AB` / `AW` / `AE`
+pub fn parse_point_list(values: &[String], size: u8) -> Vec<Pos> {
+	let mut points: Vec<Pos> = vec![];
+	for value in values {
+		let mut corners = value.splitn(2, ':');
+		let from = corners.next().and_then(|v| parse_point(v, size));
+		let to = corners.next().and_then(|v| parse_point(v, size));
+		match (from, to) {
+			(Some(from), Some(to)) => {
+				for x in from.x.min(to.x)..=from.x.max(to.x) {
+					for y in from.y.min(to.y)..=from.y.max(to.y) {
+						points.push(Pos { x, y });
+					}
+				}
+			}
+			(Some(point), None) => points.push(point),
+			_ => {}
+		}
+	}
+	points
+}
+
+/// Convert a board position to SGF point letters, or nothing for a pass
+pub fn format_point(pos: Pos, size: u8) -> String {
+	if pos.x >= size || pos.y >= size {
+		return String::new();
+	}
+	let letter = |c: u8| {
+		if c < 26 {
+			(b'a' + c) as char
+		} else {
+			(b'A' + c - 26) as char
+		}
+	};
+	format!("{}{}", letter(pos.x), letter(size - 1 - pos.y))
+}
+
+/// Parse an SGF collection, returning the root node of every game tree in it.
+pub fn parse(s: &str) -> Result<Vec<SgfNode>> {
+	let mut parser = Parser {
+		chars: s.chars().collect(),
+		i: 0,
+	};
+	let mut trees: Vec<SgfNode> = vec![];
+	parser.skip_whitespace();
+	while parser.peek() == Some('(') {
+		trees.push(parser.game_tree()?);
+		parser.skip_whitespace();
+	}
+	if trees.is_empty() {
+		return Err(parser.error("no game tree found"));
+	}
+	Ok(trees)
+}
+
+struct Parser {
+	chars: Vec<char>,
+	i: usize,
+}
+
+impl Parser {
+	fn peek(&self) -> Option<char> {
+		self.chars.get(self.i).copied()
+	}
+
+	fn skip_whitespace(&mut self) {
+		while self.peek().map_or(false, |c| c.is_whitespace()) {
+			self.i += 1;
+		}
+	}
+
+	fn error(&self, reason: &str) -> BadukError {
+		BadukError::SgfParse {
+			reason: format!("{} at character {}", reason, self.i),
+		}
+	}
+
+	fn expect(&mut self, c: char) -> Result<()> {
+		self.skip_whitespace();
+		if self.peek() != Some(c) {
+			return Err(self.error(&format!("expected `{}`", c)));
+		}
+		self.i += 1;
+		Ok(())
+	}
+
+	/// `( sequence of nodes, then variations )`
+	fn game_tree(&mut self) -> Result<SgfNode> {
+		self.expect('(')?;
+		let mut sequence: Vec<SgfNode> = vec![];
+		self.skip_whitespace();
+		while self.peek() == Some(';') {
+			sequence.push(self.node()?);
+			self.skip_whitespace();
+		}
+		if sequence.is_empty() {
+			return Err(self.error("game tree has no nodes"));
+		}
+		let mut variations: Vec<SgfNode> = vec![];
+		while self.peek() == Some('(') {
+			variations.push(self.game_tree()?);
+			self.skip_whitespace();
+		}
+		self.expect(')')?;
+
+		// link the sequence up, each node becoming the only child of the last
+		let mut node = sequence.pop().unwrap();
+		node.children = variations;
+		while let Some(mut parent) = sequence.pop() {
+			parent.children = vec![node];
+			node = parent;
+		}
+		Ok(node)
+	}
+
+	fn node(&mut self) -> Result<SgfNode> {
+		self.expect(';')?;
+		let mut node = SgfNode::default();
+		loop {
+			self.skip_whitespace();
+			match self.peek() {
+				Some(c) if c.is_ascii_alphabetic() => {
+					node.properties.push(self.property()?);
+				}
+				_ => return Ok(node),
+			}
+		}
+	}
+
+	fn property(&mut self) -> Result<SgfProperty> {
+		let mut ident = String::new();
+		while let Some(c) = self.peek().filter(|c| c.is_ascii_alphabetic()) {
+			// FF[3] allowed lowercase letters in identifiers, they're ignored
+			if c.is_ascii_uppercase() {
+				ident.push(c);
+			}
+			self.i += 1;
+		}
+		let mut values: Vec<String> = vec![];
+		self.skip_whitespace();
+		while self.peek() == Some('[') {
+			values.push(self.value()?);
+			self.skip_whitespace();
+		}
+		if values.is_empty() {
+			return Err(self.error(&format!("property {} has no value", ident)));
+		}
+		Ok(SgfProperty { ident, values })
+	}
+
+	fn value(&mut self) -> Result<String> {
+		self.expect('[')?;
+		let mut value = String::new();
+		loop {
+			match self.peek() {
+				None => return Err(self.error("unterminated property value")),
+				Some(']') => {
+					self.i += 1;
+					return Ok(value);
+				}
+				Some('\\') => {
+					self.i += 1;
+					match self.peek() {
+						// escaped line breaks are removed
+						Some('\r') if self.chars.get(self.i + 1) == Some(&'\n') => {
+							self.i += 1;
+						}
+						Some('\n') | Some('\r') => {}
+						Some(c) => value.push(c),
+						None => return Err(self.error("unterminated property value")),
+					}
+					self.i += 1;
+				}
+				Some(c) => {
+					value.push(c);
+					self.i += 1;
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,175 @@
+use crate::{
+	board::*,
+	piece::*,
+	pos::Pos,
+};
+use std::fmt;
+
+/// The 8 symmetries of a square board (the dihedral group D4).
+/// Rotations are counter-clockwise, around the center of the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+	Identity,
+	Rotate90,
+	Rotate180,
+	Rotate270,
+	/// mirror left <-> right
+	FlipHorizontal,
+	/// mirror top <-> bottom
+	FlipVertical,
+	/// mirror along the bottom-left to top-right diagonal
+	FlipDiagonal,
+	/// mirror along the top-left to bottom-right diagonal
+	FlipAntiDiagonal,
+}
+
+impl Symmetry {
+	pub const ALL: [Symmetry; 8] = [
+		Symmetry::Identity,
+		Symmetry::Rotate90,
+		Symmetry::Rotate180,
+		Symmetry::Rotate270,
+		Symmetry::FlipHorizontal,
+		Symmetry::FlipVertical,
+		Symmetry::FlipDiagonal,
+		Symmetry::FlipAntiDiagonal,
+	];
+
+	/// map a position on a board of `size` x `size`
+	pub fn apply(self, pos: Pos, size: u8) -> Pos {
+		let Pos { x, y } = pos;
+		let max = size - 1;
+		let (x, y) = match self {
+			Symmetry::Identity => (x, y),
+			Symmetry::Rotate90 => (max - y, x),
+			Symmetry::Rotate180 => (max - x, max - y),
+			Symmetry::Rotate270 => (y, max - x),
+			Symmetry::FlipHorizontal => (max - x, y),
+			Symmetry::FlipVertical => (x, max - y),
+			Symmetry::FlipDiagonal => (y, x),
+			Symmetry::FlipAntiDiagonal => (max - y, max - x),
+		};
+		Pos { x, y }
+	}
+
+	/// map an offset relative to some point, rather than a position
+	pub fn apply_offset(self, offset: (i8, i8)) -> (i8, i8) {
+		let (x, y) = offset;
+		match self {
+			Symmetry::Identity => (x, y),
+			Symmetry::Rotate90 => (-y, x),
+			Symmetry::Rotate180 => (-x, -y),
+			Symmetry::Rotate270 => (y, -x),
+			Symmetry::FlipHorizontal => (-x, y),
+			Symmetry::FlipVertical => (x, -y),
+			Symmetry::FlipDiagonal => (y, x),
+			Symmetry::FlipAntiDiagonal => (-y, -x),
+		}
+	}
+
+	/// the symmetry equivalent to applying `self`, then `other`
+	pub fn then(self, other: Symmetry) -> Self {
+		let image = |s: Symmetry| (s.apply_offset((1, 0)), s.apply_offset((0, 1)));
+		let target = (
+			other.apply_offset(self.apply_offset((1, 0))),
+			other.apply_offset(self.apply_offset((0, 1))),
+		);
+		*Symmetry::ALL.iter().find(|s| image(**s) == target).unwrap()
+	}
+
+	/// the symmetry that undoes this one
+	pub fn inverse(self) -> Self {
+		match self {
+			Symmetry::Rotate90 => Symmetry::Rotate270,
+			Symmetry::Rotate270 => Symmetry::Rotate90,
+			s => s,
+		}
+	}
+}
+
+impl fmt::Display for Symmetry {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Symmetry::Identity => write!(f, "Identity"),
+			Symmetry::Rotate90 => write!(f, "Rotate 90°"),
+			Symmetry::Rotate180 => write!(f, "Rotate 180°"),
+			Symmetry::Rotate270 => write!(f, "Rotate 270°"),
+			Symmetry::FlipHorizontal => write!(f, "Flip horizontal"),
+			Symmetry::FlipVertical => write!(f, "Flip vertical"),
+			Symmetry::FlipDiagonal => write!(f, "Flip diagonal"),
+			Symmetry::FlipAntiDiagonal => write!(f, "Flip anti-diagonal"),
+		}
+	}
+}
+
+impl PlacedPiece {
+	pub fn transform(&self, symmetry: Symmetry, size: u8) -> Self {
+		if self.is_pass() {
+			return *self;
+		}
+		PlacedPiece {
+			piece: self.piece,
+			pos: symmetry.apply(self.pos, size),
+		}
+	}
+
+	pub fn invert_color(&self) -> Self {
+		PlacedPiece::new(self.piece.color.opposite(), self.pos)
+	}
+}
+
+/// A board in canonical orientation, see `Board::canonical`
+#[derive(Clone)]
+pub struct Canonical {
+	pub board: Board,
+	/// `original.transform(symmetry)` gives the canonical board
+	pub symmetry: Symmetry,
+	/// every symmetry that gives the canonical board, starting with `symmetry`.
+	/// There is more than one if the original board is itself symmetric.
+	pub symmetries: Vec<Symmetry>,
+	pub hash: u64,
+}
+
+impl Board {
+	/// The lexicographically smallest of the 8 rotations / reflections of this
+	/// board. Boards that are symmetric to each other share a canonical form,
+	/// so its hash can be used to find the same position in any orientation.
+	/// Colors are left as they are; use `invert_colors` first if needed.
+	pub fn canonical(&self) -> Canonical {
+		let mut best: Option<(Vec<u8>, Board, Vec<Symmetry>)> = None;
+		for symmetry in Symmetry::ALL.iter() {
+			let board = self.transform(*symmetry);
+			let key = board.cells();
+			match &mut best {
+				Some((best_key, _, symmetries)) if key == *best_key => {
+					symmetries.push(*symmetry);
+				}
+				Some((best_key, _, _)) if key > *best_key => {}
+				_ => best = Some((key, board, vec![*symmetry])),
+			}
+		}
+		let (_, board, symmetries) = best.unwrap();
+		Canonical {
+			hash: board.hash(),
+			board,
+			symmetry: symmetries[0],
+			symmetries,
+		}
+	}
+
+	/// every intersection, column by column: 0 empty, 1 black, 2 white
+	fn cells(&self) -> Vec<u8> {
+		let mut cells =
+			Vec::with_capacity(self.size() as usize * self.size() as usize);
+		for i in 0..self.size() {
+			for j in 0..self.size() {
+				cells.push(match self.get_color(Pos { x: i, y: j }).unwrap() {
+					None => 0,
+					Some(Black) => 1,
+					Some(White) => 2,
+				});
+			}
+		}
+		cells
+	}
+}
@@ -3,6 +3,7 @@ use crate::{
 	board::*,
 	error::*,
 	piece::*,
+	pos::Pos,
 };
 
 #[test]
@@ -31,49 +32,82 @@ fn test_adjacents() -> Result<()> {
 
 	println!(
 		"{:?}",
-		board.adjacents((9, 10)).map(|a| a.as_type()).to_array()
+		board
+			.adjacents(Pos::new(9, 10)?)
+			.map(|a| a.as_type())
+			.to_array()
 	);
 	println!(
 		"{:?}",
-		board.adjacents((9, 9)).map(|a| a.as_type()).to_array()
+		board
+			.adjacents(Pos::new(9, 9)?)
+			.map(|a| a.as_type())
+			.to_array()
 	);
 	println!(
 		"{:?}",
-		board.adjacents((9, 8)).map(|a| a.as_type()).to_array()
+		board
+			.adjacents(Pos::new(9, 8)?)
+			.map(|a| a.as_type())
+			.to_array()
 	);
 	println!("=====");
 	println!(
 		"{:?}",
-		board.adjacents((0, 0)).map(|a| a.as_type()).to_array()
+		board
+			.adjacents(Pos::new(0, 0)?)
+			.map(|a| a.as_type())
+			.to_array()
 	);
 	println!(
 		"{:?}",
-		board.adjacents((9, 0)).map(|a| a.as_type()).to_array()
+		board
+			.adjacents(Pos::new(9, 0)?)
+			.map(|a| a.as_type())
+			.to_array()
 	);
 
 	assert_eq!(
-		board.adjacents((9, 9)).map(|a| a.as_type()).to_array(),
+		board
+			.adjacents(Pos::new(9, 9)?)
+			.map(|a| a.as_type())
+			.to_array(),
 		[PAT::Piece, PAT::Piece, PAT::Empty, PAT::Empty]
 	);
 	assert_eq!(
-		board.adjacents((9, 8)).map(|a| a.as_type()).to_array(),
+		board
+			.adjacents(Pos::new(9, 8)?)
+			.map(|a| a.as_type())
+			.to_array(),
 		[PAT::Piece, PAT::Empty, PAT::Empty, PAT::Empty]
 	);
 	assert_eq!(
-		board.adjacents((9, 10)).map(|a| a.as_type()).to_array(),
+		board
+			.adjacents(Pos::new(9, 10)?)
+			.map(|a| a.as_type())
+			.to_array(),
 		[PAT::Empty, PAT::Piece, PAT::Empty, PAT::Empty]
 	);
 	assert_eq!(
-		board.adjacents((0, 0)).map(|a| a.as_type()).to_array(),
+		board
+			.adjacents(Pos::new(0, 0)?)
+			.map(|a| a.as_type())
+			.to_array(),
 		[PAT::Empty, PAT::Edge, PAT::Edge, PAT::Empty]
 	);
 	assert_eq!(
-		board.adjacents((9, 0)).map(|a| a.as_type()).to_array(),
+		board
+			.adjacents(Pos::new(9, 0)?)
+			.map(|a| a.as_type())
+			.to_array(),
 		[PAT::Empty, PAT::Edge, PAT::Empty, PAT::Empty]
 	);
 
 	assert_eq!(
-		board.adjacents((15, 15)).map(|a| a.as_type()).to_array(),
+		board
+			.adjacents(Pos::new(15, 15)?)
+			.map(|a| a.as_type())
+			.to_array(),
 		[PAT::Empty, PAT::Empty, PAT::Empty, PAT::Empty]
 	);
 
@@ -99,13 +133,13 @@ fn test_shape() -> Result<()> {
 	])?;
 
 	println!("{}", board);
-	assert_eq!(board.shape((9, 9)).len(), 4);
-	assert_eq!(board.shape((0, 1)).len(), 2);
-	assert_eq!(board.shape((7, 6)).len(), 1);
-	assert_eq!(board.shape((15, 16)).len(), 2);
-	assert_eq!(board.shape((16, 16)).len(), 2);
-	assert_eq!(board.shape((15, 17)).len(), 1);
-	assert_eq!(board.shape((16, 17)).len(), 1);
+	assert_eq!(board.shape(Pos::new(9, 9)?).len(), 4);
+	assert_eq!(board.shape(Pos::new(0, 1)?).len(), 2);
+	assert_eq!(board.shape(Pos::new(7, 6)?).len(), 1);
+	assert_eq!(board.shape(Pos::new(15, 16)?).len(), 2);
+	assert_eq!(board.shape(Pos::new(16, 16)?).len(), 2);
+	assert_eq!(board.shape(Pos::new(15, 17)?).len(), 1);
+	assert_eq!(board.shape(Pos::new(16, 17)?).len(), 1);
 
 	Ok(())
 }
@@ -116,21 +150,21 @@ fn test_remove_shape() -> Result<()> {
 
 	for i in 0..10u8 {
 		for j in 0..10u8 {
-			board.do_move(PlacedPiece::new(White, (i, j)))?;
+			board.do_move(PlacedPiece::new(White, Pos::new(i, j)?))?;
 		}
-		board.do_move(PlacedPiece::new(Black, (i, 10)))?;
-		board.do_move(PlacedPiece::new(White, (i, 11)))?;
+		board.do_move(PlacedPiece::new(Black, Pos::new(i, 10)?))?;
+		board.do_move(PlacedPiece::new(White, Pos::new(i, 11)?))?;
 	}
 	println!("{}", board);
 	assert_eq!(board.num_pieces(White), 110);
-	board.remove_shape((0, 0));
+	board.remove_shape(Pos::new(0, 0)?);
 	println!("{}", board);
 	assert_eq!(board.num_pieces(White), 10);
 	assert_eq!(board.num_pieces(Black), 10);
-	board.remove_shape((9, 10));
+	board.remove_shape(Pos::new(9, 10)?);
 	println!("{}", board);
 	assert_eq!(board.num_pieces(Black), 0);
-	board.remove_shape((9, 11));
+	board.remove_shape(Pos::new(9, 11)?);
 	println!("{}", board);
 	assert_eq!(board.num_pieces_all(), 0);
 
@@ -142,13 +176,13 @@ fn test_liberties() -> Result<()> {
 	let mut board = Board::new();
 
 	board.do_moves_builder(vec![(White, 0, 0), (White, 0, 9), (White, 9, 9)])?;
-	assert_eq!(board.liberties_shape((0, 0)).len(), 2);
-	assert_eq!(board.liberties_shape((0, 9)).len(), 3);
-	assert_eq!(board.liberties_shape((9, 9)).len(), 4);
+	assert_eq!(board.liberties_shape(Pos::new(0, 0)?).len(), 2);
+	assert_eq!(board.liberties_shape(Pos::new(0, 9)?).len(), 3);
+	assert_eq!(board.liberties_shape(Pos::new(9, 9)?).len(), 4);
 	println!("{}", board);
 
 	board.do_moves_builder(vec![(Black, 0, 1), (Black, 1, 0)])?;
-	assert_eq!(board.liberties_shape((0, 0)).len(), 0);
+	assert_eq!(board.liberties_shape(Pos::new(0, 0)?).len(), 0);
 	println!("{}", board);
 
 	board.do_moves_builder(vec![
@@ -162,20 +196,20 @@ fn test_liberties() -> Result<()> {
 		(White, 17, 7),
 	])?;
 	println!("{}", board);
-	assert_eq!(board.liberties_shape((18, 4)).len(), 2);
-	assert_eq!(board.liberties_shape((18, 5)).len(), 2);
-	assert_eq!(board.liberties_shape((18, 6)).len(), 2);
-	assert_eq!(board.liberties_shape((18, 7)).len(), 2);
+	assert_eq!(board.liberties_shape(Pos::new(18, 4)?).len(), 2);
+	assert_eq!(board.liberties_shape(Pos::new(18, 5)?).len(), 2);
+	assert_eq!(board.liberties_shape(Pos::new(18, 6)?).len(), 2);
+	assert_eq!(board.liberties_shape(Pos::new(18, 7)?).len(), 2);
 	board.do_moves_builder(vec![(White, 18, 8)])?;
-	assert_eq!(board.liberties_shape((18, 4)).len(), 1);
-	assert_eq!(board.liberties_shape((18, 5)).len(), 1);
-	assert_eq!(board.liberties_shape((18, 6)).len(), 1);
-	assert_eq!(board.liberties_shape((18, 7)).len(), 1);
-	board.set(PlacedPiece::new(White, (18, 3)));
-	assert_eq!(board.liberties_shape((18, 4)).len(), 0);
-	assert_eq!(board.liberties_shape((18, 5)).len(), 0);
-	assert_eq!(board.liberties_shape((18, 6)).len(), 0);
-	assert_eq!(board.liberties_shape((18, 7)).len(), 0);
+	assert_eq!(board.liberties_shape(Pos::new(18, 4)?).len(), 1);
+	assert_eq!(board.liberties_shape(Pos::new(18, 5)?).len(), 1);
+	assert_eq!(board.liberties_shape(Pos::new(18, 6)?).len(), 1);
+	assert_eq!(board.liberties_shape(Pos::new(18, 7)?).len(), 1);
+	board.set(PlacedPiece::new(White, Pos::new(18, 3)?))?;
+	assert_eq!(board.liberties_shape(Pos::new(18, 4)?).len(), 0);
+	assert_eq!(board.liberties_shape(Pos::new(18, 5)?).len(), 0);
+	assert_eq!(board.liberties_shape(Pos::new(18, 6)?).len(), 0);
+	assert_eq!(board.liberties_shape(Pos::new(18, 7)?).len(), 0);
 	println!("{}", board);
 
 	Ok(())
@@ -190,11 +224,11 @@ fn test_capture() -> Result<()> {
 		(Black, 9, 10),
 		(Black, 8, 9),
 	])?;
-	assert_eq!(board.get_color((9, 9)), Some(White));
+	assert_eq!(board.get_color(Pos::new(9, 9)?)?, Some(White));
 	println!("{}", board);
 
 	board.do_moves_builder(vec![(Black, 10, 9)])?;
-	assert_eq!(board.get_color((9, 9)), None);
+	assert_eq!(board.get_color(Pos::new(9, 9)?)?, None);
 	println!("{}", board);
 
 	Ok(())
@@ -224,3 +258,97 @@ fn test_no_self_capture() -> Result<()> {
 	println!("{}", board);
 	Ok(())
 }
+
+#[test]
+fn test_out_of_bounds() -> Result<()> {
+	let mut board = Board::with_size(9)?;
+	let outside = vec![Pos { x: 30, y: 0 }, Pos { x: 9, y: 4 }, Pos::new(4, 12)?];
+	for pos in outside {
+		println!("{:?}", pos);
+		assert!(!board.in_bounds(pos));
+		assert!(matches!(
+			board.get(pos),
+			Err(BadukError::PosOutOfBounds { .. })
+		));
+		assert!(matches!(
+			board.do_move(PlacedPiece::new(Black, pos)),
+			Err(BadukError::PosOutOfBounds { .. })
+		));
+		assert!(board.set(PlacedPiece::new(Black, pos)).is_err());
+		assert!(board.remove(pos).is_err());
+		assert!(board.shape(pos).is_empty());
+	}
+	assert!(board.do_moves_builder(vec![(Black, 4, 9)]).is_err());
+	assert_eq!(board.num_pieces_all(), 0);
+
+	// the last row and column are still on the board
+	board.do_moves_builder(vec![(Black, 8, 8)])?;
+	assert_eq!(board.get_color(Pos::new(8, 8)?)?, Some(Black));
+	assert!(Pos::new(19, 0).is_err());
+
+	Ok(())
+}
+
+#[test]
+fn test_from_ascii() -> Result<()> {
+	// the white stone in the corner has no liberties left
+	let mut board = Board::from_ascii(
+		"
+		O X . . .
+		X . . . .
+		. . + . .
+		. . . . .
+		. . . O X
+		",
+	)?;
+	println!("{}", board);
+	assert_eq!(board.size(), 5);
+	assert_eq!(board.num_pieces_all(), 5);
+	assert_eq!(board.get_color(Pos::new(0, 4)?)?, Some(White));
+	assert_eq!(board.get_color(Pos::new(1, 4)?)?, Some(Black));
+	assert_eq!(board.get_color(Pos::new(4, 0)?)?, Some(Black));
+	assert_eq!(board.liberties_shape(Pos::new(0, 4)?).len(), 0);
+
+	// moves from there are played by the rules
+	board.do_move(PlacedPiece::new(White, Pos::new(4, 1)?))?;
+	assert_eq!(board.get_color(Pos::new(4, 0)?)?, None);
+	Ok(())
+}
+
+#[test]
+fn test_ascii_round_trip() -> Result<()> {
+	let mut board = Board::with_size(9)?;
+	board.do_moves_builder(vec![
+		(Black, 2, 2),
+		(White, 6, 6),
+		(Black, 4, 4),
+		(White, 0, 8),
+	])?;
+	let ascii = board.to_ascii();
+	println!("{}", ascii);
+	assert!(ascii.starts_with("   A B C D E F G H J\n 9 O . . . . . . . . 9\n"));
+	assert!(ascii.contains(" 3 . . X . . . + . . 3\n"));
+	assert_eq!(Board::from_ascii(&ascii)?.hash(), board.hash());
+
+	// the colored output of `Display` reads back too
+	let display = format!("{}", board);
+	assert_eq!(Board::from_ascii(&display)?.hash(), board.hash());
+	colored::control::set_override(true);
+	let display = format!("{}", board);
+	colored::control::unset_override();
+	assert_eq!(Board::from_ascii(&display)?.hash(), board.hash());
+
+	let full = Board::new();
+	assert_eq!(Board::from_ascii(&full.to_ascii())?.size(), 19);
+	Ok(())
+}
+
+#[test]
+fn test_from_ascii_errors() {
+	assert!(Board::from_ascii("").is_err());
+	assert!(Board::from_ascii("X .\n. .\n. .").is_err());
+	assert!(Board::from_ascii("X .\n. . O").is_err());
+	assert!(Board::from_ascii("X Q\n. .").is_err());
+	let too_big = vec![". ".repeat(20); 20].join("\n");
+	assert!(Board::from_ascii(&too_big).is_err());
+}
@@ -0,0 +1,38 @@
+use crate::{
+	board::*,
+	bot::*,
+	error::*,
+	game::*,
+	piece::*,
+	pos::Pos,
+};
+
+#[test]
+fn test_bot_plays_legal_moves() -> Result<()> {
+	let mut game = Game::with_size(9, 0)?;
+	for _ in 0..10 {
+		let m = choose_move(&game).unwrap();
+		assert_eq!(m.piece.color, game.current_turn_color());
+		game.do_move(m)?;
+	}
+	println!("{}", game);
+	assert_eq!(game.len(), 10);
+	Ok(())
+}
+
+#[test]
+fn test_bot_keeps_its_eyes() -> Result<()> {
+	// black walls off the corner, leaving a single eye at (0, 0)
+	let mut game = Game::with_size(5, 0)?;
+	game.do_moves_builder(vec![
+		(Black, 1, 0),
+		(White, 4, 4),
+		(Black, 0, 1),
+		(White, 3, 4),
+		(Black, 1, 1),
+		(White, 4, 3),
+	])?;
+	let m = choose_move(&game).unwrap();
+	assert_ne!(m.pos, Pos { x: 0, y: 0 });
+	Ok(())
+}
@@ -0,0 +1,184 @@
+use crate::{
+	clock::*,
+	error::*,
+	game::*,
+	game_result::*,
+	piece::*,
+};
+use std::time::Duration;
+
+fn secs(s: u64) -> Duration {
+	Duration::from_secs(s)
+}
+
+fn clock(control: TimeControl) -> (Clock, ManualTime) {
+	let time = ManualTime::new();
+	let mut clock = Clock::new(control, Box::new(time.clone()));
+	clock.start(Black);
+	(clock, time)
+}
+
+#[test]
+fn test_parse_time_control() -> Result<()> {
+	assert_eq!(
+		"absolute:10m".parse::<TimeControl>()?,
+		TimeControl::Absolute { main: secs(600) }
+	);
+	assert_eq!(
+		"fischer:5m+10s".parse::<TimeControl>()?,
+		TimeControl::Fischer {
+			main: secs(300),
+			increment: secs(10),
+		}
+	);
+	assert_eq!(
+		"byoyomi:1h+5x30s".parse::<TimeControl>()?,
+		TimeControl::ByoYomi {
+			main: secs(3600),
+			period: secs(30),
+			periods: 5,
+		}
+	);
+	assert_eq!(
+		"canadian:0+25/5m".parse::<TimeControl>()?,
+		TimeControl::Canadian {
+			main: secs(0),
+			period: secs(300),
+			stones: 25,
+		}
+	);
+	for bad in &[
+		"",
+		"absolute",
+		"fischer:5m",
+		"byoyomi:5m+0x30s",
+		"hourglass:5m",
+	] {
+		assert!(bad.parse::<TimeControl>().is_err(), "parsed `{}`", bad);
+	}
+	Ok(())
+}
+
+#[test]
+fn test_format_time_control() -> Result<()> {
+	for spec in &[
+		"absolute:10m",
+		"fischer:5m+10s",
+		"byoyomi:1h+5x30s",
+		"canadian:0s+25/5m",
+		"absolute:90s",
+	] {
+		assert_eq!(spec.parse::<TimeControl>()?.to_string(), *spec);
+	}
+	Ok(())
+}
+
+#[test]
+fn test_absolute() -> Result<()> {
+	let (mut clock, time) = clock(TimeControl::Absolute { main: secs(60) });
+	time.advance(secs(20));
+	clock.press(Black);
+	assert_eq!(clock.running(), Some(White));
+	assert_eq!(clock.remaining(Black).unwrap().main, secs(40));
+	time.advance(secs(59));
+	assert_eq!(clock.flagged(), None);
+	println!("{}", clock.remaining(White).unwrap());
+	assert_eq!(clock.remaining(White).unwrap().to_string(), "0:01");
+	time.advance(secs(1));
+	assert_eq!(clock.flagged(), Some(White));
+	assert_eq!(clock.remaining(White), None);
+	Ok(())
+}
+
+#[test]
+fn test_fischer() -> Result<()> {
+	let (mut clock, time) = clock(TimeControl::Fischer {
+		main: secs(60),
+		increment: secs(10),
+	});
+	time.advance(secs(5));
+	clock.press(Black);
+	assert_eq!(clock.remaining(Black).unwrap().main, secs(65));
+	time.advance(secs(70));
+	assert_eq!(clock.flagged(), Some(White));
+	Ok(())
+}
+
+#[test]
+fn test_byo_yomi() -> Result<()> {
+	let (mut clock, time) = clock(TimeControl::ByoYomi {
+		main: secs(60),
+		period: secs(30),
+		periods: 3,
+	});
+	// into the first period, which is given back by moving
+	time.advance(secs(80));
+	let black = clock.remaining(Black).unwrap();
+	assert!(black.in_overtime());
+	assert_eq!((black.overtime, black.periods), (secs(10), 3));
+	println!("{}", black);
+	clock.press(Black);
+	assert_eq!(clock.remaining(Black).unwrap().overtime, secs(30));
+
+	clock.press(White);
+	// use up two periods
+	time.advance(secs(65));
+	let black = clock.remaining(Black).unwrap();
+	assert_eq!((black.overtime, black.periods), (secs(25), 1));
+	assert_eq!(black.to_string(), "0:25 (1)");
+	clock.press(Black);
+	clock.press(White);
+	time.advance(secs(30));
+	assert_eq!(clock.flagged(), Some(Black));
+	Ok(())
+}
+
+#[test]
+fn test_canadian() -> Result<()> {
+	let (mut clock, time) = clock(TimeControl::Canadian {
+		main: secs(0),
+		period: secs(60),
+		stones: 2,
+	});
+	time.advance(secs(20));
+	clock.press(Black);
+	clock.press(White);
+	let black = clock.remaining(Black).unwrap();
+	assert_eq!((black.overtime, black.stones), (secs(40), 1));
+	assert_eq!(black.to_string(), "0:40 /1");
+	// the block is done, and a new one starts
+	time.advance(secs(30));
+	clock.press(Black);
+	clock.press(White);
+	let black = clock.remaining(Black).unwrap();
+	assert_eq!((black.overtime, black.stones), (secs(60), 2));
+	time.advance(secs(60));
+	assert_eq!(clock.flagged(), Some(Black));
+	Ok(())
+}
+
+#[test]
+fn test_game_on_time() -> Result<()> {
+	let time = ManualTime::new();
+	let mut game = Game::new(0);
+	game.set_clock(Clock::new(
+		TimeControl::Absolute { main: secs(60) },
+		Box::new(time.clone()),
+	));
+	game.do_moves_builder(vec![(Black, 3, 3), (White, 15, 15)])?;
+	time.advance(secs(30));
+	game.do_moves_builder(vec![(Black, 3, 15)])?;
+	time.advance(secs(60));
+	let result = game.check_time();
+	println!("{:?}", result);
+	assert_eq!(result, Some(GameResult::Time { winner: Black }));
+	assert_eq!(result.unwrap().to_string(), "B+T");
+	match game.do_moves_builder(vec![(White, 15, 3)]) {
+		Err(BadukError::InvalidMove {
+			source: InvalidMoveError::GameOver { .. },
+		}) => {}
+		other => panic!("expected the game to be over, got {:?}", other),
+	}
+	assert_eq!(game.len(), 3);
+	Ok(())
+}
@@ -0,0 +1,140 @@
+use crate::{
+	board::*,
+	db::*,
+	error::*,
+	joseki::Corner,
+	piece::*,
+	symmetry::Symmetry,
+};
+
+const GAMES: &str = "
+(;GM[1]FF[4]SZ[19]PB[Alice]PW[Bob]RE[B+3.5];B[pd];W[dp];B[pp];W[dd])
+(;GM[1]FF[4]SZ[19]PB[Carol]PW[Dave]RE[W+R];B[dp];W[pd];B[dd];W[pq])
+(;GM[1]FF[4]SZ[19]PB[Erin]PW[Frank]RE[B+R];B[pd];W[dp];B[pp];W[cd])
+";
+
+#[test]
+fn test_db_position_search() -> Result<()> {
+	let mut db = Database::new();
+	assert_eq!(db.add_sgf("games.sgf", GAMES)?, 3);
+	assert_eq!(db.games().len(), 3);
+	assert_eq!(db.games()[1].black, "Carol");
+	assert_eq!(db.games()[1].winner(), Some(White));
+
+	// the empty board was reached in every game
+	let result = db.search_position(&Board::new());
+	assert_eq!(result.occurrences.len(), 3);
+	// pd and dp are the same opening move, up to symmetry
+	assert_eq!(result.continuations.len(), 1);
+	assert_eq!(result.continuations[0].count, 3);
+	assert_eq!(result.continuations[0].win_rate(), Some(2.0 / 3.0));
+
+	// after B pd W dp B pp, in a different orientation. The second game
+	// reached the same position, rotated and in another move order.
+	let mut board = Board::new();
+	board.do_moves_builder(vec![
+		(Black, 15, 15),
+		(White, 3, 3),
+		(Black, 15, 3),
+	])?;
+	let board = board.transform(Symmetry::Rotate90);
+	let result = db.search_position(&board);
+	assert_eq!(result.occurrences.len(), 3);
+	assert!(result.occurrences.iter().all(|o| o.move_number == 3));
+	assert_eq!(result.continuations.len(), 3);
+	for c in result.continuations.iter() {
+		assert_eq!(c.count, 1);
+		assert_eq!(c.next.piece.color, White);
+		// all played in the empty corner, seen in the rotated orientation
+		let pos = Symmetry::Rotate90.inverse().apply(c.next.pos, 19);
+		let (x, y) = (pos.x, pos.y);
+		assert!(x <= 3 && y >= 15);
+	}
+	let wins: Vec<Option<f32>> =
+		result.continuations.iter().map(|c| c.win_rate()).collect();
+	assert_eq!(wins.iter().filter(|w| **w == Some(1.0)).count(), 1);
+	assert_eq!(wins.iter().filter(|w| **w == Some(0.0)).count(), 2);
+
+	let mut unknown = Board::new();
+	unknown.do_moves_builder(vec![(Black, 9, 9)])?;
+	assert_eq!(db.search_position(&unknown).occurrences.len(), 0);
+
+	Ok(())
+}
+
+#[test]
+fn test_db_corner_search() -> Result<()> {
+	let mut db = Database::new();
+	db.add_sgf("games.sgf", GAMES)?;
+
+	// a lone black 4-4 point appears in some corner of every game
+	let mut board = Board::new();
+	board.do_moves_builder(vec![(Black, 3, 3)])?;
+	let result = db.search_corner(&board, Corner::BottomLeft);
+	assert_eq!(result.occurrences.len(), 3);
+
+	// white 4-4 next to black's 4-4 only in the second game
+	let mut board = Board::new();
+	board.do_moves_builder(vec![(White, 15, 3)])?;
+	let result = db.search_corner(&board, Corner::BottomRight);
+	assert_eq!(result.occurrences.len(), 3);
+	assert_eq!(
+		db.search_corner(&board, Corner::TopLeft).occurrences.len(),
+		0
+	);
+
+	Ok(())
+}
+
+#[test]
+fn test_db_save_load() -> Result<()> {
+	let mut db = Database::new();
+	db.add_sgf("games.sgf", GAMES)?;
+
+	let path = std::env::temp_dir().join("bevy_baduk_test_db.bin");
+	db.save(&path)?;
+	let loaded = Database::load(&path)?;
+	std::fs::remove_file(&path)?;
+
+	assert_eq!(loaded.games(), db.games());
+	assert_eq!(loaded.num_positions(), db.num_positions());
+	let mut board = Board::new();
+	board.do_moves_builder(vec![(Black, 15, 15), (White, 3, 3)])?;
+	let (a, b) = (db.search_position(&board), loaded.search_position(&board));
+	assert_eq!(a.occurrences, b.occurrences);
+	assert_eq!(a.continuations, b.continuations);
+
+	// a game whose path claims to be 4 GiB long
+	let mut bytes = b"BADUKDB\0".to_vec();
+	bytes.push(1);
+	bytes.extend_from_slice(&1u32.to_le_bytes());
+	bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+	bytes.extend_from_slice(b"games.sgf");
+	std::fs::write(&path, &bytes)?;
+	assert!(Database::load(&path).is_err());
+	std::fs::remove_file(&path)?;
+
+	Ok(())
+}
+
+#[test]
+fn test_db_index_dir() -> Result<()> {
+	let dir = std::env::temp_dir().join("bevy_baduk_test_index");
+	let _ = std::fs::remove_dir_all(&dir);
+	std::fs::create_dir_all(dir.join("nested"))?;
+	std::fs::write(dir.join("a.sgf"), GAMES)?;
+	std::fs::write(dir.join("nested").join("b.SGF"), "(;SZ[9];B[ee])")?;
+	std::fs::write(dir.join("broken.sgf"), "(;B[ee]")?;
+	std::fs::write(dir.join("notes.txt"), "not a game")?;
+
+	let mut db = Database::new();
+	let report = db.index_dir(&dir)?;
+	std::fs::remove_dir_all(&dir)?;
+
+	assert_eq!(report.files, 3);
+	assert_eq!(report.games, 4);
+	assert_eq!(report.errors.len(), 1);
+	assert_eq!(db.games()[3].size, 9);
+
+	Ok(())
+}
@@ -4,6 +4,7 @@ use crate::{
 	error::*,
 	game::*,
 	piece::*,
+	pos::Pos,
 };
 
 #[test]
@@ -45,9 +46,70 @@ fn test_handicap() {
 		println!("handicap: {}", i);
 		println!("{}", game);
 		assert_eq!(i as u16, game.board.num_pieces_all());
+		let first = if i > 0 { White } else { Black };
+		assert_eq!(game.current_turn_color(), first);
 	}
 }
 
+#[test]
+fn test_handicap_one() -> Result<()> {
+	// the one stone is black's first move, so white answers it
+	let mut game = Game::new(1);
+	assert_eq!(game.current_turn_color(), White);
+	assert!(game.do_move(PlacedPiece::new(Black, Pos::new(15, 15)?)).is_err());
+	game.do_move(PlacedPiece::new(White, Pos::new(15, 15)?))?;
+	assert_eq!(game.current_turn_color(), Black);
+	Ok(())
+}
+
+#[test]
+fn test_handicap_small_board() -> Result<()> {
+	let game = Game::with_size(9, 5)?;
+	println!("{}", game);
+	assert_eq!(game.board.num_pieces_all(), 5);
+	for &(x, y) in &[(2, 2), (6, 6), (2, 6), (6, 2), (4, 4)] {
+		assert_eq!(game.board.get_color(Pos { x, y })?, Some(Black));
+	}
+	assert_eq!(
+		Game::with_size(13, 4)?
+			.board
+			.get_color(Pos { x: 9, y: 9 })?,
+		Some(Black)
+	);
+
+	// no middle point on an even board, and no star points on a tiny one
+	assert!(Game::with_size(10, 5).is_err());
+	assert!(Game::with_size(10, 4).is_ok());
+	assert!(Game::with_size(7, 1).is_err());
+	assert!(Game::with_size(7, 0).is_ok());
+	Ok(())
+}
+
+#[test]
+fn test_offset_turn() -> Result<()> {
+	let mut game = Game::with_size(9, 0)?;
+	// nothing to step through yet
+	game.offset_turn(-1);
+	assert_eq!(game.position(), GamePosition::Current);
+
+	game.do_moves_builder(vec![(Black, 0, 0), (White, 0, 1), (Black, 1, 1)])?;
+	game.offset_turn(-10);
+	assert_eq!(game.position(), GamePosition::Past(0));
+	assert_eq!(game.board.num_pieces_all(), 0);
+	game.offset_turn(2);
+	assert_eq!(game.position(), GamePosition::Past(2));
+	assert_eq!(game.board.num_pieces_all(), 2);
+	game.offset_turn(10);
+	assert_eq!(game.position(), GamePosition::Current);
+	assert_eq!(game.board.num_pieces_all(), 3);
+
+	game.set_turn(1)?;
+	assert_eq!(game.current_turn(), 1);
+	game.set_turn(100)?;
+	assert_eq!(game.position(), GamePosition::Current);
+	Ok(())
+}
+
 #[test]
 fn test_move_timeline() -> Result<()> {
 	let mut game = Game::new(0);
@@ -61,7 +123,7 @@ fn test_move_timeline() -> Result<()> {
 
 	game.do_move(PlacedPiece {
 		piece: Piece { color: White },
-		pos: (9, 9),
+		pos: Pos::new(9, 9)?,
 	})?;
 	assert_eq!(game.board.num_pieces_all(), 1);
 
@@ -79,3 +141,113 @@ fn test_move_timeline() -> Result<()> {
 
 	Ok(())
 }
+
+#[test]
+fn test_undo() -> Result<()> {
+	let mut game = Game::new(0);
+	assert!(game.undo().is_err());
+	// a ko in the top left corner
+	game.do_moves_builder(vec![
+		(Black, 0, 1),
+		(White, 0, 2),
+		(Black, 2, 1),
+		(White, 2, 2),
+		(Black, 1, 0),
+		(White, 1, 3),
+		(Black, 10, 10),
+		(White, 1, 1),
+		(Black, 1, 2),
+	])?;
+	println!("{}", game);
+	assert!(game.board.get_color(Pos::new(1, 1)?)?.is_none());
+	let retake = PlacedPiece::new(White, Pos::new(1, 1)?);
+	assert!(game.do_move(retake).is_err());
+
+	// taking back the capture brings the white stone back
+	assert_eq!(game.undo()?, PlacedPiece::new(Black, Pos::new(1, 2)?));
+	assert_eq!(game.board.get_color(Pos::new(1, 1)?)?, Some(White));
+	assert!(game.board.get_color(Pos::new(1, 2)?)?.is_none());
+	assert_eq!(game.current_turn_color(), Black);
+	game.do_moves_builder(vec![(Black, 1, 2)])?;
+
+	// after a ko threat and its answer white can retake, until they are
+	// taken back and the ko is as it was
+	game.do_moves_builder(vec![(White, 15, 15), (Black, 15, 3)])?;
+	assert!(game.board.valid_move(retake).is_ok());
+	game.undo()?;
+	game.undo()?;
+	assert!(game.do_move(retake).is_err());
+	assert_eq!(game.len(), 9);
+	Ok(())
+}
+
+#[test]
+fn test_undo_handicap() -> Result<()> {
+	let mut game = Game::new(2);
+	game.do_moves_builder(vec![(White, 9, 9), (Black, 10, 10)])?;
+	game.undo()?;
+	println!("{}", game);
+	assert_eq!(game.board.num_pieces_all(), 3);
+	assert_eq!(game.get_board_at_move(0)?.num_pieces_all(), 2);
+
+	// looking back at the move that is taken back
+	game.set_position(GamePosition::Past(1))?;
+	game.undo()?;
+	assert_eq!(game.position(), GamePosition::Current);
+	assert_eq!(game.board.num_pieces_all(), 2);
+	Ok(())
+}
+
+#[test]
+fn test_move_result() -> Result<()> {
+	let mut game = Game::new(0);
+	game.do_moves_builder(vec![
+		(Black, 1, 0),
+		(White, 0, 0),
+		(Black, 10, 10),
+		(White, 15, 15),
+		(Black, 0, 1),
+	])?;
+	let (m, captured) = game.move_result(5)?;
+	println!("{:?} captured {:?}", m, captured);
+	assert_eq!(m, PlacedPiece::new(Black, Pos::new(0, 1)?));
+	assert_eq!(
+		captured.into_iter().collect::<Vec<_>>(),
+		vec![Pos::new(0, 0)?]
+	);
+	assert!(game.move_result(4)?.1.is_empty());
+	assert!(game.move_result(0).is_err());
+	assert!(game.move_result(6).is_err());
+	Ok(())
+}
+
+#[test]
+fn test_set_up() -> Result<()> {
+	let mut game = Game::with_size(9, 2)?;
+	assert_eq!(game.current_turn_color(), White);
+	game.do_moves_builder(vec![(White, 4, 4)])?;
+	game.set_up(
+		vec![
+			PlacedPiece::new(Black, Pos::new(0, 1)?),
+			PlacedPiece::new(Black, Pos::new(1, 0)?),
+			PlacedPiece::new(White, Pos::new(0, 0)?),
+		],
+		White,
+	)?;
+	println!("{}", game);
+	assert_eq!(game.len(), 0);
+	assert_eq!(game.handicap, 2);
+	assert_eq!(game.setup().map(|s| s.len()), Some(3));
+	assert_eq!(game.first_turn(), White);
+	assert_eq!(game.current_turn_color(), White);
+	// the white stone has no liberties, but nothing was captured
+	assert_eq!(game.board.get_color(Pos::new(0, 0)?)?, Some(White));
+
+	game.do_moves_builder(vec![(White, 2, 2), (Black, 3, 3)])?;
+	game.undo()?;
+	game.undo()?;
+	assert_eq!(game.board.num_pieces_all(), 3);
+	assert_eq!(game.get_board_at_move(0)?.num_pieces_all(), 3);
+	assert_eq!(game.current_turn_color(), White);
+	Ok(())
+}
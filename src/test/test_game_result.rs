@@ -0,0 +1,89 @@
+use crate::{
+	error::*,
+	game::*,
+	game_result::*,
+	piece::*,
+};
+
+#[test]
+fn test_parse_result() -> Result<()> {
+	let results = vec![
+		(
+			"B+3.5",
+			GameResult::Score {
+				winner: Black,
+				margin: Some(3.5),
+			},
+		),
+		(
+			"W+",
+			GameResult::Score {
+				winner: White,
+				margin: None,
+			},
+		),
+		("W+R", GameResult::Resignation { winner: White }),
+		("B+T", GameResult::Time { winner: Black }),
+		("W+F", GameResult::Forfeit { winner: White }),
+		("0", GameResult::Draw),
+		("Void", GameResult::Void),
+	];
+	for (text, result) in results {
+		println!("{} -> {:?}", text, result);
+		assert_eq!(text.parse::<GameResult>()?, result);
+		assert_eq!(result.to_string(), text);
+	}
+	// the long forms are read, but written short
+	assert_eq!(
+		"B+Resign".parse::<GameResult>()?,
+		GameResult::Resignation { winner: Black }
+	);
+	assert_eq!("W+Time".parse::<GameResult>()?.to_string(), "W+T");
+	assert_eq!("Draw".parse::<GameResult>()?, GameResult::Draw);
+	for bad in &["?", "", "B", "X+R", "W+lots"] {
+		assert!(bad.parse::<GameResult>().is_err(), "parsed `{}`", bad);
+	}
+	Ok(())
+}
+
+#[test]
+fn test_score() {
+	assert_eq!(GameResult::score(6.5).to_string(), "B+6.5");
+	assert_eq!(GameResult::score(-0.5).to_string(), "W+0.5");
+	assert_eq!(GameResult::score(0.0), GameResult::Draw);
+	assert_eq!(GameResult::score(0.0).winner(), None);
+}
+
+#[test]
+fn test_resign() -> Result<()> {
+	let mut game = Game::new(0);
+	game.do_moves_builder(vec![(Black, 3, 3), (White, 15, 15)])?;
+	assert_eq!(game.result(), None);
+	let result = game.resign(White)?;
+	assert_eq!(result, GameResult::Resignation { winner: Black });
+	assert_eq!(game.result(), Some(result));
+	// nothing more can happen once the game is over
+	assert!(game.do_moves_builder(vec![(Black, 3, 15)]).is_err());
+	assert!(game.resign(Black).is_err());
+	assert!(game.offer_draw(Black).is_err());
+	assert_eq!(game.len(), 2);
+	Ok(())
+}
+
+#[test]
+fn test_draw_offer() -> Result<()> {
+	let mut game = Game::new(0);
+	game.do_moves_builder(vec![(Black, 3, 3)])?;
+	assert_eq!(game.offer_draw(Black)?, None);
+	assert_eq!(game.draw_offer(), Some(Black));
+	// offering again changes nothing, and a move withdraws the offer
+	assert_eq!(game.offer_draw(Black)?, None);
+	game.do_moves_builder(vec![(White, 15, 15)])?;
+	assert_eq!(game.draw_offer(), None);
+
+	assert_eq!(game.offer_draw(White)?, None);
+	assert_eq!(game.offer_draw(Black)?, Some(GameResult::Draw));
+	assert_eq!(game.result(), Some(GameResult::Draw));
+	assert!(game.do_moves_builder(vec![(Black, 3, 15)]).is_err());
+	Ok(())
+}
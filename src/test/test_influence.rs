@@ -0,0 +1,61 @@
+use crate::{
+	board::*,
+	error::*,
+	influence::*,
+	piece::*,
+	pos::Pos,
+};
+
+#[test]
+fn test_influence_empty() {
+	let board = Board::new();
+	let influence = board.influence();
+	assert_eq!(influence.area(Black), 0);
+	assert_eq!(influence.area(White), 0);
+	assert_eq!(influence.score(), 0);
+}
+
+#[test]
+fn test_influence_single_stone() -> Result<()> {
+	let mut board = Board::new();
+	board.do_moves_builder(vec![(Black, 3, 3)])?;
+	let influence = board.influence();
+	println!("{}", board);
+	println!("{}", influence);
+
+	assert!(influence.get(Pos::new(3, 3)?).unwrap() > 0);
+	assert_eq!(influence.owner(Pos::new(3, 4)?), Some(Black));
+	assert_eq!(influence.owner(Pos::new(15, 15)?), None);
+	assert!(influence.score() > 0);
+
+	// nothing to own off the board
+	assert_eq!(influence.get(PlacedPiece::pass(Black).pos), None);
+	let small = Board::with_size(9)?.influence();
+	assert_eq!(small.get(Pos::new(8, 8)?), Some(0));
+	assert_eq!(small.get(Pos::new(9, 9)?), None);
+	assert_eq!(small.owner(Pos::new(12, 3)?), None);
+
+	Ok(())
+}
+
+#[test]
+fn test_influence_walls() -> Result<()> {
+	let mut board = Board::new();
+	for i in 0..19u8 {
+		board.do_move(PlacedPiece::new(Black, Pos::new(4, i)?))?;
+		board.do_move(PlacedPiece::new(White, Pos::new(14, i)?))?;
+	}
+	let influence = board.influence();
+	println!("{}", board);
+	println!("{}", influence);
+
+	assert_eq!(influence.owner(Pos::new(0, 9)?), Some(Black));
+	assert_eq!(influence.owner(Pos::new(18, 9)?), Some(White));
+	assert_eq!(influence.owner(Pos::new(3, 0)?), Some(Black));
+	assert_eq!(influence.owner(Pos::new(15, 18)?), Some(White));
+	assert_eq!(influence.owner(Pos::new(9, 9)?), None);
+	assert_eq!(influence.area(Black), influence.area(White));
+	assert_eq!(influence.score(), 0);
+
+	Ok(())
+}
@@ -0,0 +1,105 @@
+use crate::{
+	error::*,
+	joseki::*,
+	piece::*,
+	pos::Pos,
+};
+
+const BOOK: &str = "
+(;GM[1]FF[4]SZ[19]
+(;B[pd]
+(;W[qf];B[nc];W[qi])
+(;W[qc];B[qd];W[pc];B[oc];W[ob]))
+(;B[qd];W[oc];B[qf];W[lc]))
+";
+
+#[test]
+fn test_joseki_book() -> Result<()> {
+	let book = JosekiBook::from_sgf(BOOK)?;
+	assert_eq!(book.len(), 3);
+	assert!(!book.is_empty());
+	assert!(JosekiBook::from_sgf("(;GM[1])")?.is_empty());
+
+	// a 4-4 point in the bottom left, the book was written in the top right
+	let found = book.lookup(&[PlacedPiece::new(Black, Pos::new(3, 3)?)], 19);
+	assert_eq!(found.len(), 1);
+	assert_eq!(found[0].corner, Corner::BottomLeft);
+	assert_eq!(found[0].moves, 1);
+	let mut continuations: Vec<(u8, u8)> = found[0]
+		.continuations
+		.iter()
+		.map(|m| (m.pos.x, m.pos.y))
+		.collect();
+	continuations.sort();
+	assert_eq!(continuations, vec![(2, 2), (2, 5), (5, 2)]);
+	assert!(found[0]
+		.continuations
+		.iter()
+		.all(|m| m.piece.color == White));
+
+	// mirrored approach in the top left, with moves elsewhere in between
+	let found = book.lookup(
+		&[
+			PlacedPiece::new(Black, Pos::new(3, 15)?),
+			PlacedPiece::new(White, Pos::new(15, 3)?),
+			PlacedPiece::new(Black, Pos::new(15, 15)?),
+			PlacedPiece::new(White, Pos::new(5, 16)?),
+		],
+		19,
+	);
+	let top_left = found.iter().find(|c| c.corner == Corner::TopLeft).unwrap();
+	assert_eq!(top_left.moves, 2);
+	assert_eq!(
+		top_left.continuations,
+		vec![PlacedPiece::new(Black, Pos::new(2, 13)?)]
+	);
+	// white started in the bottom right, found with colors swapped
+	let bottom_right = found
+		.iter()
+		.find(|c| c.corner == Corner::BottomRight)
+		.unwrap();
+	assert!(bottom_right
+		.continuations
+		.iter()
+		.all(|m| m.piece.color == Black));
+	assert!(found.iter().any(|c| c.corner == Corner::TopRight));
+
+	// out of the book
+	let found = book.lookup(
+		&[
+			PlacedPiece::new(Black, Pos::new(3, 3)?),
+			PlacedPiece::new(White, Pos::new(9, 4)?),
+		],
+		19,
+	);
+	assert_eq!(found.len(), 1);
+	let found = book.lookup(
+		&[
+			PlacedPiece::new(Black, Pos::new(3, 3)?),
+			PlacedPiece::new(White, Pos::new(4, 4)?),
+		],
+		19,
+	);
+	assert_eq!(found.len(), 0);
+
+	Ok(())
+}
+
+#[test]
+fn test_joseki_complete() -> Result<()> {
+	let book = JosekiBook::from_sgf(BOOK)?;
+	let found = book.lookup(
+		&[
+			PlacedPiece::new(Black, Pos::new(16, 15)?),
+			PlacedPiece::new(White, Pos::new(14, 16)?),
+			PlacedPiece::new(Black, Pos::new(16, 13)?),
+			PlacedPiece::new(White, Pos::new(11, 16)?),
+		],
+		19,
+	);
+	assert_eq!(found.len(), 1);
+	assert_eq!(found[0].moves, 4);
+	assert!(found[0].continuations.is_empty());
+
+	Ok(())
+}
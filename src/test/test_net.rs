@@ -0,0 +1,456 @@
+use crate::{
+	error::*,
+	game::*,
+	game_result::GameResult,
+	net::*,
+	piece::*,
+	pos::Pos,
+};
+use std::{
+	io::Write,
+	net::TcpStream,
+	thread,
+	time::{
+		Duration,
+		Instant,
+	},
+};
+
+/// host a game on a free localhost port and join it
+fn connect(game: &Game) -> Result<(Host, NetSession, NetSession, Game)> {
+	let mut host = Host::listen("127.0.0.1:0")?;
+	let addr = host.local_addr()?;
+	let guest = thread::spawn(move || NetSession::join(addr));
+	let host_session = accept(&mut host, game)?;
+	let (guest_session, guest_game) = guest.join().unwrap()?;
+	Ok((host, host_session, guest_session, guest_game))
+}
+
+fn accept(host: &mut Host, game: &Game) -> Result<NetSession> {
+	loop {
+		if let Some(session) = host.accept(game, Black)? {
+			return Ok(session);
+		}
+		thread::sleep(Duration::from_millis(5));
+	}
+}
+
+/// poll until something arrives
+fn wait_for(
+	session: &mut NetSession,
+	game: &mut Game,
+) -> Result<Vec<NetEvent>> {
+	for _ in 0..400 {
+		let events = session.update(game)?;
+		if !events.is_empty() {
+			return Ok(events);
+		}
+		thread::sleep(Duration::from_millis(5));
+	}
+	panic!("nothing received");
+}
+
+#[test]
+fn test_net_messages() -> Result<()> {
+	let messages = vec![
+		Message::Hello {
+			version: PROTOCOL_VERSION,
+		},
+		Message::Welcome {
+			version: PROTOCOL_VERSION,
+			size: 13,
+			handicap: 2,
+			komi: 0.5,
+			setup: Some(vec![PlacedPiece::new(Black, Pos::new(2, 2)?)]),
+			first_turn: White,
+			color: White,
+			moves: vec![
+				PlacedPiece::new(White, Pos::new(3, 3)?),
+				PlacedPiece::pass(Black),
+			],
+			token: 77,
+		},
+		Message::Create {
+			size: 9,
+			handicap: 0,
+			komi: 5.5,
+		},
+		Message::Joined {
+			game: 4,
+			color: None,
+			size: 19,
+			handicap: 0,
+			komi: 6.5,
+			setup: None,
+			first_turn: Black,
+			moves: vec![],
+			token: 0,
+		},
+		Message::Resume {
+			token: 1234,
+			acked: 6,
+		},
+		Message::Resumed {
+			game: 0,
+			color: Black,
+			from: 6,
+			moves: vec![PlacedPiece::new(Black, Pos::new(0, 0)?)],
+			hash: 99,
+		},
+		Message::Games {
+			games: vec![GameSummary {
+				id: 2,
+				players: 1,
+				moves: 0,
+			}],
+		},
+		Message::Leave,
+		Message::Resign,
+		Message::OfferDraw,
+		Message::UndoRequest { number: 8 },
+		Message::UndoReply {
+			number: 8,
+			accepted: true,
+		},
+		Message::Refused {
+			reason: "no thanks".to_string(),
+		},
+		Message::Move {
+			number: 12,
+			m: PlacedPiece::new(White, Pos::new(18, 0)?),
+			hash: 0xdead_beef_1234_5678,
+		},
+		Message::Desync {
+			number: 3,
+			hash: u64::MAX,
+		},
+	];
+	for message in messages {
+		let frame = message.encode();
+		println!("{:?} -> {:?}", message, frame);
+		assert_eq!(
+			frame.len() - 4,
+			u32::from_le_bytes([frame[0], frame[1], frame[2], frame[3]]) as usize
+		);
+		assert_eq!(Message::decode(&frame[4..])?, message);
+	}
+	assert!(Message::decode(&[]).is_err());
+	assert!(Message::decode(&[42]).is_err());
+	// a move that is cut short
+	assert!(Message::decode(&[4, 1, 0, 0, 0, 1]).is_err());
+	Ok(())
+}
+
+#[test]
+fn test_net_game() -> Result<()> {
+	let mut host_game = Game::new(0);
+	host_game.do_moves_builder(vec![(Black, 3, 3), (White, 15, 15)])?;
+	let (_, mut host, mut guest, mut guest_game) = connect(&host_game)?;
+	assert_eq!(host.color, Black);
+	assert_eq!(guest.color, White);
+	// the guest starts from the moves already played
+	assert_eq!(guest_game.moves, host_game.moves);
+
+	let m = PlacedPiece::new(Black, Pos::new(3, 15)?);
+	host.play(&mut host_game, m)?;
+	assert_eq!(
+		wait_for(&mut guest, &mut guest_game)?,
+		vec![NetEvent::Move(m)]
+	);
+
+	// not the guest's color, and then not the host's turn
+	assert!(guest
+		.play(&mut guest_game, PlacedPiece::new(Black, Pos::new(9, 9)?))
+		.is_err());
+	assert!(host
+		.play(&mut host_game, PlacedPiece::new(Black, Pos::new(9, 9)?))
+		.is_err());
+	// an occupied point, nothing is sent
+	assert!(guest
+		.play(&mut guest_game, PlacedPiece::new(White, Pos::new(3, 3)?))
+		.is_err());
+
+	let m = PlacedPiece::new(White, Pos::new(15, 3)?);
+	guest.play(&mut guest_game, m)?;
+	assert_eq!(
+		wait_for(&mut host, &mut host_game)?,
+		vec![NetEvent::Move(m)]
+	);
+	println!("{}", host_game);
+	assert_eq!(host_game.moves, guest_game.moves);
+	assert_eq!(host_game.board.hash(), guest_game.board.hash());
+	Ok(())
+}
+
+#[test]
+fn test_net_small_board() -> Result<()> {
+	let mut host_game = Game::with_size(9, 2)?;
+	host_game.komi = 3.5;
+	host_game.do_moves_builder(vec![(White, 4, 4)])?;
+	let (_, _, _, guest_game) = connect(&host_game)?;
+	assert_eq!(guest_game.board.size(), 9);
+	assert_eq!(guest_game.handicap, 2);
+	assert_eq!(guest_game.komi, 3.5);
+	assert_eq!(guest_game.board.hash(), host_game.board.hash());
+	Ok(())
+}
+
+#[test]
+fn test_net_set_up() -> Result<()> {
+	// stones with no usual handicap place on a 7x7 board, and white to move
+	let mut host_game = Game::with_size(7, 0)?;
+	host_game.handicap = 2;
+	host_game.set_up(
+		vec![
+			PlacedPiece::new(Black, Pos::new(2, 2)?),
+			PlacedPiece::new(Black, Pos::new(4, 4)?),
+		],
+		White,
+	)?;
+	host_game.do_moves_builder(vec![(White, 3, 3)])?;
+	let (_, _, _, guest_game) = connect(&host_game)?;
+	assert_eq!(guest_game.handicap, 2);
+	assert_eq!(guest_game.setup(), host_game.setup());
+	assert_eq!(guest_game.first_turn(), White);
+	assert_eq!(guest_game.current_turn_color(), Black);
+	assert_eq!(guest_game.board.hash(), host_game.board.hash());
+	Ok(())
+}
+
+#[test]
+fn test_net_game_end() -> Result<()> {
+	let mut host_game = Game::new(0);
+	let (_, mut host, mut guest, mut guest_game) = connect(&host_game)?;
+	host.play(&mut host_game, PlacedPiece::new(Black, Pos::new(3, 3)?))?;
+	wait_for(&mut guest, &mut guest_game)?;
+
+	// the guest offers a draw, and the host agrees
+	assert_eq!(guest.offer_draw(&mut guest_game)?, None);
+	assert_eq!(
+		wait_for(&mut host, &mut host_game)?,
+		vec![NetEvent::DrawOffered]
+	);
+	assert_eq!(host.offer_draw(&mut host_game)?, Some(GameResult::Draw));
+	assert_eq!(
+		wait_for(&mut guest, &mut guest_game)?,
+		vec![NetEvent::Ended(GameResult::Draw)]
+	);
+	assert_eq!(guest_game.result(), Some(GameResult::Draw));
+	assert!(guest
+		.play(&mut guest_game, PlacedPiece::new(White, Pos::new(15, 15)?))
+		.is_err());
+
+	let mut host_game = Game::new(0);
+	let (_, mut host, mut guest, mut guest_game) = connect(&host_game)?;
+	let resigned = GameResult::Resignation { winner: White };
+	assert_eq!(host.resign(&mut host_game)?, resigned);
+	assert_eq!(
+		wait_for(&mut guest, &mut guest_game)?,
+		vec![NetEvent::Ended(resigned)]
+	);
+	Ok(())
+}
+
+#[test]
+fn test_net_undo() -> Result<()> {
+	let mut host_game = Game::new(0);
+	let (_, mut host, mut guest, mut guest_game) = connect(&host_game)?;
+	let m = PlacedPiece::new(Black, Pos::new(3, 3)?);
+	host.play(&mut host_game, m)?;
+	wait_for(&mut guest, &mut guest_game)?;
+
+	// the guest turns the first request down
+	host.request_undo(&host_game)?;
+	assert_eq!(
+		wait_for(&mut guest, &mut guest_game)?,
+		vec![NetEvent::UndoRequested]
+	);
+	assert!(guest.undo_requested());
+	assert_eq!(guest.reply_undo(&mut guest_game, false)?, None);
+	assert_eq!(
+		wait_for(&mut host, &mut host_game)?,
+		vec![NetEvent::UndoDeclined]
+	);
+	assert_eq!(host_game.len(), 1);
+
+	// and accepts the second
+	host.request_undo(&host_game)?;
+	wait_for(&mut guest, &mut guest_game)?;
+	assert_eq!(guest.reply_undo(&mut guest_game, true)?, Some(m));
+	assert_eq!(
+		wait_for(&mut host, &mut host_game)?,
+		vec![NetEvent::Undone(m)]
+	);
+	assert_eq!(host_game.len(), 0);
+	assert_eq!(guest_game.len(), 0);
+	assert!(guest.reply_undo(&mut guest_game, true).is_err());
+
+	// a request that crosses the opponent's move is turned down
+	host.play(&mut host_game, PlacedPiece::new(Black, Pos::new(15, 15)?))?;
+	wait_for(&mut guest, &mut guest_game)?;
+	host.request_undo(&host_game)?;
+	guest.play(&mut guest_game, PlacedPiece::new(White, Pos::new(3, 3)?))?;
+	thread::sleep(Duration::from_millis(50));
+	assert!(guest.update(&mut guest_game)?.is_empty());
+	assert!(!guest.undo_requested());
+	let events = wait_for(&mut host, &mut host_game)?;
+	println!("{:?}", events);
+	assert_eq!(
+		events,
+		vec![NetEvent::Move(PlacedPiece::new(White, Pos::new(3, 3)?))]
+	);
+	thread::sleep(Duration::from_millis(50));
+	assert!(host.update(&mut host_game)?.is_empty());
+	assert_eq!(host_game.moves, guest_game.moves);
+
+	// an undo that can't be done is still answered
+	host.request_undo(&host_game)?;
+	wait_for(&mut guest, &mut guest_game)?;
+	guest_game.resign(White)?;
+	assert!(guest.reply_undo(&mut guest_game, true).is_err());
+	assert_eq!(
+		wait_for(&mut host, &mut host_game)?,
+		vec![NetEvent::UndoDeclined]
+	);
+	assert_eq!(host_game.len(), 2);
+	Ok(())
+}
+
+#[test]
+fn test_net_desync() -> Result<()> {
+	let mut host_game = Game::new(0);
+	let (_, mut host, mut guest, mut guest_game) = connect(&host_game)?;
+
+	// a stone only the guest can see
+	guest_game
+		.board
+		.set(PlacedPiece::new(White, Pos::new(0, 0)?))?;
+	host.play(&mut host_game, PlacedPiece::new(Black, Pos::new(9, 9)?))?;
+	let events = wait_for(&mut guest, &mut guest_game)?;
+	println!("{:?}", events);
+	assert_eq!(events.len(), 2);
+	assert_eq!(
+		events[0],
+		NetEvent::Move(PlacedPiece::new(Black, Pos::new(9, 9)?))
+	);
+	match events[1] {
+		NetEvent::Desync {
+			number,
+			local,
+			remote,
+		} => {
+			assert_eq!(number, 1);
+			assert_eq!(local, guest_game.board.hash());
+			assert_eq!(remote, host_game.board.hash());
+		}
+		_ => panic!("expected a desync"),
+	}
+	// the host hears about it too
+	match wait_for(&mut host, &mut host_game)?[0] {
+		NetEvent::Desync { number, .. } => assert_eq!(number, 1),
+		_ => panic!("expected a desync"),
+	}
+	Ok(())
+}
+
+#[test]
+fn test_net_version_mismatch() -> Result<()> {
+	let game = Game::new(0);
+	let mut host = Host::listen("127.0.0.1:0")?;
+	let mut stream = TcpStream::connect(host.local_addr()?)?;
+	stream.write_all(
+		&Message::Hello {
+			version: PROTOCOL_VERSION + 1,
+		}
+		.encode(),
+	)?;
+	let res = loop {
+		match host.accept(&game, Black) {
+			Ok(None) => thread::sleep(Duration::from_millis(5)),
+			res => break res,
+		}
+	};
+	assert!(res.is_err());
+	let mut conn = Connection::new(stream)?;
+	match conn.recv()? {
+		Message::Refused { reason } => println!("refused: {}", reason),
+		m => panic!("expected Refused, got {:?}", m),
+	}
+	Ok(())
+}
+
+#[test]
+fn test_net_silent_guest() -> Result<()> {
+	let game = Game::new(0);
+	let mut host = Host::listen("127.0.0.1:0")?;
+	let addr = host.local_addr()?;
+	// someone connects and says nothing, which doesn't hold up the host
+	let silent = TcpStream::connect(addr)?;
+	let started = Instant::now();
+	for _ in 0..10 {
+		assert!(host.accept(&game, Black)?.is_none());
+		thread::sleep(Duration::from_millis(5));
+	}
+	assert!(started.elapsed() < Duration::from_secs(1));
+
+	// the handshake goes on where it was once they speak
+	let mut silent = Connection::new(silent)?;
+	silent.send(&Message::Hello {
+		version: PROTOCOL_VERSION,
+	})?;
+	silent.send(&Message::Join { game: 0 })?;
+	let session = accept(&mut host, &game)?;
+	assert_eq!(session.color, Black);
+	assert!(matches!(silent.recv()?, Message::Welcome { .. }));
+	Ok(())
+}
+
+#[test]
+fn test_net_resume() -> Result<()> {
+	let mut host_game = Game::new(0);
+	let (mut listener, mut host, guest, mut guest_game) = connect(&host_game)?;
+	let addr = guest.peer_addr();
+	let token = guest.token;
+	host.play(&mut host_game, PlacedPiece::new(Black, Pos::new(3, 3)?))?;
+	// the guest drops before receiving the move
+	drop(guest);
+	loop {
+		match host.update(&mut host_game) {
+			Err(BadukError::Disconnected) => break,
+			Err(err) => return Err(err),
+			Ok(_) => thread::sleep(Duration::from_millis(5)),
+		}
+	}
+
+	// nobody else can take the guest's place
+	let stranger = thread::spawn(move || NetSession::join(addr));
+	assert!(accept(&mut listener, &host_game).is_err());
+	assert!(stranger.join().unwrap().is_err());
+	let mut wrong = Game::new(0);
+	let stranger =
+		thread::spawn(move || NetSession::resume(addr, token + 1, &mut wrong));
+	assert!(accept(&mut listener, &host_game).is_err());
+	assert!(stranger.join().unwrap().is_err());
+
+	let resumed = thread::spawn(move || {
+		NetSession::resume(addr, token, &mut guest_game).map(|r| (r, guest_game))
+	});
+	let mut host = accept(&mut listener, &host_game)?;
+	let ((mut guest, events), mut guest_game) = resumed.join().unwrap()?;
+	println!("{:?}", events);
+	assert_eq!(
+		events,
+		vec![NetEvent::Move(PlacedPiece::new(Black, Pos::new(3, 3)?))]
+	);
+	assert_eq!(guest.color, White);
+	assert_eq!(guest_game.board.hash(), host_game.board.hash());
+
+	// and the game goes on
+	let m = PlacedPiece::new(White, Pos::new(15, 15)?);
+	guest.play(&mut guest_game, m)?;
+	assert_eq!(
+		wait_for(&mut host, &mut host_game)?,
+		vec![NetEvent::Move(m)]
+	);
+	Ok(())
+}
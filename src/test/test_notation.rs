@@ -0,0 +1,75 @@
+use crate::{
+	board::*,
+	error::*,
+	notation::*,
+	piece::*,
+	pos::Pos,
+};
+
+#[test]
+fn test_go_coordinates() -> Result<()> {
+	let points = vec![
+		((0, 0), "A1"),
+		((3, 3), "D4"),
+		((7, 0), "H1"),
+		// no I column
+		((8, 0), "J1"),
+		((18, 18), "T19"),
+		((15, 16), "Q17"),
+	];
+	for ((x, y), text) in points {
+		let pos = Pos::new(x, y)?;
+		println!("{:?} -> {}", pos, pos);
+		assert_eq!(pos.to_string(), text);
+		assert_eq!(text.parse::<Pos>()?, pos);
+		assert_eq!(to_go(pos), text);
+	}
+	assert_eq!("q16".parse::<Pos>()?, Pos::new(15, 15)?);
+	for bad in &["", "I5", "A0", "A20", "U1", "5A", "D"] {
+		assert!(bad.parse::<Pos>().is_err(), "parsed `{}`", bad);
+	}
+
+	// off the board, written without panicking
+	assert_eq!(PlacedPiece::pass(Black).pos.to_string(), "pass");
+	assert_eq!(PlacedPiece::pass(White).vertex().to_string(), "pass");
+	assert_eq!(Pos { x: 20, y: 3 }.to_string(), "(20, 3)");
+	assert_eq!(column_letter(25), '?');
+	Ok(())
+}
+
+#[test]
+fn test_sgf_letters() -> Result<()> {
+	let pos = Pos::new(3, 15)?;
+	assert_eq!(to_sgf(pos, 19), "dd");
+	assert_eq!(from_sgf("dd", 19)?, pos);
+	assert_eq!(to_sgf(Pos::new(0, 0)?, 9), "ai");
+	assert_eq!(from_sgf("ai", 9)?, Pos::new(0, 0)?);
+	assert!(from_sgf("zz", 19).is_err());
+	Ok(())
+}
+
+#[test]
+fn test_gtp_vertex() -> Result<()> {
+	assert_eq!(Vertex::parse("pass", 19)?, Vertex::Pass);
+	assert_eq!(Vertex::parse("PASS", 9)?.to_string(), "pass");
+	assert_eq!(Vertex::parse("e5", 9)?, Vertex::Point(Pos::new(4, 4)?));
+	assert_eq!(Vertex::parse("E5", 9)?.to_string(), "E5");
+	// on the board, but not a 9x9 one
+	assert!(Vertex::parse("K10", 9).is_err());
+	assert!(Vertex::parse("K10", 19).is_ok());
+	Ok(())
+}
+
+#[test]
+fn test_board_labels() -> Result<()> {
+	let mut board = Board::with_size(9)?;
+	board.set(PlacedPiece::new(Black, Pos::new(2, 6)?))?;
+	let text = board.to_string();
+	println!("{}", text);
+	let lines: Vec<&str> = text.lines().filter(|l| !l.is_empty()).collect();
+	assert_eq!(lines.len(), 11);
+	assert!(lines[0].contains("A B C D E F G H J"));
+	assert!(lines[1].contains(" 9 "));
+	assert!(lines[9].contains(" 1 "));
+	Ok(())
+}
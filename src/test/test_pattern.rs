@@ -0,0 +1,106 @@
+use crate::{
+	board::*,
+	error::*,
+	pattern::*,
+	piece::*,
+	pos::Pos,
+	symmetry::Symmetry,
+};
+
+#[test]
+fn test_parse_pattern() -> Result<()> {
+	let pattern: Pattern = "
+		# # #
+		. * X
+		? o x
+	"
+	.parse()?;
+	assert_eq!(pattern.width(), 3);
+	assert_eq!(pattern.height(), 3);
+	assert_eq!(pattern.get(0, 0), PatternCell::Edge);
+	assert_eq!(pattern.get(2, 1), PatternCell::Black);
+	assert_eq!(pattern.get(1, 2), PatternCell::WhiteOrEmpty);
+	assert_eq!(format!("{}", pattern), "###\n.*X\n?ox\n");
+
+	"".parse::<Pattern>()
+		.expect_err("Expected empty pattern to be invalid");
+	"..\n..."
+		.parse::<Pattern>()
+		.expect_err("Expected uneven rows to be invalid");
+	"*.\n.*"
+		.parse::<Pattern>()
+		.expect_err("Expected two anchors to be invalid");
+	"..\n.Z"
+		.parse::<Pattern>()
+		.expect_err("Expected unknown cells to be invalid");
+
+	Ok(())
+}
+
+#[test]
+fn test_find_pattern() -> Result<()> {
+	let pattern: Pattern = "
+		. X .
+		X * X
+	"
+	.parse()?;
+
+	let mut board = Board::new();
+	board.do_moves_builder(vec![(Black, 5, 6), (Black, 4, 5), (Black, 6, 5)])?;
+	println!("{}", board);
+	let matches = board.find_pattern(&pattern);
+	assert_eq!(matches.len(), 1);
+	assert_eq!(matches[0].pos, Pos::new(5, 5)?);
+	assert!(!matches[0].inverted);
+
+	// rotated and with the other color
+	board.do_moves_builder(vec![
+		(White, 12, 12),
+		(White, 13, 11),
+		(White, 13, 13),
+	])?;
+	println!("{}", board);
+	let matches = board.find_pattern(&pattern);
+	assert_eq!(matches.len(), 2);
+	let at = Pos::new(13, 12)?;
+	let rotated = matches.iter().find(|m| m.pos == at).unwrap();
+	assert!(rotated.inverted);
+	assert_eq!(rotated.symmetry, Symmetry::Rotate90);
+
+	// one of the empty cells is taken
+	board.do_moves_builder(vec![(White, 4, 6)])?;
+	assert_eq!(board.find_pattern(&pattern).len(), 1);
+
+	Ok(())
+}
+
+#[test]
+fn test_find_pattern_edge() -> Result<()> {
+	let pattern: Pattern = "
+		# # #
+		. * .
+	"
+	.parse()?;
+
+	// every edge point except the corners
+	assert_eq!(Board::new().find_pattern(&pattern).len(), 4 * 17);
+	assert_eq!(Board::with_size(9)?.find_pattern(&pattern).len(), 4 * 7);
+
+	let pattern: Pattern = "
+		# # # #
+		# * X ?
+	"
+	.parse()?;
+	let mut board = Board::new();
+	board.do_moves_builder(vec![(White, 0, 17), (Black, 1, 0), (Black, 9, 0)])?;
+	println!("{}", board);
+	let mut positions: Vec<(u8, u8)> = board
+		.find_pattern(&pattern)
+		.iter()
+		.map(|m| (m.pos.x, m.pos.y))
+		.collect();
+	positions.sort();
+	assert_eq!(positions, vec![(0, 0), (0, 18)]);
+
+	Ok(())
+}
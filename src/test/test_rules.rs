@@ -0,0 +1,30 @@
+use crate::{
+	error::*,
+	game::*,
+	rules::*,
+};
+
+#[test]
+fn test_ruleset_names() -> Result<()> {
+	for &ruleset in Ruleset::ALL.iter() {
+		assert_eq!(ruleset.to_string().parse::<Ruleset>()?, ruleset);
+	}
+	assert_eq!("japanese".parse::<Ruleset>()?, Ruleset::Japanese);
+	assert_eq!("New Zealand".parse::<Ruleset>()?, Ruleset::NewZealand);
+	assert!("Ing".parse::<Ruleset>().is_err());
+	Ok(())
+}
+
+#[test]
+fn test_default_komi() -> Result<()> {
+	assert_eq!(Ruleset::Chinese.default_komi(), 7.5);
+	assert!(Ruleset::Chinese.counts_area());
+	assert!(!Ruleset::Japanese.counts_area());
+
+	let game = Game::with_size(19, 0)?;
+	assert_eq!(game.ruleset, Ruleset::Japanese);
+	assert_eq!(game.komi, 6.5);
+	// with handicap stones white only gets the half point
+	assert_eq!(Game::with_size(19, 2)?.komi, 0.5);
+	Ok(())
+}
@@ -0,0 +1,369 @@
+use crate::{
+	error::*,
+	game::Game,
+	game_result::GameResult,
+	net::*,
+	piece::*,
+	pos::Pos,
+	server::*,
+	sgf,
+};
+use std::{
+	env,
+	fs,
+	sync::{
+		atomic::{
+			AtomicBool,
+			Ordering,
+		},
+		Arc,
+	},
+	thread,
+	time::Duration,
+};
+
+/// run a server on a free localhost port until the returned flag is set
+fn start_server(
+	save_dir: &str,
+) -> Result<(String, Arc<AtomicBool>, thread::JoinHandle<()>)> {
+	let dir = env::temp_dir().join(save_dir);
+	let _ = fs::remove_dir_all(&dir);
+	let mut server = Server::bind("127.0.0.1:0", Some(dir))?;
+	let addr = server.local_addr()?.to_string();
+	let stop = Arc::new(AtomicBool::new(false));
+	let server_stop = stop.clone();
+	let handle = thread::spawn(move || server.run(&server_stop, |_| {}));
+	Ok((addr, stop, handle))
+}
+
+fn expect_move(client: &mut Client, m: PlacedPiece) -> Result<()> {
+	match client.recv()? {
+		Message::Move { m: received, .. } => assert_eq!(received, m),
+		other => panic!("expected {:?}, got {:?}", m, other),
+	}
+	assert!(client.in_sync);
+	Ok(())
+}
+
+#[test]
+fn test_server_game() -> Result<()> {
+	let (addr, stop, handle) = start_server("baduk_test_server_game")?;
+
+	let mut alice = Client::connect(&addr)?;
+	let mut bob = Client::connect(&addr)?;
+	let mut carol = Client::connect(&addr)?;
+
+	let game = alice.create(19, 0, 6.5)?;
+	assert_eq!(alice.color, Some(Black));
+	let other_game = carol.create(9, 2, 0.5)?;
+	assert_ne!(game, other_game);
+	assert_eq!(carol.game.board.size(), 9);
+	assert_eq!(carol.game.komi, 0.5);
+	assert_eq!(carol.game.first_turn(), White);
+	let games = bob.list()?;
+	println!("{:?}", games);
+	assert_eq!(games.len(), 2);
+	assert!(games.iter().all(|g| g.players == 1 && g.moves == 0));
+
+	// black can't start until someone joins
+	alice.play(Pos::new(3, 3)?)?;
+	assert!(matches!(alice.recv()?, Message::Refused { .. }));
+
+	assert_eq!(bob.join(game)?, White);
+	// carol is in her own game, and has to leave it to watch
+	assert!(carol.spectate(game).is_err());
+	carol.leave()?;
+	assert!(matches!(carol.recv()?, Message::GameOver { .. }));
+	carol.spectate(game)?;
+	assert_eq!(carol.color, None);
+
+	let m = PlacedPiece::new(Black, Pos::new(3, 3)?);
+	alice.play(m.pos)?;
+	for client in [&mut alice, &mut bob, &mut carol].iter_mut() {
+		expect_move(client, m)?;
+	}
+
+	// not black's turn, sent straight to the server to skip the local check
+	alice.send(&Message::Move {
+		number: 2,
+		m: PlacedPiece::new(Black, Pos::new(4, 4)?),
+		hash: 0,
+	})?;
+	match alice.recv()? {
+		Message::Refused { reason } => {
+			println!("{}", reason);
+			assert!(reason.contains("White's turn"));
+		}
+		other => panic!("expected Refused, got {:?}", other),
+	}
+	// an occupied point
+	bob.send(&Message::Move {
+		number: 2,
+		m: PlacedPiece::new(White, Pos::new(3, 3)?),
+		hash: 0,
+	})?;
+	assert!(matches!(bob.recv()?, Message::Refused { .. }));
+
+	let m = PlacedPiece::new(White, Pos::new(15, 15)?);
+	bob.play(m.pos)?;
+	for client in [&mut alice, &mut bob, &mut carol].iter_mut() {
+		expect_move(client, m)?;
+	}
+	let games = carol.list()?;
+	assert_eq!(games.len(), 1);
+	assert_eq!(games[0].players, 2);
+	assert_eq!(games[0].moves, 2);
+
+	// a third player can only watch
+	let mut dave = Client::connect(&addr)?;
+	assert!(dave.join(game).is_err());
+	dave.spectate(game)?;
+	assert_eq!(dave.game.moves, alice.game.moves);
+
+	// white leaves and forfeits
+	bob.leave()?;
+	for client in [&mut alice, &mut bob, &mut carol, &mut dave].iter_mut() {
+		match client.recv()? {
+			Message::GameOver { result } => assert_eq!(result, "B+F"),
+			other => panic!("expected GameOver, got {:?}", other),
+		}
+		assert_eq!(client.game_id, None);
+	}
+	assert!(dave.list()?.is_empty());
+
+	stop.store(true, Ordering::Relaxed);
+	handle.join().unwrap();
+
+	let path = env::temp_dir()
+		.join("baduk_test_server_game")
+		.join(format!("game-{}.sgf", game));
+	let saved = fs::read_to_string(&path)?;
+	println!("{}", saved);
+	let root = &sgf::parse(&saved)?[0];
+	assert_eq!(root.get("RE"), Some("B+F"));
+	let moves: Vec<PlacedPiece> = root
+		.main_line()
+		.iter()
+		.filter_map(|node| node.get_move(19))
+		.collect();
+	assert_eq!(moves, alice.game.moves);
+	// carol's game never had a move, so it wasn't saved
+	assert!(!path
+		.with_file_name(format!("game-{}.sgf", other_game))
+		.exists());
+	Ok(())
+}
+
+#[test]
+fn test_server_resign() -> Result<()> {
+	let (addr, stop, handle) = start_server("baduk_test_server_resign")?;
+	let mut alice = Client::connect(&addr)?;
+	let mut bob = Client::connect(&addr)?;
+	let game = alice.create(19, 0, 6.5)?;
+	bob.join(game)?;
+	let m = PlacedPiece::new(Black, Pos::new(3, 3)?);
+	alice.play(m.pos)?;
+	expect_move(&mut alice, m)?;
+	expect_move(&mut bob, m)?;
+
+	// bob offers a draw, alice resigns instead
+	bob.offer_draw()?;
+	assert_eq!(alice.recv()?, Message::OfferDraw);
+	alice.resign()?;
+	for client in [&mut alice, &mut bob].iter_mut() {
+		match client.recv()? {
+			Message::GameOver { result } => assert_eq!(result, "W+R"),
+			other => panic!("expected GameOver, got {:?}", other),
+		}
+		assert_eq!(
+			client.game.result(),
+			Some(GameResult::Resignation { winner: White })
+		);
+	}
+
+	// a new game ends in an agreed draw
+	let game = alice.create(19, 0, 6.5)?;
+	bob.join(game)?;
+	alice.offer_draw()?;
+	assert_eq!(bob.recv()?, Message::OfferDraw);
+	bob.offer_draw()?;
+	for client in [&mut alice, &mut bob].iter_mut() {
+		match client.recv()? {
+			Message::GameOver { result } => assert_eq!(result, "0"),
+			other => panic!("expected GameOver, got {:?}", other),
+		}
+	}
+
+	stop.store(true, Ordering::Relaxed);
+	handle.join().unwrap();
+	Ok(())
+}
+
+#[test]
+fn test_server_undo() -> Result<()> {
+	let (addr, stop, handle) = start_server("baduk_test_server_undo")?;
+	let mut alice = Client::connect(&addr)?;
+	let mut bob = Client::connect(&addr)?;
+	let mut carol = Client::connect(&addr)?;
+	let game = alice.create(19, 0, 6.5)?;
+	bob.join(game)?;
+	carol.spectate(game)?;
+	let m = PlacedPiece::new(Black, Pos::new(3, 3)?);
+	alice.play(m.pos)?;
+	for client in [&mut alice, &mut bob, &mut carol].iter_mut() {
+		expect_move(client, m)?;
+	}
+
+	// declined, only alice hears about it
+	alice.request_undo()?;
+	assert_eq!(bob.recv()?, Message::UndoRequest { number: 1 });
+	bob.reply_undo(false)?;
+	assert_eq!(
+		alice.recv()?,
+		Message::UndoReply {
+			number: 1,
+			accepted: false,
+		}
+	);
+
+	// accepted, the move is taken back for everyone
+	alice.request_undo()?;
+	assert_eq!(bob.recv()?, Message::UndoRequest { number: 1 });
+	bob.reply_undo(true)?;
+	for client in [&mut alice, &mut bob, &mut carol].iter_mut() {
+		assert!(matches!(
+			client.recv()?,
+			Message::UndoReply { accepted: true, .. }
+		));
+		assert_eq!(client.game.len(), 0);
+	}
+	assert_eq!(carol.list()?[0].moves, 0);
+
+	// black moves again
+	let m = PlacedPiece::new(Black, Pos::new(15, 15)?);
+	alice.play(m.pos)?;
+	for client in [&mut alice, &mut bob, &mut carol].iter_mut() {
+		expect_move(client, m)?;
+	}
+
+	stop.store(true, Ordering::Relaxed);
+	handle.join().unwrap();
+	Ok(())
+}
+
+#[test]
+fn test_server_reconnect() -> Result<()> {
+	let (addr, stop, handle) = start_server("baduk_test_server_reconnect")?;
+	let mut alice = Client::connect(&addr)?;
+	let game = alice.create(19, 0, 6.5)?;
+	let mut bob = Client::connect(&addr)?;
+	bob.join(game)?;
+	let mut carol = Client::connect(&addr)?;
+	carol.spectate(game)?;
+	assert!(alice.token.is_some());
+	assert_ne!(alice.token, bob.token);
+	assert_eq!(carol.token, None);
+
+	let m = PlacedPiece::new(Black, Pos::new(9, 9)?);
+	alice.play(m.pos)?;
+	for client in [&mut alice, &mut bob, &mut carol].iter_mut() {
+		expect_move(client, m)?;
+	}
+	// bob plays and drops before hearing back
+	bob.play(Pos::new(3, 3)?)?;
+	let token = bob.token.unwrap();
+	let bob_game = std::mem::replace(&mut bob.game, Game::new(0));
+	drop(bob);
+	expect_move(&mut alice, PlacedPiece::new(White, Pos::new(3, 3)?))?;
+	expect_move(&mut carol, PlacedPiece::new(White, Pos::new(3, 3)?))?;
+	// the game goes on without him
+	let m = PlacedPiece::new(Black, Pos::new(15, 15)?);
+	alice.play(m.pos)?;
+	expect_move(&mut alice, m)?;
+	expect_move(&mut carol, m)?;
+	assert_eq!(carol.list()?[0].players, 2);
+
+	// a wrong token gets nothing
+	assert!(Client::resume(&addr, token ^ 1, Game::new(0)).is_err());
+
+	let (mut bob, events) = Client::resume(&addr, token, bob_game)?;
+	println!("{:?}", events);
+	assert_eq!(
+		events,
+		vec![
+			NetEvent::Move(PlacedPiece::new(White, Pos::new(3, 3)?)),
+			NetEvent::Move(PlacedPiece::new(Black, Pos::new(15, 15)?)),
+		]
+	);
+	assert!(bob.in_sync);
+	assert_eq!(bob.color, Some(White));
+	assert_eq!(bob.game_id, Some(game));
+	assert_eq!(bob.game.board.hash(), alice.game.board.hash());
+
+	let m = PlacedPiece::new(White, Pos::new(3, 15)?);
+	bob.play(m.pos)?;
+	for client in [&mut alice, &mut bob, &mut carol].iter_mut() {
+		expect_move(client, m)?;
+	}
+
+	// a desynced client finds out when resuming
+	let token = bob.token.unwrap();
+	let mut bob_game = std::mem::replace(&mut bob.game, Game::new(0));
+	drop(bob);
+	bob_game
+		.board
+		.set(PlacedPiece::new(Black, Pos::new(0, 0)?))?;
+	let (bob, events) = Client::resume(&addr, token, bob_game)?;
+	assert!(!bob.in_sync);
+	match events.as_slice() {
+		[NetEvent::Desync { number, .. }] => assert_eq!(*number, 4),
+		_ => panic!("expected a desync, got {:?}", events),
+	}
+
+	stop.store(true, Ordering::Relaxed);
+	handle.join().unwrap();
+	Ok(())
+}
+
+#[test]
+fn test_server_disconnect() -> Result<()> {
+	let dir = env::temp_dir().join("baduk_test_server_disconnect");
+	let server = Server::bind("127.0.0.1:0", Some(dir))?;
+	// no time to reconnect
+	let mut server = server.with_reconnect_timeout(Duration::from_millis(0));
+	let addr = server.local_addr()?.to_string();
+	let stop = Arc::new(AtomicBool::new(false));
+	let server_stop = stop.clone();
+	let handle = thread::spawn(move || server.run(&server_stop, |_| {}));
+
+	let mut alice = Client::connect(&addr)?;
+	let game = alice.create(19, 0, 6.5)?;
+	{
+		let mut bob = Client::connect(&addr)?;
+		bob.join(game)?;
+		alice.play(Pos::new(9, 9)?)?;
+		expect_move(&mut alice, PlacedPiece::new(Black, Pos::new(9, 9)?))?;
+		expect_move(&mut bob, PlacedPiece::new(Black, Pos::new(9, 9)?))?;
+		// bob's connection closes here
+	}
+	match alice.recv()? {
+		Message::GameOver { result } => assert_eq!(result, "B+F"),
+		other => panic!("expected GameOver, got {:?}", other),
+	}
+	stop.store(true, Ordering::Relaxed);
+	handle.join().unwrap();
+	Ok(())
+}
+
+#[test]
+fn test_server_version_mismatch() -> Result<()> {
+	let (addr, stop, handle) = start_server("baduk_test_server_version")?;
+	let mut conn = Connection::new(std::net::TcpStream::connect(&addr)?)?;
+	conn.send(&Message::Hello {
+		version: PROTOCOL_VERSION + 1,
+	})?;
+	assert!(matches!(conn.recv()?, Message::Refused { .. }));
+	stop.store(true, Ordering::Relaxed);
+	handle.join().unwrap();
+	Ok(())
+}
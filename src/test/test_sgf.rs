@@ -0,0 +1,278 @@
+use crate::{
+	annotation::*,
+	clock::*,
+	error::*,
+	game::*,
+	game_result::GameResult,
+	piece::*,
+	pos::Pos,
+	rules::Ruleset,
+	sgf::{
+		self,
+		SgfNode,
+	},
+};
+
+#[test]
+fn test_parse_sgf() -> Result<()> {
+	let trees = sgf::parse(
+		"(;FF[4]C[a \\] comment \\\nhere]SZ[19];B[aa];W[sa](;B[ss])(;B[tt]))",
+	)?;
+	assert_eq!(trees.len(), 1);
+	let root = &trees[0];
+	assert_eq!(root.get("C"), Some("a ] comment here"));
+	assert_eq!(root.get("SZ"), Some("19"));
+	let main_line = root.main_line();
+	assert_eq!(main_line.len(), 4);
+	assert_eq!(
+		main_line[1].get_move(19),
+		Some(PlacedPiece::new(Black, Pos::new(0, 18)?))
+	);
+	assert_eq!(
+		main_line[2].get_move(19),
+		Some(PlacedPiece::new(White, Pos::new(18, 18)?))
+	);
+	assert_eq!(
+		main_line[3].get_move(19),
+		Some(PlacedPiece::new(Black, Pos::new(18, 0)?))
+	);
+	assert_eq!(main_line[2].children.len(), 2);
+	// "tt" is an old way of writing a pass
+	assert_eq!(
+		main_line[2].children[1].get_move(19),
+		Some(PlacedPiece::pass(Black))
+	);
+	assert_eq!(root.get_move(19), None);
+
+	assert_eq!(sgf::format_point(Pos::new(0, 18)?, 19), "aa");
+	assert_eq!(sgf::format_point(Pos::new(3, 3)?, 9), "df");
+	assert_eq!(sgf::parse_point("df", 9), Some(Pos::new(3, 3)?));
+
+	sgf::parse("").expect_err("Expected an empty collection to be invalid");
+	sgf::parse("(;B[aa]").expect_err("Expected missing `)` to be invalid");
+	sgf::parse("(;C[abc)")
+		.expect_err("Expected unterminated value to be invalid");
+
+	Ok(())
+}
+
+#[test]
+fn test_write_sgf() -> Result<()> {
+	let mut game = Game::new(2);
+	game.do_moves_builder(vec![(White, 2, 16), (Black, 16, 2)])?;
+	let mut root = SgfNode::from_game(&game);
+	root.push("C", "a [bracketed] \\ comment");
+	let s = root.to_string();
+	println!("{}", s);
+	assert!(s.starts_with("(;GM[1]FF[4]SZ[19]HA[2]AB[dp][pd]"));
+	assert!(!s.contains("PL["));
+	assert!(s.ends_with(";W[cc];B[qq])"));
+
+	// parsing it back gives the same tree
+	let parsed = sgf::parse(&s)?;
+	assert_eq!(parsed, vec![root]);
+	assert_eq!(parsed[0].get("C"), Some("a [bracketed] \\ comment"));
+
+	// variations are written as nested trees
+	let tree = &sgf::parse("(;SZ[9](;B[aa];W[bb])(;B[cc]))")?[0];
+	assert_eq!(tree.to_string(), "(;SZ[9]\n(;B[aa];W[bb])\n(;B[cc]))");
+	assert_eq!(sgf::parse(&tree.to_string())?[0], *tree);
+	Ok(())
+}
+
+#[test]
+fn test_sgf_to_game() -> Result<()> {
+	let mut game = Game::with_size(13, 3)?;
+	game.komi = 7.5;
+	game.ruleset = Ruleset::Chinese;
+	game.set_clock(Clock::real("byoyomi:10m+5x30s".parse()?));
+	game.do_moves_builder(vec![(White, 6, 6), (Black, 2, 2), (White, 5, 8)])?;
+	game.set_position(GamePosition::Past(2))?;
+	game.resign(White)?;
+
+	let s = SgfNode::from_game(&game).to_string();
+	println!("{}", s);
+	let loaded = sgf::parse(&s)?[0].to_game()?;
+	assert_eq!(loaded.board.size(), 13);
+	assert_eq!(loaded.handicap, 3);
+	assert_eq!(loaded.komi, 7.5);
+	assert_eq!(loaded.ruleset, Ruleset::Chinese);
+	assert_eq!(loaded.moves, game.moves);
+	assert_eq!(loaded.position(), GamePosition::Past(2));
+	assert_eq!(loaded.board.hash(), game.board.hash());
+	assert_eq!(
+		loaded.result(),
+		Some(GameResult::Resignation { winner: Black })
+	);
+	assert_eq!(
+		loaded.clock.map(|clock| clock.control().to_string()),
+		Some("byoyomi:10m+5x30s".to_string())
+	);
+
+	// a plain game from elsewhere, with the usual defaults
+	let loaded = sgf::parse("(;SZ[9];B[ee];W[cc])")?[0].to_game()?;
+	assert_eq!(loaded.len(), 2);
+	assert_eq!(loaded.position(), GamePosition::Current);
+	assert_eq!(loaded.komi, 6.5);
+	assert!(loaded.result().is_none());
+
+	// setup stones that aren't handicap stones
+	let loaded =
+		sgf::parse("(;SZ[9]AB[aa][bb]AW[cc]PL[W];W[dd])")?[0].to_game()?;
+	assert_eq!(loaded.setup().map(|s| s.len()), Some(3));
+	assert_eq!(loaded.first_turn(), White);
+	assert_eq!(loaded.len(), 1);
+	assert!(sgf::parse("(;SZ[9]PL[X])")?[0].to_game().is_err());
+	Ok(())
+}
+
+#[test]
+fn test_sgf_variations() -> Result<()> {
+	let s = "(;SZ[9];B[ee](;W[cc];B[gg])(;W[gc])(;W[cg]))";
+	let mut game = sgf::parse(s)?[0].to_game()?;
+	assert_eq!(game.len(), 3);
+	assert_eq!(game.variations().count_leaves(), 3);
+	game.set_turn(1)?;
+	assert_eq!(game.alternatives().len(), 3);
+
+	let written = SgfNode::from_game(&game).to_string();
+	println!("{}", written);
+	let loaded = sgf::parse(&written)?[0].to_game()?;
+	assert_eq!(loaded.variations(), game.variations());
+	assert_eq!(loaded.moves, game.moves);
+
+	// the line followed is written as the main line, up to the moves played
+	let other = vec![game.moves[0], PlacedPiece::new(White, Pos::new(6, 6)?)];
+	game.go_to_variation(&other)?;
+	let loaded =
+		sgf::parse(&SgfNode::from_game(&game).to_string())?[0].to_game()?;
+	assert_eq!(loaded.moves, other);
+	game.undo()?;
+	let loaded =
+		sgf::parse(&SgfNode::from_game(&game).to_string())?[0].to_game()?;
+	assert_eq!(loaded.moves, other[..1].to_vec());
+	assert_eq!(loaded.variations().count_leaves(), 3);
+
+	// a variation with an illegal move
+	assert!(sgf::parse("(;SZ[9];B[ee](;W[cc])(;W[ee]))")?[0]
+		.to_game()
+		.is_err());
+	Ok(())
+}
+
+#[test]
+fn test_sgf_annotations() -> Result<()> {
+	let s = "(;SZ[9]C[Black to play];B[ee]C[The centre]TE[1]TR[dd][fd]MA[cc:cd]\
+		LB[gg:a][gc:b \\] c];W[cc]BM[1](;B[dc]DO[])(;B[cd]IT[]SQ[aa]CR[ii]))";
+	let mut game = sgf::parse(s)?[0].to_game()?;
+	game.set_turn(0)?;
+	assert_eq!(game.annotations().comment.as_deref(), Some("Black to play"));
+	game.set_turn(1)?;
+	let notes = game.annotations().clone();
+	assert_eq!(notes.comment.as_deref(), Some("The centre"));
+	assert_eq!(notes.quality, Some(MoveQuality::Good));
+	assert_eq!(notes.markup.len(), 6);
+	assert_eq!(notes.mark(Pos::new(3, 5)?), Some(&Mark::Triangle));
+	assert_eq!(notes.mark(Pos::new(2, 5)?), Some(&Mark::Cross));
+	assert_eq!(
+		notes.mark(Pos::new(6, 6)?),
+		Some(&Mark::Label("b ] c".to_string()))
+	);
+	game.set_turn(2)?;
+	assert_eq!(game.annotations().quality, Some(MoveQuality::Bad));
+	let mut line = game.moves[..2].to_vec();
+	line.push(PlacedPiece::new(Black, Pos::new(2, 5)?));
+	game.go_to_variation(&line)?;
+	assert_eq!(game.annotations().quality, Some(MoveQuality::Interesting));
+	assert_eq!(game.annotations().markup.len(), 2);
+
+	let written = SgfNode::from_game(&game).to_string();
+	println!("{}", written);
+	let loaded = sgf::parse(&written)?[0].to_game()?;
+	// the variation followed is now the main line
+	assert_eq!(loaded.moves, game.moves);
+	assert_eq!(loaded.annotations(), game.annotations());
+	assert_eq!(loaded.variations().count_leaves(), 2);
+
+	// marks are kept on a point of their own
+	let corner = Pos::new(0, 8)?;
+	game.annotations_mut().set_mark(corner, Some(Mark::Circle));
+	assert_eq!(game.annotations().markup.len(), 2);
+	game.annotations_mut().set_mark(corner, None);
+	assert_eq!(game.annotations().markup.len(), 1);
+	Ok(())
+}
+
+#[test]
+fn test_sgf_setup() -> Result<()> {
+	// a handicap stone cleared, and one added
+	let game = sgf::parse("(;SZ[9]HA[2]AB[gc][cg][ee]AE[gc])")?[0].to_game()?;
+	assert_eq!(game.handicap, 2);
+	assert_eq!(game.board.num_pieces_all(), 2);
+	assert_eq!(game.board.get_color(Pos::new(6, 2)?)?, None);
+	assert_eq!(game.first_turn(), White);
+
+	let root = SgfNode::from_game(&game);
+	assert_eq!(root.get("PL"), None);
+	let loaded = sgf::parse(&root.to_string())?[0].to_game()?;
+	assert_eq!(loaded.board.hash(), game.board.hash());
+	assert_eq!(loaded.setup(), game.setup());
+
+	// handicap stones alone aren't a setup
+	let game = sgf::parse("(;SZ[9]HA[2]AB[gc][cg])")?[0].to_game()?;
+	assert!(game.setup().is_none());
+
+	// handicap stones on a board with no usual place for them
+	let game = sgf::parse("(;SZ[7]HA[2]AB[cc][ee];W[dd])")?[0].to_game()?;
+	assert_eq!(game.handicap, 2);
+	assert_eq!(game.board.num_pieces_all(), 3);
+	assert_eq!(game.current_turn_color(), Black);
+	let loaded =
+		sgf::parse(&SgfNode::from_game(&game).to_string())?[0].to_game()?;
+	assert_eq!(loaded.board.hash(), game.board.hash());
+	assert!(sgf::parse("(;SZ[7]HA[2])")?[0].to_game().is_err());
+
+	// setup partway through can't be played through
+	assert!(sgf::parse("(;SZ[9];B[cc];AW[ee];W[gg])")?[0]
+		.to_game()
+		.is_err());
+	Ok(())
+}
+
+#[test]
+fn test_sgf_first_player() -> Result<()> {
+	// the handicap stones where the file puts them, and white starting
+	let game =
+		sgf::parse("(;SZ[19]HA[2]AB[dd][pp];W[qd];B[dp])")?[0].to_game()?;
+	println!("{}", game);
+	assert_eq!(game.handicap, 2);
+	assert_eq!(game.first_turn(), White);
+	assert_eq!(game.len(), 2);
+	assert_eq!(game.board.num_pieces_all(), 4);
+	assert_eq!(game.board.get_color(Pos::new(3, 15)?)?, Some(Black));
+	assert_eq!(game.board.get_color(Pos::new(3, 3)?)?, Some(Black));
+	assert_eq!(game.board.get_color(Pos::new(16, 15)?)?, Some(White));
+
+	// without a first move, `PL` or white after handicap stones says who starts
+	let game = sgf::parse("(;SZ[9]HA[2])")?[0].to_game()?;
+	assert_eq!(game.first_turn(), White);
+	assert!(game.setup().is_none());
+	let game = sgf::parse("(;SZ[9]PL[W])")?[0].to_game()?;
+	assert_eq!(game.first_turn(), White);
+	Ok(())
+}
+
+#[test]
+fn test_sgf_passes() -> Result<()> {
+	let game = sgf::parse("(;B[dd];W[];B[pp])")?[0].to_game()?;
+	assert_eq!(game.len(), 3);
+	assert_eq!(game.moves[1], PlacedPiece::pass(White));
+	assert_eq!(game.board.num_pieces_all(), 2);
+	assert_eq!(game.current_turn_color(), White);
+
+	let s = SgfNode::from_game(&game).to_string();
+	println!("{}", s);
+	assert!(s.ends_with(";B[dd];W[];B[pp])"));
+	assert_eq!(sgf::parse(&s)?[0].to_game()?.moves, game.moves);
+	Ok(())
+}
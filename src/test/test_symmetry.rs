@@ -0,0 +1,145 @@
+use crate::{
+	board::*,
+	error::*,
+	game::*,
+	piece::*,
+	pos::Pos,
+	symmetry::*,
+};
+
+#[test]
+fn test_symmetry_positions() -> Result<()> {
+	for symmetry in Symmetry::ALL.iter() {
+		for &(x, y) in &[(0, 0), (3, 15), (9, 9), (18, 2)] {
+			let pos = Pos::new(x, y)?;
+			let moved = symmetry.apply(pos, 19);
+			assert_eq!(symmetry.inverse().apply(moved, 19), pos);
+		}
+	}
+	let apply = |symmetry: Symmetry, x, y, size| {
+		let pos = symmetry.apply(Pos { x, y }, size);
+		(pos.x, pos.y)
+	};
+	assert_eq!(apply(Symmetry::Rotate90, 0, 0, 19), (18, 0));
+	assert_eq!(apply(Symmetry::Rotate90, 18, 0, 19), (18, 18));
+	assert_eq!(apply(Symmetry::Rotate180, 2, 3, 9), (6, 5));
+	assert_eq!(apply(Symmetry::FlipDiagonal, 2, 3, 9), (3, 2));
+	assert_eq!(apply(Symmetry::FlipAntiDiagonal, 0, 0, 9), (8, 8));
+	Ok(())
+}
+
+#[test]
+fn test_transform_board() -> Result<()> {
+	let mut board = Board::new();
+	board.do_moves_builder(vec![(Black, 3, 3), (White, 2, 5), (Black, 16, 3)])?;
+
+	let rotated = board.transform(Symmetry::Rotate90);
+	println!("{}", rotated);
+	assert_eq!(rotated.get_color(Pos::new(15, 3)?)?, Some(Black));
+	assert_eq!(rotated.get_color(Pos::new(13, 2)?)?, Some(White));
+	assert_eq!(rotated.get_color(Pos::new(15, 16)?)?, Some(Black));
+	assert_eq!(rotated.num_pieces_all(), 3);
+
+	let mut back = rotated.clone();
+	for _ in 0..3 {
+		back = back.transform(Symmetry::Rotate90);
+	}
+	assert_eq!(back.hash(), board.hash());
+
+	let inverted = board.invert_colors();
+	assert_eq!(inverted.get_color(Pos::new(3, 3)?)?, Some(White));
+	assert_eq!(inverted.get_color(Pos::new(2, 5)?)?, Some(Black));
+	assert_ne!(inverted.hash(), board.hash());
+	assert_eq!(inverted.invert_colors().hash(), board.hash());
+
+	Ok(())
+}
+
+#[test]
+fn test_transform_keeps_ko() -> Result<()> {
+	let mut board = Board::new();
+	board.do_moves_builder(vec![
+		(Black, 1, 0),
+		(Black, 0, 1),
+		(Black, 1, 2),
+		(White, 2, 0),
+		(White, 3, 1),
+		(White, 2, 2),
+		(White, 1, 1),
+		(Black, 2, 1),
+	])?;
+	println!("{}", board);
+	board
+		.valid_move(PlacedPiece::new(White, Pos::new(1, 1)?))
+		.expect_err("Expected retaking the ko to be invalid");
+
+	let flipped = board.transform(Symmetry::FlipHorizontal);
+	flipped
+		.valid_move(PlacedPiece::new(White, Pos::new(17, 1)?))
+		.expect_err("Expected ko to survive the transform");
+
+	Ok(())
+}
+
+#[test]
+fn test_canonical() -> Result<()> {
+	let mut board = Board::new();
+	board.do_moves_builder(vec![(Black, 3, 3), (White, 2, 5), (Black, 16, 3)])?;
+	let canonical = board.canonical();
+	println!("{}", canonical.board);
+	assert_eq!(
+		board.transform(canonical.symmetry).hash(),
+		canonical.board.hash()
+	);
+
+	for symmetry in Symmetry::ALL.iter() {
+		let other = board.transform(*symmetry).canonical();
+		assert_eq!(other.hash, canonical.hash);
+	}
+	assert_ne!(board.invert_colors().canonical().hash, canonical.hash);
+	assert_eq!(canonical.symmetries.len(), 1);
+	assert_eq!(Board::new().canonical().symmetries.len(), 8);
+
+	Ok(())
+}
+
+#[test]
+fn test_canonical_small_board() -> Result<()> {
+	let mut board = Board::with_size(9)?;
+	board.do_moves_builder(vec![(Black, 2, 2), (White, 6, 3)])?;
+	println!("{}", board);
+
+	let canonical = board.canonical();
+	for symmetry in Symmetry::ALL.iter() {
+		assert_eq!(board.transform(*symmetry).canonical().hash, canonical.hash);
+	}
+	assert_ne!(Board::new().hash(), Board::with_size(9)?.hash());
+	Board::with_size(20)
+		.err()
+		.expect("Expected size 20 to be rejected");
+
+	Ok(())
+}
+
+#[test]
+fn test_transform_game() -> Result<()> {
+	let mut game = Game::new(0);
+	game.do_moves_builder(vec![(Black, 3, 3), (White, 15, 16), (Black, 2, 5)])?;
+
+	let transformed = game.transform(Symmetry::FlipVertical);
+	assert_eq!(transformed.moves[0].pos, Pos::new(3, 15)?);
+	assert_eq!(transformed.moves[1].pos, Pos::new(15, 2)?);
+	assert_eq!(
+		transformed.get_board_at_move(3)?.hash(),
+		game
+			.get_board_at_move(3)?
+			.transform(Symmetry::FlipVertical)
+			.hash()
+	);
+
+	let inverted = game.invert_colors();
+	assert_eq!(inverted.moves[0].piece.color, White);
+	assert_eq!(inverted.current_turn_color(), Black);
+
+	Ok(())
+}
@@ -0,0 +1,93 @@
+use crate::{
+	error::*,
+	game::*,
+	piece::*,
+	pos::Pos,
+	tui::*,
+};
+use std::path::PathBuf;
+
+#[test]
+fn test_parse_command() -> Result<()> {
+	assert_eq!(Command::parse("D4", 19)?, Command::Play(Pos::new(3, 3)?));
+	assert_eq!(Command::parse(" j10 ", 19)?, Command::Play(Pos::new(8, 9)?));
+	assert_eq!(Command::parse("Undo", 19)?, Command::Undo);
+	assert_eq!(Command::parse("back", 19)?, Command::Back(1));
+	assert_eq!(Command::parse("forward 5", 19)?, Command::Forward(5));
+	assert_eq!(
+		Command::parse("save Game.sgf", 19)?,
+		Command::Save(Some(PathBuf::from("Game.sgf")))
+	);
+	assert_eq!(Command::parse("Pass", 19)?, Command::Pass);
+	assert_eq!(Command::parse("q", 19)?, Command::Quit);
+
+	assert!(Command::parse("", 19).is_err());
+	assert!(Command::parse("K10", 9).is_err());
+	assert!(Command::parse("back two", 19).is_err());
+	assert!(Command::parse("undo 2", 19).is_err());
+	Ok(())
+}
+
+#[test]
+fn test_tui_play() -> Result<()> {
+	let mut tui = Tui::new(Game::with_size(9, 0)?, None, PathBuf::new());
+	assert_eq!(tui.cursor, Pos::new(4, 4)?);
+	assert_eq!(tui.cursor_cell(), (11, 6));
+
+	// an empty line plays on the cursor
+	tui.move_cursor(-10, 1);
+	assert_eq!(tui.cursor, Pos::new(0, 5)?);
+	tui.enter("");
+	tui.enter("c3");
+	println!("{}", tui.render());
+	assert_eq!(tui.game.len(), 2);
+	assert_eq!(tui.game.board.get_color(Pos::new(0, 5)?)?, Some(Black));
+	assert_eq!(tui.game.board.get_color(Pos::new(2, 2)?)?, Some(White));
+	assert_eq!(tui.cursor, Pos::new(2, 2)?);
+
+	tui.enter("c3");
+	assert!(tui.message.is_some());
+	assert_eq!(tui.game.len(), 2);
+
+	// a move played while looking back goes after the last one
+	tui.enter("back 5");
+	assert_eq!(tui.game.position(), GamePosition::Past(0));
+	tui.enter("e5");
+	assert_eq!(tui.game.position(), GamePosition::Current);
+	assert_eq!(tui.game.len(), 3);
+
+	tui.enter("undo");
+	assert_eq!(tui.game.len(), 2);
+	tui.enter("pass");
+	assert_eq!(tui.game.len(), 3);
+	assert!(tui.game.moves.last().unwrap().is_pass());
+	assert_eq!(tui.game.current_turn_color(), White);
+	tui.enter("resign");
+	assert!(tui.game.result().is_some());
+	tui.enter("quit");
+	assert!(tui.quit);
+	Ok(())
+}
+
+#[test]
+fn test_tui_bot() -> Result<()> {
+	let mut tui = Tui::new(Game::with_size(9, 0)?, Some(White), PathBuf::new());
+	assert!(!tui.bot_move());
+	tui.enter("e5");
+	tui.enter("d4");
+	assert_eq!(tui.game.len(), 1);
+	assert!(tui.bot_move());
+	assert_eq!(tui.game.len(), 2);
+	assert_eq!(tui.game.current_turn_color(), Black);
+
+	// the bot's reply is taken back with the player's move
+	tui.enter("undo");
+	assert_eq!(tui.game.len(), 0);
+	assert_eq!(tui.game.current_turn_color(), Black);
+
+	tui.enter("resign");
+	assert!(!tui.bot_move());
+	println!("{}", tui.render());
+	assert!(tui.render().contains("Game over"));
+	Ok(())
+}
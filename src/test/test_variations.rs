@@ -0,0 +1,88 @@
+use crate::{
+	error::*,
+	game::*,
+	piece::*,
+	pos::Pos,
+	variations::*,
+};
+
+fn line(moves: &[(PieceColor, u8, u8)]) -> Vec<PlacedPiece> {
+	moves
+		.iter()
+		.map(|&(color, x, y)| PlacedPiece::new(color, Pos { x, y }))
+		.collect()
+}
+
+#[test]
+fn test_variations_tree() {
+	let main = line(&[(Black, 3, 3), (White, 15, 15), (Black, 3, 15)]);
+	let other = line(&[(Black, 3, 3), (White, 15, 3)]);
+	let mut tree = Variations::default();
+	tree.add(&main);
+	tree.add(&other);
+	tree.add(&main[..2]);
+	assert_eq!(tree.count_leaves(), 2);
+	assert_eq!(tree.main_line(), main);
+	assert_eq!(
+		tree.find(&main[..1]).unwrap().next_moves(),
+		vec![main[1], other[1]]
+	);
+	assert!(tree.find(&line(&[(Black, 9, 9)])).is_none());
+
+	tree.remove(&main[..2]);
+	assert_eq!(tree.count_leaves(), 1);
+	assert_eq!(tree.main_line(), other);
+}
+
+#[test]
+fn test_game_variations() -> Result<()> {
+	let mut game = Game::with_size(9, 0)?;
+	game.do_moves_builder(vec![(Black, 2, 2), (White, 6, 6), (Black, 6, 2)])?;
+	let other = line(&[(Black, 2, 2), (White, 2, 6)]);
+	game.add_variation(&other)?;
+	assert!(game
+		.add_variation(&line(&[(Black, 2, 2), (Black, 2, 6)]))
+		.is_err());
+	assert_eq!(game.variations().count_leaves(), 2);
+
+	game.set_turn(1)?;
+	assert_eq!(
+		game.alternatives(),
+		vec![PlacedPiece::new(White, Pos { x: 6, y: 6 }), other[1]]
+	);
+
+	// looking at the variation, the next move played there comes first
+	game.go_to_variation(&other[..1])?;
+	game.go_to_variation(&other)?;
+	assert_eq!(game.moves, other);
+	assert_eq!(game.position(), GamePosition::Current);
+	game.set_turn(1)?;
+	assert_eq!(game.alternatives()[0], other[1]);
+
+	// a move played at its end carries the variation on
+	game.set_turn(2)?;
+	game.do_moves_builder(vec![(Black, 4, 4)])?;
+	assert_eq!(game.variations().count_leaves(), 2);
+	game.go_to_variation(&[])?;
+	assert_eq!(game.len(), 3);
+	assert_eq!(game.current_turn(), 0);
+	assert!(game.go_to_variation(&line(&[(Black, 4, 4)])).is_err());
+
+	// taking moves back keeps them in the tree
+	game.go_to_variation(&other)?;
+	game.undo()?;
+	game.undo()?;
+	assert_eq!(game.variations().count_leaves(), 2);
+	game.go_to_variation(&other)?;
+	assert_eq!(game.len(), 3);
+
+	// until the variation is removed
+	game.remove_variation(&other)?;
+	assert_eq!(game.variations().count_leaves(), 1);
+	assert_eq!(game.moves, other[..1].to_vec());
+	assert!(game.remove_variation(&other).is_err());
+	assert!(game.remove_variation(&[]).is_err());
+	game.go_to_variation(&[])?;
+	assert_eq!(game.len(), 3);
+	Ok(())
+}
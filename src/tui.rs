@@ -0,0 +1,253 @@
+//! Playing in a terminal, for when there is no display. The board is drawn
+//! by its colored `Display`, and moves are typed in Go coordinates or picked
+//! with a cursor. The `baduk_tui` binary runs it.
+
+use crate::{
+	bot,
+	error::*,
+	game::*,
+	notation::Vertex,
+	piece::*,
+	pos::Pos,
+	sgf,
+};
+use std::path::PathBuf;
+
+pub const HELP: &str = "Type a point like D4, or move the cursor with the \
+arrow keys and press Enter, to play. PageUp / PageDown step through the game.
+Commands: pass, undo, back [n], forward [n], start, end, save [file], resign, quit";
+
+/// A line typed at the prompt
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+	Play(Pos),
+	Pass,
+	Undo,
+	/// look back this many moves
+	Back(usize),
+	Forward(usize),
+	Start,
+	End,
+	/// save to this file, or the one saved to last
+	Save(Option<PathBuf>),
+	Resign,
+	Help,
+	Quit,
+}
+
+impl Command {
+	/// read a command, checking a point is on a board of `size`
+	pub fn parse(text: &str, size: u8) -> Result<Self> {
+		let unknown = || BadukError::UnknownCommand {
+			text: text.trim().to_string(),
+		};
+		let words: Vec<&str> = text.split_whitespace().collect();
+		let (keyword, args) = match words.split_first() {
+			Some((keyword, args)) => (keyword.to_lowercase(), args),
+			None => return Err(unknown()),
+		};
+		let count = |args: &[&str]| match args {
+			[] => Ok(1),
+			[n] => n.parse().map_err(|_| unknown()),
+			_ => Err(unknown()),
+		};
+		let command = match (keyword.as_str(), args) {
+			("undo", []) | ("u", []) => Command::Undo,
+			("back", args) => Command::Back(count(args)?),
+			("forward", args) => Command::Forward(count(args)?),
+			("start", []) => Command::Start,
+			("end", []) => Command::End,
+			("save", []) => Command::Save(None),
+			("save", [path]) => Command::Save(Some(PathBuf::from(path))),
+			("resign", []) => Command::Resign,
+			("help", []) | ("?", []) => Command::Help,
+			("quit", []) | ("q", []) => Command::Quit,
+			(_, []) => match Vertex::parse(words[0], size)? {
+				Vertex::Point(pos) => Command::Play(pos),
+				Vertex::Pass => Command::Pass,
+			},
+			_ => return Err(unknown()),
+		};
+		Ok(command)
+	}
+}
+
+/// A game played in the terminal, with the point the cursor is on
+pub struct Tui {
+	pub game: Game,
+	pub cursor: Pos,
+	/// the color the bot plays, if one is playing
+	pub bot: Option<PieceColor>,
+	/// where `save` writes the game
+	pub path: PathBuf,
+	/// what came of the last command
+	pub message: Option<String>,
+	pub quit: bool,
+}
+
+impl Tui {
+	pub fn new(game: Game, bot: Option<PieceColor>, path: PathBuf) -> Self {
+		let middle = game.board.size() / 2;
+		Self {
+			game,
+			cursor: Pos {
+				x: middle,
+				y: middle,
+			},
+			bot,
+			path,
+			message: None,
+			quit: false,
+		}
+	}
+
+	/// move the cursor, staying on the board
+	pub fn move_cursor(&mut self, dx: i8, dy: i8) {
+		let max = self.game.board.size() as i8 - 1;
+		self.cursor = Pos {
+			x: (self.cursor.x as i8 + dx).max(0).min(max) as u8,
+			y: (self.cursor.y as i8 + dy).max(0).min(max) as u8,
+		};
+	}
+
+	/// A line entered at the prompt. An empty one plays on the cursor.
+	pub fn enter(&mut self, text: &str) {
+		if text.trim().is_empty() {
+			self.run(Command::Play(self.cursor));
+			return;
+		}
+		match Command::parse(text, self.game.board.size()) {
+			Ok(command) => self.run(command),
+			Err(err) => self.message = Some(err.to_string()),
+		}
+	}
+
+	/// carry out a command, keeping what came of it as the message
+	pub fn run(&mut self, command: Command) {
+		self.message = match self.apply(command) {
+			Ok(message) => message,
+			Err(err) => Some(err.to_string()),
+		};
+	}
+
+	fn apply(&mut self, command: Command) -> Result<Option<String>> {
+		let message = match command {
+			Command::Play(pos) => {
+				self.cursor = pos;
+				self.play(|color| PlacedPiece::new(color, pos))?
+			}
+			Command::Pass => self.play(PlacedPiece::pass)?,
+			Command::Undo => {
+				let mut m = self.game.undo()?;
+				// against the bot, take back its reply and the move before it
+				if self.bot == Some(m.piece.color) && !self.game.is_empty() {
+					m = self.game.undo()?;
+				}
+				Some(format!("Took back {}", m.vertex()))
+			}
+			Command::Back(n) => {
+				self.game.offset_turn(-(n as i32));
+				None
+			}
+			Command::Forward(n) => {
+				self.game.offset_turn(n as i32);
+				None
+			}
+			Command::Start => {
+				self.game.set_turn(0)?;
+				None
+			}
+			Command::End => {
+				self.game.set_turn(self.game.len())?;
+				None
+			}
+			Command::Save(path) => {
+				if let Some(path) = path {
+					self.path = path;
+				}
+				sgf::save_game(&self.path, &self.game)?;
+				Some(format!("Saved {}", self.path.display()))
+			}
+			Command::Resign => {
+				// against the bot, the player always resigns for themselves
+				let color = match self.bot {
+					Some(color) => color.opposite(),
+					None => self.game.current_turn_color(),
+				};
+				Some(format!("Game over: {}", self.game.resign(color)?))
+			}
+			Command::Help => Some(HELP.to_string()),
+			Command::Quit => {
+				self.quit = true;
+				None
+			}
+		};
+		Ok(message)
+	}
+
+	/// play the move `m` makes for whoever's turn it is, unless it's the bot's
+	fn play(
+		&mut self,
+		m: impl FnOnce(PieceColor) -> PlacedPiece,
+	) -> Result<Option<String>> {
+		let color = self.game.current_turn_color();
+		if self.bot == Some(color) {
+			return Ok(Some("It is the bot's turn".to_string()));
+		}
+		// a move is always played after the last one
+		self.game.set_turn(self.game.len())?;
+		self.game.do_move(m(color))?;
+		Ok(None)
+	}
+
+	/// Play the bot's move, if it is its turn. It resigns when it has
+	/// nothing left worth playing. Whether the game changed.
+	pub fn bot_move(&mut self) -> bool {
+		let color = self.game.current_turn_color();
+		if self.bot != Some(color) || self.game.result().is_some() {
+			return false;
+		}
+		let res = match bot::choose_move(&self.game) {
+			Some(m) => self
+				.game
+				.do_move(m)
+				.map(|_| format!("The bot played {}", m.vertex())),
+			None => self
+				.game
+				.resign(color)
+				.map(|result| format!("Game over: {}", result)),
+		};
+		let played = res.is_ok();
+		self.message = Some(match res {
+			Ok(message) => message,
+			Err(err) => err.to_string(),
+		});
+		played
+	}
+
+	/// the board as it is being looked at, with the game's state under it
+	pub fn render(&self) -> String {
+		let mut lines = vec![self.game.to_string()];
+		if let GamePosition::Past(turn) = self.game.position() {
+			lines.push(format!("Looking at move {} of {}", turn, self.game.len()));
+		}
+		if let Some(result) = self.game.result() {
+			lines.push(format!("Game over: {}", result));
+		}
+		lines.push(format!("Cursor on {}", self.cursor));
+		if let Some(message) = &self.message {
+			lines.push(message.clone());
+		}
+		lines.join("\n")
+	}
+
+	/// The column and row of the cursor's point in `render`. The board is
+	/// drawn after a blank line and the column letters, each point followed
+	/// by a line to the next and each row after its number.
+	pub fn cursor_cell(&self) -> (u16, u16) {
+		let max = self.game.board.size() - 1;
+		let column = 3 + 2 * self.cursor.x as u16;
+		let row = 2 + (max - self.cursor.y) as u16;
+		(column, row)
+	}
+}
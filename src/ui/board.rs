@@ -0,0 +1,550 @@
+use bevy::{
+	prelude::*,
+	window::WindowResized,
+};
+
+use crate::ui::{
+	piece::*,
+	GlobalBoard,
+	GlobalEntities,
+	GlobalHover,
+	MaterialHandles,
+	PosValue,
+};
+use crate::{
+	annotation::Mark,
+	game::Game,
+	notation,
+	piece::*,
+	pos::Pos,
+};
+use std::collections::HashMap;
+
+/// room around the board for the coordinate labels
+pub const LABEL_MARGIN: f32 = 20.0;
+/// room above the board for the status text
+pub const STATUS_HEIGHT: f32 = 50.0;
+/// room right of the board for the variation tree and the comments
+pub const PANEL_WIDTH: f32 = 200.0;
+/// room below the variation tree for the comments
+pub const COMMENT_HEIGHT: f32 = 180.0;
+/// the window at a scale of 1, fitting a 19x19 board with 23px points and
+/// the side panel
+pub const WINDOW_WIDTH: f32 = 481.0 + PANEL_WIDTH;
+pub const WINDOW_HEIGHT: f32 = 531.0;
+
+/// the border of the board texture around the outer lines, in points
+const BORDER: f32 = 2.0 / 23.0;
+/// the smallest a point gets, however small the window
+const MIN_POINT: f32 = 8.0;
+/// sizes on top of the stones, in points
+const MARKER_SIZE: f32 = 0.4;
+const NUMBER_FONT_SIZE: f32 = 0.5;
+/// how long captured stones take to fade out
+const FADE_SECONDS: f32 = 0.5;
+
+/// Where the board goes in the window. It is worked out again whenever the
+/// window is resized or the board size changes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoardLayout {
+	/// width and height of the window
+	pub window: (f32, f32),
+	/// lines on the board
+	pub size: u8,
+	/// width of one point, and of a stone
+	pub point: f32,
+	/// bottom left corner of the board, from the bottom left of the window
+	pub left: f32,
+	pub bottom: f32,
+}
+
+impl BoardLayout {
+	/// the largest board that fits in the window, with room for the labels,
+	/// the status text and the variation tree
+	pub fn new(width: f32, height: f32, size: u8) -> Self {
+		let room_x = width - PANEL_WIDTH - 2.0 * LABEL_MARGIN;
+		let room_y = height - STATUS_HEIGHT - 2.0 * LABEL_MARGIN;
+		let points = size as f32 + 2.0 * BORDER;
+		let point = (room_x.min(room_y) / points).max(MIN_POINT);
+		let board = point * points;
+		Self {
+			window: (width, height),
+			size,
+			point,
+			left: ((width - PANEL_WIDTH - board) / 2.0).max(LABEL_MARGIN),
+			bottom: LABEL_MARGIN + ((room_y - board) / 2.0).max(0.0),
+		}
+	}
+
+	/// width of the whole board, borders included
+	pub fn board_width(&self) -> f32 {
+		self.point * (self.size as f32 + 2.0 * BORDER)
+	}
+
+	/// left and bottom of point `pos`, relative to the board
+	pub fn corner(&self, pos: Pos) -> (f32, f32) {
+		(
+			self.point * (BORDER + pos.x as f32),
+			self.point * (BORDER + pos.y as f32),
+		)
+	}
+
+	/// bottom left of point `pos`, moved in by `inset` pixels
+	pub fn offset(&self, pos: Pos, inset: f32) -> Rect<Val> {
+		let (left, bottom) = self.corner(pos);
+		Rect {
+			left: Val::Px(left + inset),
+			bottom: Val::Px(bottom + inset),
+			..Default::default()
+		}
+	}
+
+	/// move and size a node to cover point `pos`, hiding it when the point
+	/// is off the board
+	pub fn place(&self, style: &mut Style, pos: Pos) {
+		style.size = Size::new(Val::Px(self.point), Val::Px(self.point));
+		style.position = self.offset(pos, 0.0);
+		style.display = if pos.x < self.size && pos.y < self.size {
+			Display::Flex
+		} else {
+			Display::None
+		};
+	}
+
+	/// left, bottom and width of the move slider, above the column labels
+	pub fn slider(&self) -> (f32, f32, f32) {
+		let width = self.board_width();
+		(self.left, self.bottom + width + LABEL_MARGIN + 4.0, width)
+	}
+
+	/// left, bottom, width and height of the variation tree, right of the
+	/// row labels
+	pub fn panel(&self) -> (f32, f32, f32, f32) {
+		let (left, bottom, width, height) = self.comment_box();
+		let top = self.window.1 - STATUS_HEIGHT - LABEL_MARGIN;
+		let bottom = bottom + height;
+		(left, bottom, width, (top - bottom).max(0.0))
+	}
+
+	/// left, bottom, width and height of the comments, below the tree
+	pub fn comment_box(&self) -> (f32, f32, f32, f32) {
+		let left = self.left + self.board_width() + LABEL_MARGIN;
+		let width = PANEL_WIDTH - LABEL_MARGIN;
+		(left, LABEL_MARGIN, width, COMMENT_HEIGHT)
+	}
+
+	pub fn point_style(&self, pos: Pos) -> Style {
+		let mut style = Style {
+			position_type: PositionType::Absolute,
+			..Default::default()
+		};
+		self.place(&mut style, pos);
+		style
+	}
+}
+
+impl Default for BoardLayout {
+	fn default() -> Self {
+		Self::new(WINDOW_WIDTH, WINDOW_HEIGHT, 19)
+	}
+}
+
+/// Take `--scale <factor>` out of the command line. Bevy doesn't tell us the
+/// display's DPI, so this is how the window starts bigger on a dense screen.
+pub fn window_scale_from_args(args: &mut Vec<String>) -> Result<f32, String> {
+	let i = match args.iter().position(|arg| arg == "--scale") {
+		Some(i) => i,
+		None => return Ok(1.0),
+	};
+	let value = args.get(i + 1).cloned().unwrap_or_default();
+	args.drain(i..(i + 2).min(args.len()));
+	match value.parse::<f32>() {
+		Ok(scale) if scale > 0.0 => Ok(scale),
+		_ => Err(format!("Invalid scale `{}`", value)),
+	}
+}
+
+/// What is drawn on top of the stones. `L` toggles the marker on the last
+/// move, `M` the move numbers.
+pub struct BoardMarkers {
+	pub last_move: bool,
+	pub move_numbers: bool,
+	/// the turn the board was last drawn at
+	drawn_turn: usize,
+	/// stones captured by the move just drawn, waiting to fade out
+	captured: Vec<PlacedPiece>,
+}
+
+impl Default for BoardMarkers {
+	fn default() -> Self {
+		Self {
+			last_move: true,
+			move_numbers: false,
+			drawn_turn: 0,
+			captured: vec![],
+		}
+	}
+}
+
+/// A coordinate label, laid out again with the board
+pub struct CoordinateLabel;
+
+#[derive(Default)]
+pub struct WindowResizedReader(pub EventReader<WindowResized>);
+
+/// A captured stone fading out
+pub struct CaptureFade(Timer);
+
+pub fn redraw_board(
+	commands: &mut Commands,
+	materials: &MaterialHandles,
+	layout: &BoardLayout,
+	markers: &mut BoardMarkers,
+	board: Entity,
+	game: &Game,
+	ui_pieces: &mut Query<(Entity, &UiPiece)>,
+) {
+	for piece in &mut ui_pieces.iter() {
+		commands.despawn(piece.0);
+	}
+	let turn = game.current_turn();
+	let stones = game.get_board_at_move(turn).unwrap();
+	for piece in stones.all_pieces() {
+		place_ui_piece(
+			commands,
+			layout,
+			board,
+			materials.piece_mat(piece.piece.color, false),
+			piece.pos,
+			true,
+		);
+	}
+
+	let notes = game.annotations();
+	// marks are drawn instead of the last move marker and move numbers
+	let last = if turn > 0 {
+		Some(game.moves[turn - 1])
+			.filter(|m| !m.is_pass())
+			.map(|m| m.pos)
+			.filter(|&pos| notes.mark(pos).is_none())
+	} else {
+		None
+	};
+	if markers.move_numbers {
+		// a point played more than once shows its latest move
+		let mut numbers: HashMap<Pos, usize> = HashMap::new();
+		for (i, m) in game.moves[..turn].iter().enumerate() {
+			numbers.insert(m.pos, i + 1);
+		}
+		for piece in stones.all_pieces() {
+			let number = match numbers.get(&piece.pos) {
+				Some(number) => *number,
+				// handicap stones
+				None => continue,
+			};
+			if notes.mark(piece.pos).is_some() {
+				continue;
+			}
+			let color = if markers.last_move && last == Some(piece.pos) {
+				Color::RED
+			} else {
+				match piece.piece.color {
+					Black => Color::WHITE,
+					White => Color::BLACK,
+				}
+			};
+			let entity = Entity::new();
+			commands
+				.spawn_as_entity(
+					entity,
+					point_label(
+						layout,
+						number.to_string(),
+						piece.pos,
+						color,
+						materials.font,
+					),
+				)
+				.with(UiPiece)
+				.push_children(board, &[entity]);
+		}
+	} else if let (true, Some(pos)) = (markers.last_move, last) {
+		let entity = Entity::new();
+		commands
+			.spawn_as_entity(entity, last_move_marker(layout, materials.red, pos))
+			.with(UiPiece)
+			.push_children(board, &[entity]);
+	}
+
+	for markup in notes.markup.iter() {
+		let on_black = matches!(stones.get_color(markup.pos), Ok(Some(Black)));
+		let entity = Entity::new();
+		match &markup.mark {
+			Mark::Label(text) => {
+				let color = if on_black { Color::WHITE } else { Color::BLACK };
+				commands.spawn_as_entity(
+					entity,
+					point_label(layout, text.clone(), markup.pos, color, materials.font),
+				);
+			}
+			mark => {
+				let material = materials.mark_mat(mark, on_black).unwrap();
+				commands
+					.spawn_as_entity(entity, ui_piece(layout, material, markup.pos, true))
+					.with(PosValue(markup.pos));
+			}
+		}
+		commands.with(UiPiece).push_children(board, &[entity]);
+	}
+
+	// only a move just played, or stepped forward to, fades its captures
+	if turn == markers.drawn_turn + 1 {
+		if let Ok((m, captured)) = game.move_result(turn) {
+			let color = m.piece.color.opposite();
+			markers.captured = captured
+				.into_iter()
+				.map(|pos| PlacedPiece::new(color, pos))
+				.collect();
+		}
+	}
+	markers.drawn_turn = turn;
+	// println!("========== Redrew board ==========");
+}
+
+fn last_move_marker(
+	layout: &BoardLayout,
+	material: Handle<ColorMaterial>,
+	pos: Pos,
+) -> NodeComponents {
+	let size = layout.point * MARKER_SIZE;
+	NodeComponents {
+		style: Style {
+			size: Size::new(Val::Px(size), Val::Px(size)),
+			position_type: PositionType::Absolute,
+			position: layout.offset(pos, (layout.point - size) / 2.0),
+			..Default::default()
+		},
+		material,
+		..Default::default()
+	}
+}
+
+/// a move number or letter on point `pos`
+pub fn point_label(
+	layout: &BoardLayout,
+	value: String,
+	pos: Pos,
+	color: Color,
+	font: Handle<Font>,
+) -> TextComponents {
+	let font_size = layout.point * NUMBER_FONT_SIZE;
+	// roughly centered, characters are about half as wide as they are tall
+	let width = value.len() as f32 * font_size * 0.55;
+	let (left, bottom) = layout.corner(pos);
+	TextComponents {
+		style: Style {
+			size: Size::new(Val::Px(layout.point), Val::Px(layout.point)),
+			position_type: PositionType::Absolute,
+			position: Rect {
+				left: Val::Px(left + (layout.point - width) / 2.0),
+				bottom: Val::Px(bottom + (layout.point - font_size) / 2.0),
+				..Default::default()
+			},
+			..Default::default()
+		},
+		text: Text {
+			value,
+			font,
+			style: TextStyle { font_size, color },
+			..Default::default()
+		},
+		..Default::default()
+	}
+}
+
+pub fn markers_system(
+	mut commands: Commands,
+	materials: Res<MaterialHandles>,
+	layout: Res<BoardLayout>,
+	global_entities: Res<GlobalEntities>,
+	keyboard_input: Res<Input<KeyCode>>,
+	game: Res<Game>,
+	mut markers: ResMut<BoardMarkers>,
+	mut ui_pieces: Query<(Entity, &UiPiece)>,
+) {
+	if keyboard_input.just_pressed(KeyCode::L) {
+		markers.last_move = !markers.last_move;
+	} else if keyboard_input.just_pressed(KeyCode::M) {
+		markers.move_numbers = !markers.move_numbers;
+	} else {
+		return;
+	}
+	redraw_board(
+		&mut commands,
+		&materials,
+		&layout,
+		&mut markers,
+		global_entities.board,
+		&game,
+		&mut ui_pieces,
+	);
+}
+
+/// Draws the stones captured by the last move over the board, each with its
+/// own material so it can fade out by itself.
+pub fn capture_fade_system(
+	mut commands: Commands,
+	time: Res<Time>,
+	materials: Res<MaterialHandles>,
+	layout: Res<BoardLayout>,
+	global_entities: Res<GlobalEntities>,
+	mut markers: ResMut<BoardMarkers>,
+	mut color_materials: ResMut<Assets<ColorMaterial>>,
+	mut fading: Query<(Entity, &mut CaptureFade, &Handle<ColorMaterial>)>,
+) {
+	for piece in markers.captured.drain(..) {
+		let texture = color_materials
+			.get(&materials.piece_mat(piece.piece.color, false))
+			.and_then(|material| material.texture);
+		let material = color_materials.add(ColorMaterial {
+			texture,
+			color: Color::WHITE,
+		});
+		let entity = Entity::new();
+		commands
+			.spawn_as_entity(entity, ui_piece(&layout, material, piece.pos, true))
+			.with(CaptureFade(Timer::from_seconds(FADE_SECONDS)))
+			.with(PosValue(piece.pos))
+			.push_children(global_entities.board, &[entity]);
+	}
+
+	for (entity, mut fade, handle) in &mut fading.iter() {
+		fade.0.tick(time.delta_seconds);
+		if fade.0.finished {
+			commands.despawn(entity);
+			color_materials.remove(&*handle);
+		} else if let Some(material) = color_materials.get_mut(&*handle) {
+			material.color.a = 1.0 - fade.0.elapsed / fade.0.duration;
+		}
+	}
+}
+
+/// Lays the board out again for a new window or board size: the board, the
+/// hover stone, everything on a point, the labels, and the stones.
+pub fn layout_system(
+	mut commands: Commands,
+	materials: Res<MaterialHandles>,
+	global_entities: Res<GlobalEntities>,
+	game: Res<Game>,
+	resized: Res<Events<WindowResized>>,
+	mut reader: ResMut<WindowResizedReader>,
+	mut layout: ResMut<BoardLayout>,
+	mut markers: ResMut<BoardMarkers>,
+	mut boards: Query<(Entity, &mut GlobalBoard)>,
+	mut hovers: Query<(Entity, &mut GlobalHover)>,
+	mut points: Query<(&PosValue, &mut Style)>,
+	mut labels: Query<(Entity, &CoordinateLabel)>,
+	mut ui_pieces: Query<(Entity, &UiPiece)>,
+) {
+	let mut window = layout.window;
+	for event in reader.0.iter(&resized) {
+		window = (event.width as f32, event.height as f32);
+	}
+	let size = game.board.size();
+	if window == layout.window && size == layout.size {
+		return;
+	}
+	*layout = BoardLayout::new(window.0, window.1, size);
+
+	let mut board_style = boards.get_mut::<Style>(global_entities.board).unwrap();
+	let width = layout.board_width();
+	board_style.size = Size::new(Val::Px(width), Val::Px(width));
+	board_style.position = Rect {
+		left: Val::Px(layout.left),
+		bottom: Val::Px(layout.bottom),
+		..Default::default()
+	};
+	let mut hover_style = hovers.get_mut::<Style>(global_entities.hover).unwrap();
+	hover_style.size = Size::new(Val::Px(layout.point), Val::Px(layout.point));
+	for (pos, mut style) in &mut points.iter() {
+		layout.place(&mut style, pos.0);
+	}
+
+	for (entity, _) in &mut labels.iter() {
+		commands.despawn(entity);
+	}
+	spawn_coordinate_labels(&mut commands, materials.font, &layout);
+	redraw_board(
+		&mut commands,
+		&materials,
+		&layout,
+		&mut markers,
+		global_entities.board,
+		&game,
+		&mut ui_pieces,
+	);
+}
+
+/// column letters below and above the board, row numbers on both sides
+pub fn spawn_coordinate_labels(
+	commands: &mut Commands,
+	font: Handle<Font>,
+	layout: &BoardLayout,
+) {
+	let width = layout.board_width();
+	let top = layout.bottom + width;
+	let right = layout.left + width;
+	for i in 0..layout.size {
+		// the middle of column or row `i`
+		let middle = layout.point * (BORDER + i as f32 + 0.5);
+		let column = layout.left + middle;
+		let row = layout.bottom + middle;
+		let letter = notation::column_letter(i).to_string();
+		let number = (i + 1).to_string();
+		let labels = vec![
+			(
+				letter.clone(),
+				column - 4.0,
+				layout.bottom - LABEL_MARGIN + 2.0,
+			),
+			(letter, column - 4.0, top + 2.0),
+			(number.clone(), layout.left - LABEL_MARGIN + 2.0, row - 8.0),
+			(number, right + 2.0, row - 8.0),
+		];
+		for (value, left, bottom) in labels {
+			commands
+				.spawn(coordinate_label(value, font, left, bottom))
+				.with(CoordinateLabel);
+		}
+	}
+}
+
+fn coordinate_label(
+	value: String,
+	font: Handle<Font>,
+	left: f32,
+	bottom: f32,
+) -> TextComponents {
+	TextComponents {
+		style: Style {
+			size: Size::new(Val::Px(LABEL_MARGIN), Val::Px(16.0)),
+			position_type: PositionType::Absolute,
+			position: Rect {
+				left: Val::Px(left),
+				bottom: Val::Px(bottom),
+				..Default::default()
+			},
+			..Default::default()
+		},
+		text: Text {
+			value,
+			font,
+			style: TextStyle {
+				font_size: 14.0,
+				color: Color::WHITE,
+			},
+			..Default::default()
+		},
+		..Default::default()
+	}
+}
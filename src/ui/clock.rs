@@ -0,0 +1,41 @@
+use bevy::prelude::*;
+
+use crate::{
+	clock::*,
+	error::*,
+	game::Game,
+	piece::*,
+};
+
+/// Take `--time <control>` out of the command line, e.g.
+/// `--time byoyomi:10m+5x30s`
+pub fn time_control_from_args(
+	args: &mut Vec<String>,
+) -> Result<Option<TimeControl>> {
+	let i = match args.iter().position(|arg| arg == "--time") {
+		Some(i) => i,
+		None => return Ok(None),
+	};
+	let spec = args.get(i + 1).cloned().unwrap_or_default();
+	args.drain(i..(i + 2).min(args.len()));
+	Ok(Some(spec.parse()?))
+}
+
+/// `B 4:59  W 5:00` while the game is played on time
+pub fn clock_text(game: &Game) -> Option<String> {
+	let clock = game.clock.as_ref()?;
+	let time = |color: PieceColor| match clock.remaining(color) {
+		Some(time) => time.to_string(),
+		None => "0:00".to_string(),
+	};
+	Some(format!("B {}  W {}", time(Black), time(White)))
+}
+
+pub fn clock_system(mut game: ResMut<Game>) {
+	let over = game.result().is_some();
+	if let Some(result) = game.check_time() {
+		if !over {
+			println!("Out of time: {}", result);
+		}
+	}
+}
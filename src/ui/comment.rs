@@ -0,0 +1,168 @@
+use bevy::prelude::*;
+
+use crate::annotation::MoveQuality;
+use crate::game::Game;
+use crate::ui::{
+	board::BoardLayout,
+	GlobalEntities,
+	MaterialHandles,
+};
+
+const COMMENT_FONT_SIZE: f32 = 14.0;
+const COMMENT_LINE_HEIGHT: f32 = 18.0;
+/// how wide a character is, on average, for wrapping the text
+const CHAR_WIDTH: f32 = COMMENT_FONT_SIZE * 0.5;
+
+/// The box the comments on the position are written in
+pub struct CommentBox;
+/// A line of the comment, written again when the position changes
+pub struct CommentLine;
+
+/// What the comment box was last written for
+#[derive(Default)]
+pub struct CommentState {
+	drawn_at: Option<(BoardLayout, Option<String>, Option<MoveQuality>)>,
+}
+
+/// Break `text` into lines of at most `width` characters, at spaces where
+/// it can. Line breaks in the text are kept.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+	let width = width.max(1);
+	let mut lines = vec![];
+	for paragraph in text.lines() {
+		let mut line = String::new();
+		for word in paragraph.split_whitespace() {
+			let len = line.chars().count() + 1 + word.chars().count();
+			if !line.is_empty() && len > width {
+				lines.push(std::mem::take(&mut line));
+			}
+			if !line.is_empty() {
+				line.push(' ');
+			}
+			line.push_str(word);
+			// a word too long for a line of its own is cut up
+			while line.chars().count() > width {
+				let cut = line.char_indices().nth(width).unwrap().0;
+				let rest = line.split_off(cut);
+				lines.push(std::mem::replace(&mut line, rest));
+			}
+		}
+		lines.push(line);
+	}
+	lines
+}
+
+pub fn spawn_comment_box(
+	commands: &mut Commands,
+	materials: &MaterialHandles,
+	layout: &BoardLayout,
+	entity: Entity,
+) {
+	let (left, bottom, width, height) = layout.comment_box();
+	commands
+		.spawn_as_entity(
+			entity,
+			NodeComponents {
+				style: Style {
+					size: Size::new(Val::Px(width), Val::Px(height)),
+					position_type: PositionType::Absolute,
+					position: Rect {
+						left: Val::Px(left),
+						bottom: Val::Px(bottom),
+						..Default::default()
+					},
+					..Default::default()
+				},
+				material: materials.transparent,
+				draw: Draw {
+					is_transparent: true,
+					..Default::default()
+				},
+				..Default::default()
+			},
+		)
+		.with(CommentBox);
+}
+
+/// Writes what is thought of the move and the comment on the position being
+/// looked at, under the variation tree. A comment too long for the box is
+/// cut short.
+pub fn comment_system(
+	mut commands: Commands,
+	materials: Res<MaterialHandles>,
+	layout: Res<BoardLayout>,
+	global_entities: Res<GlobalEntities>,
+	game: Res<Game>,
+	mut state: ResMut<CommentState>,
+	mut boxes: Query<(&CommentBox, &mut Style)>,
+	mut drawn: Query<(Entity, &CommentLine)>,
+) {
+	let notes = game.annotations();
+	let key = (*layout, notes.comment.clone(), notes.quality);
+	if state.drawn_at.as_ref() == Some(&key) {
+		return;
+	}
+	state.drawn_at = Some(key);
+
+	for (entity, _) in &mut drawn.iter() {
+		commands.despawn(entity);
+	}
+	let (left, bottom, width, height) = layout.comment_box();
+	for (_, mut style) in &mut boxes.iter() {
+		style.size = Size::new(Val::Px(width), Val::Px(height));
+		style.position = Rect {
+			left: Val::Px(left),
+			bottom: Val::Px(bottom),
+			..Default::default()
+		};
+	}
+
+	let mut lines = vec![];
+	if let Some(quality) = notes.quality {
+		lines.push((quality.to_string(), Color::RED));
+	}
+	if let Some(comment) = &notes.comment {
+		for line in wrap(comment, (width / CHAR_WIDTH) as usize) {
+			lines.push((line, Color::WHITE));
+		}
+	}
+	let rows = (height / COMMENT_LINE_HEIGHT) as usize;
+	if lines.len() > rows && rows > 0 {
+		lines.truncate(rows);
+		lines[rows - 1].0.push_str("...");
+	}
+
+	let mut entities = vec![];
+	for (i, (value, color)) in lines.into_iter().enumerate() {
+		let entity = Entity::new();
+		commands
+			.spawn_as_entity(
+				entity,
+				TextComponents {
+					style: Style {
+						size: Size::new(Val::Px(width), Val::Px(COMMENT_LINE_HEIGHT)),
+						position_type: PositionType::Absolute,
+						position: Rect {
+							left: Val::Px(0.0),
+							top: Val::Px(i as f32 * COMMENT_LINE_HEIGHT),
+							..Default::default()
+						},
+						..Default::default()
+					},
+					text: Text {
+						value,
+						font: materials.font,
+						style: TextStyle {
+							font_size: COMMENT_FONT_SIZE,
+							color,
+						},
+						..Default::default()
+					},
+					..Default::default()
+				},
+			)
+			.with(CommentLine);
+		entities.push(entity);
+	}
+	commands.push_children(global_entities.comment, &entities);
+}
@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+
+use crate::ui::{
+	menu::AppState,
+	net::NetState,
+};
+use crate::game::Game;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EndRequest {
+	Resign,
+	Draw,
+}
+
+/// `Q` resigns and `D` offers a draw, both waiting for `Y` to confirm
+#[derive(Default)]
+pub struct GameEndState {
+	pub confirming: Option<EndRequest>,
+}
+
+impl GameEndState {
+	pub fn status(&self) -> Option<String> {
+		match self.confirming? {
+			EndRequest::Resign => Some("Resign? Y/N".to_string()),
+			EndRequest::Draw => Some("Offer a draw? Y/N".to_string()),
+		}
+	}
+}
+
+pub fn game_end_system(
+	keyboard_input: Res<Input<KeyCode>>,
+	mut game: ResMut<Game>,
+	mut net: ResMut<NetState>,
+	mut state: ResMut<GameEndState>,
+	app_state: Res<AppState>,
+) {
+	if game.result().is_some() || *app_state != AppState::Playing {
+		state.confirming = None;
+		return;
+	}
+	let request = match state.confirming {
+		None => {
+			if keyboard_input.just_pressed(KeyCode::Q) {
+				state.confirming = Some(EndRequest::Resign);
+			} else if keyboard_input.just_pressed(KeyCode::D) {
+				state.confirming = Some(EndRequest::Draw);
+			}
+			return;
+		}
+		Some(request) => request,
+	};
+	if keyboard_input.just_pressed(KeyCode::N)
+		|| keyboard_input.just_pressed(KeyCode::Escape)
+	{
+		state.confirming = None;
+		return;
+	}
+	if !keyboard_input.just_pressed(KeyCode::Y) {
+		return;
+	}
+	state.confirming = None;
+	let res = match request {
+		EndRequest::Resign => net.resign(&mut game).map(Some),
+		EndRequest::Draw => net.offer_draw(&mut game),
+	};
+	match res {
+		Ok(Some(result)) => println!("Game over: {}", result),
+		Ok(None) => println!("Offered a draw"),
+		Err(err) => println!("{}", err),
+	}
+}
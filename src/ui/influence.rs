@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+
+use crate::ui::{
+	board::BoardLayout,
+	MaterialHandles,
+	PosValue,
+};
+use crate::{
+	game::Game,
+	game_result::GameResult,
+	piece::PieceColor,
+	pos::Pos,
+};
+
+pub struct InfluenceOverlay;
+
+/// Whether the influence overlay is shown, and the last estimate drawn.
+#[derive(Default)]
+pub struct InfluenceState {
+	pub visible: bool,
+	pub estimate: Option<String>,
+	/// the hash of the board the overlay was drawn for
+	drawn_at: Option<u64>,
+}
+
+pub fn influence_overlay(
+	layout: &BoardLayout,
+	material: Handle<ColorMaterial>,
+	pos: Pos,
+) -> NodeComponents {
+	NodeComponents {
+		style: layout.point_style(pos),
+		material,
+		draw: Draw {
+			is_transparent: true,
+			is_visible: true,
+			..Default::default()
+		},
+		..Default::default()
+	}
+}
+
+pub fn influence_system(
+	materials: Res<MaterialHandles>,
+	game: Res<Game>,
+	keyboard_input: Res<Input<KeyCode>>,
+	mut state: ResMut<InfluenceState>,
+	mut overlays: Query<(
+		&InfluenceOverlay,
+		&PosValue,
+		&mut Handle<ColorMaterial>,
+	)>,
+) {
+	if keyboard_input.just_pressed(KeyCode::I) {
+		state.visible = !state.visible;
+		state.drawn_at = None;
+	}
+	let key = game.board.hash();
+	if state.drawn_at == Some(key) {
+		return;
+	}
+	state.drawn_at = Some(key);
+
+	if !state.visible {
+		state.estimate = None;
+		for (_, _, mut material) in &mut overlays.iter() {
+			*material = materials.transparent;
+		}
+		return;
+	}
+
+	let influence = game
+		.get_board_at_move(game.current_turn())
+		.unwrap()
+		.influence();
+	let score = influence.score() as f32 - game.komi;
+	state.estimate = Some(GameResult::score(score).to_string());
+	for (_, pos, mut material) in &mut overlays.iter() {
+		*material = match influence.owner(pos.0) {
+			Some(PieceColor::Black) => materials.influence_b,
+			Some(PieceColor::White) => materials.influence_w,
+			None => materials.transparent,
+		};
+	}
+}
@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+
+use crate::ui::{
+	board::BoardLayout,
+	piece::ui_piece,
+	GlobalEntities,
+	MaterialHandles,
+	PosValue,
+};
+use crate::{
+	game::Game,
+	joseki::JosekiBook,
+	piece::PieceColor,
+};
+
+pub const JOSEKI_PATH: &str = "assets/joseki.sgf";
+
+/// A book continuation drawn as a see-through stone
+pub struct JosekiGhost;
+
+/// The loaded joseki book, and what was last drawn from it.
+pub struct JosekiState {
+	pub book: Option<JosekiBook>,
+	pub visible: bool,
+	pub status: Option<String>,
+	/// the board the continuations were drawn for, and who was to play
+	drawn_at: Option<(u64, PieceColor)>,
+}
+
+impl JosekiState {
+	pub fn load(path: &str) -> Self {
+		let book = match JosekiBook::load(path) {
+			Ok(book) => {
+				println!("Loaded {} joseki from {}", book.len(), path);
+				Some(book)
+			}
+			Err(err) => {
+				println!("No joseki book loaded from {}: {}", path, err);
+				None
+			}
+		};
+		Self {
+			book,
+			visible: true,
+			status: None,
+			drawn_at: None,
+		}
+	}
+}
+
+pub fn joseki_system(
+	mut commands: Commands,
+	materials: Res<MaterialHandles>,
+	layout: Res<BoardLayout>,
+	global_entities: Res<GlobalEntities>,
+	game: Res<Game>,
+	keyboard_input: Res<Input<KeyCode>>,
+	mut state: ResMut<JosekiState>,
+	mut ghosts: Query<(Entity, &JosekiGhost)>,
+) {
+	if keyboard_input.just_pressed(KeyCode::J) {
+		state.visible = !state.visible;
+		state.drawn_at = None;
+	}
+	let key = (game.board.hash(), game.current_turn_color());
+	if state.drawn_at == Some(key) {
+		return;
+	}
+	state.drawn_at = Some(key);
+
+	for (entity, _) in &mut ghosts.iter() {
+		commands.despawn(entity);
+	}
+	state.status = None;
+	if !state.visible {
+		return;
+	}
+	let found = match &state.book {
+		Some(book) => {
+			let board = game.get_board_at_move(game.current_turn()).unwrap();
+			let found = book.lookup(&game.moves[..game.current_turn()], board.size());
+			for corner in found.iter() {
+				for m in corner.continuations.iter() {
+					if !matches!(board.get_color(m.pos), Ok(None)) {
+						continue;
+					}
+					let ghost = Entity::new();
+					commands
+						.spawn_as_entity(
+							ghost,
+							ui_piece(
+								&layout,
+								materials.piece_mat(m.piece.color, true),
+								m.pos,
+								true,
+							),
+						)
+						.with(JosekiGhost)
+						.with(PosValue(m.pos))
+						.push_children(global_entities.board, &[ghost]);
+				}
+			}
+			found
+		}
+		None => return,
+	};
+	if !found.is_empty() {
+		let corners: Vec<&str> =
+			found.iter().map(|c| c.corner.abbreviation()).collect();
+		state.status = Some(format!("Joseki {}", corners.join(" ")));
+	}
+}
@@ -0,0 +1,495 @@
+use bevy::prelude::*;
+
+use crate::ui::{
+	board::{
+		redraw_board,
+		BoardLayout,
+		BoardMarkers,
+	},
+	net::NetState,
+	piece::UiPiece,
+	save::SaveState,
+	GlobalEntities,
+	MaterialHandles,
+};
+use crate::{
+	board::MAX_SIZE,
+	bot,
+	clock::{
+		Clock,
+		TimeControl,
+	},
+	error::*,
+	game::Game,
+	piece::*,
+	rules::Ruleset,
+};
+use std::{
+	sync::{
+		mpsc::{
+			self,
+			Receiver,
+			TryRecvError,
+		},
+		Mutex,
+	},
+	thread,
+};
+
+/// seconds the bot waits before playing, so its moves can be followed
+const BOT_DELAY: f32 = 0.5;
+const BOARD_SIZES: [u8; 3] = [9, 13, 19];
+const TIME_CONTROLS: [&str; 4] = [
+	"absolute:30m",
+	"fischer:5m+10s",
+	"byoyomi:10m+5x30s",
+	"canadian:10m+25/5m",
+];
+const MENU_FONT_SIZE: f32 = 24.0;
+const MENU_ROW_HEIGHT: f32 = 32.0;
+
+/// Which screen is up. The board only takes moves while playing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AppState {
+	Menu,
+	Playing,
+	/// a position being set up by hand
+	Setup,
+}
+
+impl Default for AppState {
+	fn default() -> Self {
+		AppState::Menu
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayerType {
+	Human,
+	Bot,
+}
+
+impl PlayerType {
+	fn toggle(self) -> Self {
+		match self {
+			PlayerType::Human => PlayerType::Bot,
+			PlayerType::Bot => PlayerType::Human,
+		}
+	}
+}
+
+/// Who plays each color in the game being played
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Players {
+	pub black: PlayerType,
+	pub white: PlayerType,
+}
+
+impl Players {
+	pub fn get(&self, color: PieceColor) -> PlayerType {
+		match color {
+			Black => self.black,
+			White => self.white,
+		}
+	}
+}
+
+impl Default for Players {
+	fn default() -> Self {
+		Self {
+			black: PlayerType::Human,
+			white: PlayerType::Human,
+		}
+	}
+}
+
+/// The game being set up in the menu
+#[derive(Clone, Debug)]
+pub struct NewGameConfig {
+	pub size: u8,
+	pub handicap: u8,
+	pub komi: f32,
+	pub ruleset: Ruleset,
+	pub players: Players,
+	pub time_control: Option<TimeControl>,
+}
+
+impl Default for NewGameConfig {
+	fn default() -> Self {
+		Self {
+			size: MAX_SIZE,
+			handicap: 0,
+			komi: Ruleset::default().default_komi(),
+			ruleset: Ruleset::default(),
+			players: Players::default(),
+			time_control: None,
+		}
+	}
+}
+
+impl NewGameConfig {
+	pub fn new_game(&self) -> Result<Game> {
+		let mut game = Game::with_size(self.size, self.handicap)?;
+		game.komi = self.komi;
+		game.ruleset = self.ruleset;
+		if let Some(time_control) = self.time_control {
+			game.set_clock(Clock::real(time_control));
+		}
+		Ok(game)
+	}
+
+	/// Change the setting on `row` one step up or down. Changing the ruleset
+	/// or handicap sets the komi that goes with it.
+	fn step(&mut self, row: MenuRow, up: bool) {
+		let delta: i32 = if up { 1 } else { -1 };
+		match row {
+			MenuRow::Size => {
+				self.size = cycle(&BOARD_SIZES, &self.size, delta);
+			}
+			MenuRow::Handicap => {
+				self.handicap = (self.handicap as i32 + delta).clamp(0, 9) as u8;
+				self.komi = self.even_komi();
+			}
+			MenuRow::Komi => {
+				self.komi = (self.komi + 0.5 * delta as f32).clamp(-50.0, 50.0);
+			}
+			MenuRow::Ruleset => {
+				self.ruleset = cycle(&Ruleset::ALL, &self.ruleset, delta);
+				self.komi = self.even_komi();
+			}
+			MenuRow::Black => self.players.black = self.players.black.toggle(),
+			MenuRow::White => self.players.white = self.players.white.toggle(),
+			MenuRow::Time => {
+				let mut choices: Vec<Option<TimeControl>> = vec![None];
+				choices.extend(TIME_CONTROLS.iter().map(|spec| spec.parse().ok()));
+				if !choices.contains(&self.time_control) {
+					// one given on the command line
+					choices.push(self.time_control);
+				}
+				self.time_control = cycle(&choices, &self.time_control, delta);
+			}
+			MenuRow::Start | MenuRow::Load => {}
+		}
+	}
+
+	fn even_komi(&self) -> f32 {
+		if self.handicap > 0 {
+			0.5
+		} else {
+			self.ruleset.default_komi()
+		}
+	}
+
+	fn row_text(&self, row: MenuRow) -> String {
+		let player = |player: PlayerType| match player {
+			PlayerType::Human => "Human",
+			PlayerType::Bot => "Bot",
+		};
+		match row {
+			MenuRow::Size => format!("Board size: {}x{}", self.size, self.size),
+			MenuRow::Handicap => format!("Handicap: {}", self.handicap),
+			MenuRow::Komi => format!("Komi: {}", self.komi),
+			MenuRow::Ruleset => format!("Rules: {}", self.ruleset),
+			MenuRow::Black => format!("Black: {}", player(self.players.black)),
+			MenuRow::White => format!("White: {}", player(self.players.white)),
+			MenuRow::Time => match self.time_control {
+				Some(time_control) => format!("Time: {}", time_control),
+				None => "Time: none".to_string(),
+			},
+			MenuRow::Start => "Start".to_string(),
+			MenuRow::Load => "Load".to_string(),
+		}
+	}
+}
+
+/// the value `delta` steps away from `current` in `values`, wrapping around
+fn cycle<T: Clone + PartialEq>(values: &[T], current: &T, delta: i32) -> T {
+	let i = values.iter().position(|v| v == current).unwrap_or(0) as i32;
+	let len = values.len() as i32;
+	values[(i + delta).rem_euclid(len) as usize].clone()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuRow {
+	Size,
+	Handicap,
+	Komi,
+	Ruleset,
+	Black,
+	White,
+	Time,
+	Start,
+	Load,
+}
+
+const MENU_ROWS: [MenuRow; 9] = [
+	MenuRow::Size,
+	MenuRow::Handicap,
+	MenuRow::Komi,
+	MenuRow::Ruleset,
+	MenuRow::Black,
+	MenuRow::White,
+	MenuRow::Time,
+	MenuRow::Start,
+	MenuRow::Load,
+];
+
+/// The row picked in the menu, and why the last game couldn't start
+#[derive(Default)]
+pub struct MenuState {
+	pub selected: usize,
+	pub error: Option<String>,
+}
+
+/// A line of the menu, the index into `MENU_ROWS` or past it for the error
+pub struct MenuText(usize);
+
+/// Darkens the board behind the menu
+pub struct MenuBackdrop;
+
+pub fn spawn_menu(commands: &mut Commands, materials: &MaterialHandles) {
+	commands
+		.spawn(NodeComponents {
+			style: Style {
+				size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+				position_type: PositionType::Absolute,
+				..Default::default()
+			},
+			material: materials.menu_backdrop,
+			draw: Draw {
+				is_transparent: true,
+				..Default::default()
+			},
+			..Default::default()
+		})
+		.with(MenuBackdrop);
+	for i in 0..=MENU_ROWS.len() {
+		commands
+			.spawn(TextComponents {
+				style: Style {
+					size: Size::new(Val::Percent(100.0), Val::Px(MENU_FONT_SIZE)),
+					position_type: PositionType::Absolute,
+					position: Rect {
+						top: Val::Px(60.0 + MENU_ROW_HEIGHT * i as f32),
+						left: Val::Px(40.0),
+						..Default::default()
+					},
+					..Default::default()
+				},
+				text: Text {
+					value: String::new(),
+					font: materials.font,
+					style: TextStyle {
+						font_size: MENU_FONT_SIZE,
+						color: Color::WHITE,
+					},
+					..Default::default()
+				},
+				..Default::default()
+			})
+			.with(MenuText(i));
+	}
+}
+
+/// In the menu, `Up` and `Down` pick a setting, `Left` and `Right` change
+/// it, `Enter` starts the game, or loads the saved one, and `Escape` goes
+/// back to the one being played. While playing, `F2` opens the menu.
+pub fn menu_system(
+	mut commands: Commands,
+	materials: Res<MaterialHandles>,
+	layout: Res<BoardLayout>,
+	mut markers: ResMut<BoardMarkers>,
+	global_entities: Res<GlobalEntities>,
+	keyboard_input: Res<Input<KeyCode>>,
+	net: Res<NetState>,
+	mut app_state: ResMut<AppState>,
+	mut menu: ResMut<MenuState>,
+	mut config: ResMut<NewGameConfig>,
+	mut players: ResMut<Players>,
+	mut save: ResMut<SaveState>,
+	mut game: ResMut<Game>,
+	mut ui_pieces: Query<(Entity, &UiPiece)>,
+) {
+	if *app_state != AppState::Menu {
+		if *app_state == AppState::Playing
+			&& keyboard_input.just_pressed(KeyCode::F2)
+		{
+			if net.offline() {
+				*app_state = AppState::Menu;
+			} else {
+				println!("A network game can't be replaced by a new one");
+			}
+		}
+		return;
+	}
+	if keyboard_input.just_pressed(KeyCode::Escape) {
+		menu.error = None;
+		*app_state = AppState::Playing;
+	} else if keyboard_input.just_pressed(KeyCode::Up) {
+		menu.selected = (menu.selected + MENU_ROWS.len() - 1) % MENU_ROWS.len();
+	} else if keyboard_input.just_pressed(KeyCode::Down) {
+		menu.selected = (menu.selected + 1) % MENU_ROWS.len();
+	} else if keyboard_input.just_pressed(KeyCode::Left) {
+		config.step(MENU_ROWS[menu.selected], false);
+	} else if keyboard_input.just_pressed(KeyCode::Right) {
+		config.step(MENU_ROWS[menu.selected], true);
+	} else if keyboard_input.just_pressed(KeyCode::Return) {
+		let res = if MENU_ROWS[menu.selected] == MenuRow::Load {
+			save.load()
+		} else {
+			config.new_game()
+		};
+		match res {
+			Ok(new_game) => {
+				*game = new_game;
+				*players = config.players;
+				menu.error = None;
+				*app_state = AppState::Playing;
+				redraw_board(
+					&mut commands,
+					&materials,
+					&layout,
+					&mut markers,
+					global_entities.board,
+					&game,
+					&mut ui_pieces,
+				);
+			}
+			Err(err) => menu.error = Some(err.to_string()),
+		}
+	}
+}
+
+/// Shows the menu over the board while it is open
+pub fn menu_text_system(
+	app_state: Res<AppState>,
+	menu: Res<MenuState>,
+	config: Res<NewGameConfig>,
+	save: Res<SaveState>,
+	mut texts: Query<(&MenuText, &mut Text)>,
+	mut backdrops: Query<(&MenuBackdrop, &mut Draw)>,
+) {
+	let open = *app_state == AppState::Menu;
+	for (_, mut draw) in &mut backdrops.iter() {
+		draw.is_visible = open;
+	}
+	for (row, mut text) in &mut texts.iter() {
+		let line = |menu_row: MenuRow| match menu_row {
+			MenuRow::Load => format!("Load {}", save.path.display()),
+			_ => config.row_text(menu_row),
+		};
+		let (value, color) = match MENU_ROWS.get(row.0) {
+			_ if !open => (String::new(), Color::WHITE),
+			Some(&menu_row) if row.0 == menu.selected => {
+				(format!("> {}", line(menu_row)), Color::rgb(1.0, 0.85, 0.3))
+			}
+			Some(&menu_row) => (format!("  {}", line(menu_row)), Color::WHITE),
+			None => (menu.error.clone().unwrap_or_default(), Color::RED),
+		};
+		text.value = value;
+		text.style.color = color;
+	}
+}
+
+/// The bot's wait before its next move, and the move it is working out
+pub struct BotState {
+	pub timer: Timer,
+	/// the moves played when the bot started thinking, and where its move
+	/// comes from once the thread choosing it is done
+	thinking: Option<(Vec<PlacedPiece>, Mutex<Receiver<Option<PlacedPiece>>>)>,
+}
+
+impl Default for BotState {
+	fn default() -> Self {
+		Self {
+			timer: Timer::from_seconds(BOT_DELAY),
+			thinking: None,
+		}
+	}
+}
+
+/// Plays the bot's moves, resigning when it has nothing left to play. The
+/// move is chosen on another thread, to keep the frame going meanwhile.
+pub fn bot_system(
+	mut commands: Commands,
+	time: Res<Time>,
+	materials: Res<MaterialHandles>,
+	layout: Res<BoardLayout>,
+	mut markers: ResMut<BoardMarkers>,
+	global_entities: Res<GlobalEntities>,
+	app_state: Res<AppState>,
+	players: Res<Players>,
+	net: Res<NetState>,
+	mut bot: ResMut<BotState>,
+	mut game: ResMut<Game>,
+	mut ui_pieces: Query<(Entity, &UiPiece)>,
+) {
+	let color = game.current_turn_color();
+	if *app_state != AppState::Playing
+		|| !net.offline()
+		|| game.result().is_some()
+		|| players.get(color) != PlayerType::Bot
+	{
+		bot.timer.reset();
+		bot.thinking = None;
+		return;
+	}
+	// think again when the game changed meanwhile, e.g. by an undo
+	if bot
+		.thinking
+		.as_ref()
+		.map_or(true, |(moves, _)| *moves != game.moves)
+	{
+		// the game may be looked at from an earlier move
+		let board = match game.get_board_at_move(game.len()) {
+			Ok(board) => board,
+			Err(err) => {
+				println!("{}", err);
+				return;
+			}
+		};
+		let (sender, receiver) = mpsc::channel();
+		thread::spawn(move || {
+			// nobody is listening any more if the game moved on
+			let _ = sender.send(bot::best_move(&board, color));
+		});
+		bot.thinking = Some((game.moves.clone(), Mutex::new(receiver)));
+	}
+	bot.timer.tick(time.delta_seconds);
+	if !bot.timer.finished {
+		return;
+	}
+	let received = match &bot.thinking {
+		Some((_, receiver)) => receiver.lock().unwrap().try_recv(),
+		None => return,
+	};
+	let choice = match received {
+		Ok(choice) => choice,
+		Err(TryRecvError::Empty) => return,
+		// the thread went away without a move, so think again
+		Err(TryRecvError::Disconnected) => {
+			bot.thinking = None;
+			return;
+		}
+	};
+	bot.timer.reset();
+	bot.thinking = None;
+	let res = match choice {
+		Some(m) => game.do_move(m).map(|_| ()),
+		None => game
+			.resign(color)
+			.map(|result| println!("Game over: {}", result)),
+	};
+	if let Err(err) = res {
+		println!("{}", err);
+	}
+	redraw_board(
+		&mut commands,
+		&materials,
+		&layout,
+		&mut markers,
+		global_entities.board,
+		&game,
+		&mut ui_pieces,
+	);
+}
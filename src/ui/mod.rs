@@ -0,0 +1,513 @@
+//! The Bevy front end. Add [`BadukPlugin`] after the default plugins to get a
+//! playable board in any app.
+
+pub mod board;
+pub mod clock;
+pub mod comment;
+pub mod game_end;
+pub mod influence;
+pub mod joseki;
+pub mod menu;
+pub mod net;
+pub mod piece;
+pub mod review;
+pub mod save;
+pub mod setup;
+pub mod systems;
+pub mod tree;
+
+use bevy::prelude::*;
+
+use crate::{
+	annotation::Mark,
+	board::MAX_SIZE,
+	game::Game,
+	piece::PieceColor,
+	pos::Pos,
+};
+use board::{
+	capture_fade_system,
+	layout_system,
+	markers_system,
+	BoardLayout,
+	BoardMarkers,
+	WindowResizedReader,
+	WINDOW_HEIGHT,
+	WINDOW_WIDTH,
+};
+use clock::clock_system;
+use comment::{
+	comment_system,
+	CommentState,
+};
+use game_end::{
+	game_end_system,
+	GameEndState,
+};
+use influence::*;
+use joseki::{
+	joseki_system,
+	JosekiState,
+	JOSEKI_PATH,
+};
+use menu::{
+	bot_system,
+	menu_system,
+	menu_text_system,
+	AppState,
+	BotState,
+	MenuState,
+	NewGameConfig,
+	Players,
+};
+use net::{
+	net_system,
+	NetState,
+};
+use review::{
+	review_system,
+	slider_system,
+	ReviewState,
+};
+use save::{
+	autosave_system,
+	save_system,
+	SaveState,
+};
+use setup::{
+	setup_system,
+	SetupState,
+};
+use systems::*;
+use tree::{
+	tree_click_system,
+	tree_system,
+	TreeState,
+};
+
+pub struct PosValue(pub Pos);
+
+pub struct GlobalHover;
+pub struct GlobalBoard;
+
+pub struct GlobalEntities {
+	pub board: Entity,
+	pub hover: Entity,
+	pub text: Entity,
+	/// the variation tree panel
+	pub tree: Entity,
+	/// the box the comments are written in
+	pub comment: Entity,
+}
+
+impl std::default::Default for GlobalEntities {
+	fn default() -> Self {
+		Self {
+			board: Entity::new(),
+			hover: Entity::new(),
+			text: Entity::new(),
+			tree: Entity::new(),
+			comment: Entity::new(),
+		}
+	}
+}
+
+/// The textures, font and overlay colors the board is drawn with
+#[derive(Clone, Debug)]
+pub struct BadukTheme {
+	pub board: String,
+	pub piece_b: String,
+	pub piece_w: String,
+	pub font: String,
+	/// white markup shapes, drawn black or white to show on the point
+	pub triangle: String,
+	pub circle: String,
+	pub square: String,
+	pub cross: String,
+	pub influence_b: Color,
+	pub influence_w: Color,
+}
+
+impl Default for BadukTheme {
+	fn default() -> Self {
+		Self {
+			board: "assets/board.png".to_string(),
+			piece_b: "assets/b.png".to_string(),
+			piece_w: "assets/w.png".to_string(),
+			font: "assets/OpenSans-Regular.ttf".to_string(),
+			triangle: "assets/triangle.png".to_string(),
+			circle: "assets/circle.png".to_string(),
+			square: "assets/square.png".to_string(),
+			cross: "assets/cross.png".to_string(),
+			influence_b: Color::rgba(0.0, 0.0, 0.0, 0.35),
+			influence_w: Color::rgba(1.0, 1.0, 1.0, 0.45),
+		}
+	}
+}
+
+pub struct MaterialHandles {
+	pub board: Handle<ColorMaterial>,
+	pub piece_b: Handle<ColorMaterial>,
+	pub piece_b_alpha: Handle<ColorMaterial>,
+	pub piece_w: Handle<ColorMaterial>,
+	pub piece_w_alpha: Handle<ColorMaterial>,
+	pub red: Handle<ColorMaterial>,
+	pub transparent: Handle<ColorMaterial>,
+	pub influence_b: Handle<ColorMaterial>,
+	pub influence_w: Handle<ColorMaterial>,
+	pub menu_backdrop: Handle<ColorMaterial>,
+	pub slider: Handle<ColorMaterial>,
+	/// triangle, circle, square and cross, for empty points and white stones
+	pub marks_dark: [Handle<ColorMaterial>; 4],
+	/// the same for black stones
+	pub marks_light: [Handle<ColorMaterial>; 4],
+	pub font: Handle<Font>,
+}
+
+impl MaterialHandles {
+	pub fn piece_mat(
+		&self,
+		color: PieceColor,
+		alpha: bool,
+	) -> Handle<ColorMaterial> {
+		match color {
+			PieceColor::Black => {
+				if alpha {
+					self.piece_b_alpha
+				} else {
+					self.piece_b
+				}
+			}
+			PieceColor::White => {
+				if alpha {
+					self.piece_w_alpha
+				} else {
+					self.piece_w
+				}
+			}
+		}
+	}
+
+	/// the texture of a mark, drawn light on a black stone. Labels are
+	/// drawn as text.
+	pub fn mark_mat(
+		&self,
+		mark: &Mark,
+		light: bool,
+	) -> Option<Handle<ColorMaterial>> {
+		let marks = if light {
+			&self.marks_light
+		} else {
+			&self.marks_dark
+		};
+		match mark {
+			Mark::Triangle => Some(marks[0]),
+			Mark::Circle => Some(marks[1]),
+			Mark::Square => Some(marks[2]),
+			Mark::Cross => Some(marks[3]),
+			Mark::Label(_) => None,
+		}
+	}
+}
+
+impl FromResources for MaterialHandles {
+	fn from_resources(resources: &Resources) -> Self {
+		let asset_server = resources.get::<AssetServer>().unwrap();
+		let mut materials = resources.get_mut::<Assets<ColorMaterial>>().unwrap();
+		let theme = resources
+			.get::<BadukTheme>()
+			.map(|theme| (*theme).clone())
+			.unwrap_or_default();
+
+		let texture_board = asset_server.load(&theme.board).unwrap();
+		let texture_piece_b = asset_server.load(&theme.piece_b).unwrap();
+		let texture_piece_w = asset_server.load(&theme.piece_w).unwrap();
+		let mark_textures = [
+			asset_server.load(&theme.triangle).unwrap(),
+			asset_server.load(&theme.circle).unwrap(),
+			asset_server.load(&theme.square).unwrap(),
+			asset_server.load(&theme.cross).unwrap(),
+		];
+		let mut marks = |color: Color| {
+			let mut handles = [Handle::default(); 4];
+			for (handle, texture) in handles.iter_mut().zip(mark_textures.iter()) {
+				*handle = materials.add(ColorMaterial {
+					texture: Some(*texture),
+					color,
+				});
+			}
+			handles
+		};
+		let marks_dark = marks(Color::BLACK);
+		let marks_light = marks(Color::WHITE);
+
+		MaterialHandles {
+			board: materials.add(texture_board.into()),
+			piece_b: materials.add(texture_piece_b.into()),
+			piece_b_alpha: materials.add(ColorMaterial {
+				texture: Some(texture_piece_b),
+				color: Color::rgba(1.0, 1.0, 1.0, 0.5),
+			}),
+			piece_w: materials.add(texture_piece_w.into()),
+			piece_w_alpha: materials.add(ColorMaterial {
+				texture: Some(texture_piece_w),
+				color: Color::rgba(1.0, 1.0, 1.0, 0.5),
+			}),
+			red: materials.add(ColorMaterial {
+				color: Color::RED,
+				..Default::default()
+			}),
+			transparent: materials.add(ColorMaterial {
+				color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+				..Default::default()
+			}),
+			influence_b: materials.add(ColorMaterial {
+				color: theme.influence_b,
+				..Default::default()
+			}),
+			influence_w: materials.add(ColorMaterial {
+				color: theme.influence_w,
+				..Default::default()
+			}),
+			menu_backdrop: materials.add(ColorMaterial {
+				color: Color::rgba(0.0, 0.0, 0.0, 0.8),
+				..Default::default()
+			}),
+			slider: materials.add(ColorMaterial {
+				color: Color::rgba(1.0, 1.0, 1.0, 0.3),
+				..Default::default()
+			}),
+			marks_dark,
+			marks_light,
+			font: asset_server.load(&theme.font).unwrap(),
+		}
+	}
+}
+
+/// Everything needed to play on a board: the resources, events and systems,
+/// and the entities spawned at startup.
+///
+/// The app opens on the new game menu, set up for a game of `size` with
+/// `handicap` stones. A `Game`, `NetState`, `AppState`, `NewGameConfig`,
+/// `Players` or `SaveState` added to the app before the plugin is used
+/// instead, e.g. to go straight into a game joined over the network.
+#[derive(Clone, Debug)]
+pub struct BadukPlugin {
+	pub size: u8,
+	pub handicap: u8,
+	pub theme: BadukTheme,
+}
+
+impl Default for BadukPlugin {
+	fn default() -> Self {
+		Self {
+			size: MAX_SIZE,
+			handicap: 0,
+			theme: BadukTheme::default(),
+		}
+	}
+}
+
+impl Plugin for BadukPlugin {
+	fn build(&self, app: &mut AppBuilder) {
+		let has_game = app.resources().get::<Game>().is_some();
+		if !has_game {
+			match Game::with_size(self.size, self.handicap) {
+				Ok(game) => app.add_resource(game),
+				Err(err) => panic!("Could not set up the board: {}", err),
+			};
+		}
+		let has_net = app.resources().get::<NetState>().is_some();
+		if !has_net {
+			app.add_resource(NetState::default());
+		}
+		let has_config = app.resources().get::<NewGameConfig>().is_some();
+		if !has_config {
+			let config = NewGameConfig {
+				size: self.size,
+				handicap: self.handicap,
+				..Default::default()
+			};
+			app.add_resource(config);
+		}
+		let has_players = app.resources().get::<Players>().is_some();
+		if !has_players {
+			app.add_resource(Players::default());
+		}
+		let has_state = app.resources().get::<AppState>().is_some();
+		if !has_state {
+			app.add_resource(AppState::Menu);
+		}
+		let has_save = app.resources().get::<SaveState>().is_some();
+		if !has_save {
+			app.add_resource(SaveState::default());
+		}
+		let has_joseki = app.resources().get::<JosekiState>().is_some();
+		if !has_joseki {
+			app.add_resource(JosekiState::load(JOSEKI_PATH));
+		}
+		let window = app
+			.resources()
+			.get::<WindowDescriptor>()
+			.map(|window| (window.width as f32, window.height as f32))
+			.unwrap_or((WINDOW_WIDTH, WINDOW_HEIGHT));
+		let size = app.resources().get::<Game>().unwrap().board.size();
+
+		app
+			.add_resource(self.theme.clone())
+			.add_resource(BoardLayout::new(window.0, window.1, size))
+			.add_event::<BoardEvent>()
+			.init_resource::<MaterialHandles>()
+			.init_resource::<BoardMarkers>()
+			.add_resource(WindowResizedReader::default())
+			.add_resource(GlobalEntities::default())
+			.add_resource(BoardEventResource(EventReader::default()))
+			.add_resource(MostRecentButtonResource::default())
+			.add_resource(InfluenceState::default())
+			.add_resource(GameEndState::default())
+			.init_resource::<MenuState>()
+			.init_resource::<BotState>()
+			.init_resource::<ReviewState>()
+			.init_resource::<TreeState>()
+			.init_resource::<CommentState>()
+			.init_resource::<SetupState>()
+			.add_startup_system(setup.system())
+			.add_system(layout_system.system())
+			.add_system(mouse_system.system())
+			.add_system(board_events_system.system())
+			.add_system(keyboard_events_system.system())
+			.add_system(undo_system.system())
+			.add_system(markers_system.system())
+			.add_system(capture_fade_system.system())
+			.add_system(influence_system.system())
+			.add_system(joseki_system.system())
+			.add_system(net_system.system())
+			.add_system(clock_system.system())
+			.add_system(game_end_system.system())
+			.add_system(status_text_system.system())
+			.add_system(menu_system.system())
+			.add_system(menu_text_system.system())
+			.add_system(bot_system.system())
+			.add_system(save_system.system())
+			.add_system(autosave_system.system())
+			.add_system(review_system.system())
+			.add_system(slider_system.system())
+			.add_system(tree_system.system())
+			.add_system(tree_click_system.system())
+			.add_system(comment_system.system())
+			.add_system(setup_system.system());
+	}
+}
+
+fn setup(
+	mut commands: Commands,
+	mat_handles: Res<MaterialHandles>,
+	layout: Res<BoardLayout>,
+	global_entities: Res<GlobalEntities>,
+) {
+	let font = mat_handles.font;
+	let board_width = layout.board_width();
+
+	commands
+		.spawn(UiCameraComponents::default())
+		.spawn_as_entity(
+			global_entities.board,
+			NodeComponents {
+				style: Style {
+					display: Display::Flex,
+					size: Size::new(Val::Px(board_width), Val::Px(board_width)),
+					position: Rect {
+						bottom: Val::Px(layout.bottom),
+						left: Val::Px(layout.left),
+						..Default::default()
+					},
+					position_type: PositionType::Absolute,
+					..Default::default()
+				},
+				draw: Draw {
+					is_visible: true,
+					..Default::default()
+				},
+				material: mat_handles.board,
+				..Default::default()
+			},
+		)
+		.with(GlobalBoard)
+		.with_children(|parent| {
+			for i in 0..MAX_SIZE {
+				for j in 0..MAX_SIZE {
+					let pos = Pos { x: i, y: j };
+					parent
+						.spawn(influence_overlay(&layout, mat_handles.transparent, pos))
+						.with(InfluenceOverlay)
+						.with(PosValue(pos));
+				}
+			}
+		})
+		.with_children(|parent| {
+			parent
+				.spawn_as_entity(
+					global_entities.hover,
+					piece::ui_piece(&layout, mat_handles.piece_b, Pos::default(), true),
+				)
+				.with(GlobalHover);
+		})
+		.with_children(|parent| {
+			// every point of the largest board, those off the board are hidden
+			for i in 0..MAX_SIZE {
+				for j in 0..MAX_SIZE {
+					let pos = Pos { x: i, y: j };
+					parent
+						.spawn(ButtonComponents {
+							style: layout.point_style(pos),
+							draw: Draw {
+								is_visible: false,
+								..Default::default()
+							},
+							..Default::default()
+						})
+						.with(PosValue(pos));
+				}
+			}
+		})
+		.spawn_as_entity(
+			global_entities.text,
+			TextComponents {
+				style: Style {
+					size: Size::new(Val::Percent(100.0), Val::Px(20.0)),
+					position_type: PositionType::Absolute,
+					position: Rect {
+						top: Val::Px(10.0),
+						..Default::default()
+					},
+					..Default::default()
+				},
+				text: Text {
+					value: "Hello, world!".to_string(),
+					font,
+					style: TextStyle {
+						font_size: 30.0,
+						color: Color::WHITE,
+					},
+					..Default::default()
+				},
+				..Default::default()
+			},
+		);
+
+	board::spawn_coordinate_labels(&mut commands, font, &layout);
+	review::spawn_slider(&mut commands, &mat_handles, &layout);
+	tree::spawn_tree_panel(
+		&mut commands,
+		&mat_handles,
+		&layout,
+		global_entities.tree,
+	);
+	comment::spawn_comment_box(
+		&mut commands,
+		&mat_handles,
+		&layout,
+		global_entities.comment,
+	);
+	menu::spawn_menu(&mut commands, &mat_handles);
+}
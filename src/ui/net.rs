@@ -0,0 +1,316 @@
+use bevy::prelude::*;
+
+use crate::ui::{
+	board::{
+		BoardLayout,
+		BoardMarkers,
+	},
+	piece::UiPiece,
+	GlobalEntities,
+	MaterialHandles,
+};
+use crate::{
+	board::MoveResult,
+	error::*,
+	game::Game,
+	game_result::GameResult,
+	net::*,
+	piece::*,
+};
+use std::{
+	mem,
+	net::SocketAddr,
+};
+
+pub enum NetMode {
+	Offline,
+	/// waiting for someone to join, or for the guest to come back
+	Hosting(Host),
+	/// the host keeps listening, in case the guest has to resume
+	Playing(NetSession, Option<Host>),
+	/// the guest lost the connection, `R` tries to resume the session
+	Disconnected {
+		addr: SocketAddr,
+		token: u64,
+		color: PieceColor,
+	},
+}
+
+/// The network side of the game, if it is played over the network
+pub struct NetState {
+	pub mode: NetMode,
+	pub status: Option<String>,
+	/// a joined game may already have stones to draw
+	redraw: bool,
+}
+
+impl Default for NetState {
+	fn default() -> Self {
+		Self {
+			mode: NetMode::Offline,
+			status: None,
+			redraw: false,
+		}
+	}
+}
+
+impl NetState {
+	/// Set up from the command line: `--host [port]` waits for a player to
+	/// join, `--join <address>` joins one. Joining gets the game being played.
+	pub fn from_args(args: &[String]) -> Result<(Self, Option<Game>)> {
+		let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+		match args.as_slice() {
+			["--host"] => Self::host(DEFAULT_PORT),
+			["--host", port] => {
+				Self::host(port.parse().map_err(|_| BadukError::Protocol {
+					reason: format!("invalid port `{}`", port),
+				})?)
+			}
+			["--join", addr] => {
+				let addr = if addr.contains(':') {
+					addr.to_string()
+				} else {
+					format!("{}:{}", addr, DEFAULT_PORT)
+				};
+				let (session, game) = NetSession::join(addr.as_str())?;
+				println!("Joined {}, playing {}", addr, session.color);
+				let state = Self {
+					status: Some(format!("Playing {}", session.color)),
+					mode: NetMode::Playing(session, None),
+					redraw: true,
+				};
+				Ok((state, Some(game)))
+			}
+			_ => Ok((Self::default(), None)),
+		}
+	}
+
+	fn host(port: u16) -> Result<(Self, Option<Game>)> {
+		let host = Host::listen(("0.0.0.0", port))?;
+		println!("Waiting for a player on port {}", port);
+		let state = Self {
+			mode: NetMode::Hosting(host),
+			status: Some(format!("Waiting on port {}", port)),
+			redraw: false,
+		};
+		Ok((state, None))
+	}
+
+	/// Play a move from this window, sending it if the game is networked.
+	pub fn play(
+		&mut self,
+		game: &mut Game,
+		m: PlacedPiece,
+	) -> Result<MoveResult> {
+		match &mut self.mode {
+			NetMode::Offline => game.do_move(m),
+			NetMode::Playing(session, _) => session.play(game, m),
+			NetMode::Hosting(_) => Err(BadukError::Protocol {
+				reason: "waiting for an opponent".to_string(),
+			}),
+			NetMode::Disconnected { .. } => Err(BadukError::Disconnected),
+		}
+	}
+
+	/// Resign the color played here, or whoever is to move when both are
+	pub fn resign(&mut self, game: &mut Game) -> Result<GameResult> {
+		match &mut self.mode {
+			NetMode::Offline => game.resign(game.current_turn_color()),
+			NetMode::Playing(session, _) => session.resign(game),
+			NetMode::Hosting(_) => Err(BadukError::Protocol {
+				reason: "waiting for an opponent".to_string(),
+			}),
+			NetMode::Disconnected { .. } => Err(BadukError::Disconnected),
+		}
+	}
+
+	/// Offer a draw, or accept the one offered. When both colors are played
+	/// here, both agree at once.
+	pub fn offer_draw(&mut self, game: &mut Game) -> Result<Option<GameResult>> {
+		match &mut self.mode {
+			NetMode::Offline => {
+				let turn = game.current_turn_color();
+				game.offer_draw(turn)?;
+				game.offer_draw(turn.opposite())
+			}
+			NetMode::Playing(session, _) => session.offer_draw(game),
+			NetMode::Hosting(_) => Err(BadukError::Protocol {
+				reason: "waiting for an opponent".to_string(),
+			}),
+			NetMode::Disconnected { .. } => Err(BadukError::Disconnected),
+		}
+	}
+
+	/// Take back the last move. In a network game this asks the opponent, or
+	/// accepts their request if they asked first.
+	pub fn undo(&mut self, game: &mut Game) -> Result<Option<PlacedPiece>> {
+		match &mut self.mode {
+			NetMode::Offline => game.undo().map(Some),
+			NetMode::Playing(session, _) if session.undo_requested() => {
+				session.reply_undo(game, true)
+			}
+			NetMode::Playing(session, _) => {
+				session.request_undo(game)?;
+				self.status = Some("Asked to undo".to_string());
+				Ok(None)
+			}
+			NetMode::Hosting(_) => Err(BadukError::Protocol {
+				reason: "waiting for an opponent".to_string(),
+			}),
+			NetMode::Disconnected { .. } => Err(BadukError::Disconnected),
+		}
+	}
+
+	/// turn down the opponent's undo request, if there is one
+	pub fn decline_undo(&mut self, game: &mut Game) -> Result<()> {
+		if let NetMode::Playing(session, _) = &mut self.mode {
+			if session.undo_requested() {
+				session.reply_undo(game, false)?;
+				self.status = None;
+			}
+		}
+		Ok(())
+	}
+
+	/// whether the game is only played in this window
+	pub fn offline(&self) -> bool {
+		matches!(self.mode, NetMode::Offline)
+	}
+
+	/// the color this window plays, or `None` when both are played here
+	pub fn color(&self) -> Option<PieceColor> {
+		match &self.mode {
+			NetMode::Playing(session, _) => Some(session.color),
+			NetMode::Disconnected { color, .. } => Some(*color),
+			_ => None,
+		}
+	}
+}
+
+pub fn net_system(
+	mut commands: Commands,
+	materials: Res<MaterialHandles>,
+	layout: Res<BoardLayout>,
+	mut markers: ResMut<BoardMarkers>,
+	global_entities: Res<GlobalEntities>,
+	keyboard_input: Res<Input<KeyCode>>,
+	mut game: ResMut<Game>,
+	mut state: ResMut<NetState>,
+	mut ui_pieces: Query<(Entity, &UiPiece)>,
+) {
+	if state.redraw {
+		state.redraw = false;
+		crate::ui::board::redraw_board(
+			&mut commands,
+			&materials,
+			&layout,
+			&mut markers,
+			global_entities.board,
+			&game,
+			&mut ui_pieces,
+		);
+	}
+	let mut events: Vec<NetEvent> = vec![];
+	state.mode = match mem::replace(&mut state.mode, NetMode::Offline) {
+		NetMode::Hosting(mut host) => match host.accept(&game, Black) {
+			Ok(Some(session)) => {
+				println!("{} joined", session.peer_addr());
+				state.status = Some(format!("Playing {}", session.color));
+				NetMode::Playing(session, Some(host))
+			}
+			Ok(None) => NetMode::Hosting(host),
+			Err(err) => {
+				// a bad handshake, keep waiting
+				println!("{}", err);
+				NetMode::Hosting(host)
+			}
+		},
+		NetMode::Playing(mut session, host) => match session.update(&mut game) {
+			Ok(received) => {
+				events = received;
+				NetMode::Playing(session, host)
+			}
+			Err(err) => {
+				println!("{}", err);
+				match host {
+					Some(host) => {
+						state.status = Some("Waiting for the guest to return".to_string());
+						NetMode::Hosting(host)
+					}
+					None => {
+						state.status = Some("Disconnected, R to reconnect".to_string());
+						NetMode::Disconnected {
+							addr: session.peer_addr(),
+							token: session.token,
+							color: session.color,
+						}
+					}
+				}
+			}
+		},
+		NetMode::Disconnected { addr, token, color }
+			if keyboard_input.just_pressed(KeyCode::R) =>
+		{
+			match NetSession::resume(addr, token, &mut game) {
+				Ok((session, received)) => {
+					events = received;
+					state.status = Some(format!("Playing {}", session.color));
+					NetMode::Playing(session, None)
+				}
+				Err(err) => {
+					println!("{}", err);
+					state.status = Some(format!("Could not reconnect: {}", err));
+					NetMode::Disconnected { addr, token, color }
+				}
+			}
+		}
+		mode => mode,
+	};
+
+	let mut moved = false;
+	for event in events {
+		match event {
+			NetEvent::Move(_) => moved = true,
+			NetEvent::Desync {
+				number,
+				local,
+				remote,
+			} => {
+				println!(
+					"Out of sync after move {}: {:016x} here, {:016x} there",
+					number, local, remote
+				);
+				state.status = Some(format!("Out of sync after move {}", number));
+			}
+			NetEvent::DrawOffered => {
+				state.status = Some("Draw offered, D to accept".to_string());
+			}
+			NetEvent::Ended(result) => {
+				println!("Game over: {}", result);
+				state.status = None;
+			}
+			NetEvent::UndoRequested => {
+				state.status = Some("Undo? U to accept, X to decline".to_string());
+			}
+			NetEvent::Undone(m) => {
+				println!("Took back {:?}", m);
+				state.status = None;
+				moved = true;
+			}
+			NetEvent::UndoDeclined => {
+				state.status = Some("Undo declined".to_string());
+			}
+		}
+	}
+	if moved {
+		crate::ui::board::redraw_board(
+			&mut commands,
+			&materials,
+			&layout,
+			&mut markers,
+			global_entities.board,
+			&game,
+			&mut ui_pieces,
+		);
+	}
+}
@@ -0,0 +1,45 @@
+use crate::ui::{
+	board::BoardLayout,
+	PosValue,
+};
+use bevy::prelude::*;
+use crate::{
+	piece::PieceColor,
+	pos::Pos,
+};
+
+pub struct UiPiece;
+
+pub fn place_ui_piece(
+	commands: &mut Commands,
+	layout: &BoardLayout,
+	board_entity: Entity,
+	material: Handle<ColorMaterial>,
+	pos: Pos,
+	visible: bool,
+) {
+	let piece_entity = Entity::new();
+	commands
+		.spawn_as_entity(piece_entity, ui_piece(layout, material, pos, visible))
+		.with(UiPiece)
+		.with(PosValue(pos))
+		.push_children(board_entity, &[piece_entity]);
+}
+
+pub fn ui_piece(
+	layout: &BoardLayout,
+	material: Handle<ColorMaterial>,
+	pos: Pos,
+	visible: bool,
+) -> NodeComponents {
+	NodeComponents {
+		style: layout.point_style(pos),
+		material,
+		draw: Draw {
+			is_transparent: true,
+			is_visible: visible,
+			..Default::default()
+		},
+		..Default::default()
+	}
+}
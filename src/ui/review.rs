@@ -0,0 +1,238 @@
+use bevy::{
+	input::mouse::MouseWheel,
+	prelude::*,
+	window::CursorMoved,
+};
+
+use crate::game::Game;
+use crate::ui::{
+	board::{
+		redraw_board,
+		BoardLayout,
+		BoardMarkers,
+	},
+	menu::AppState,
+	piece::UiPiece,
+	GlobalEntities,
+	MaterialHandles,
+};
+
+const SLIDER_HEIGHT: f32 = 8.0;
+const KNOB_WIDTH: f32 = 8.0;
+/// autoplay intervals `[` and `]` step between, in seconds
+const MIN_INTERVAL: f32 = 0.25;
+const MAX_INTERVAL: f32 = 10.0;
+
+/// Autoplay, and what the mouse did since the last frame
+pub struct ReviewState {
+	pub autoplay: bool,
+	/// seconds between moves while autoplaying
+	pub interval: f32,
+	timer: Timer,
+	cursor: Vec2,
+	cursor_reader: EventReader<CursorMoved>,
+	wheel_reader: EventReader<MouseWheel>,
+	drawn_at: Option<(BoardLayout, usize, usize)>,
+}
+
+impl Default for ReviewState {
+	fn default() -> Self {
+		Self {
+			autoplay: false,
+			interval: 1.0,
+			timer: Timer::from_seconds(1.0),
+			cursor: Vec2::default(),
+			cursor_reader: EventReader::default(),
+			wheel_reader: EventReader::default(),
+			drawn_at: None,
+		}
+	}
+}
+
+impl ReviewState {
+	pub fn status(&self) -> Option<String> {
+		if self.autoplay {
+			Some(format!("Autoplay {}s", self.interval))
+		} else {
+			None
+		}
+	}
+
+	fn set_interval(&mut self, interval: f32) {
+		self.interval = interval.clamp(MIN_INTERVAL, MAX_INTERVAL);
+		self.timer = Timer::from_seconds(self.interval);
+	}
+}
+
+/// The bar above the board showing how far into the game the board is,
+/// clicked to jump there
+pub struct SliderTrack;
+pub struct SliderKnob;
+
+pub fn spawn_slider(
+	commands: &mut Commands,
+	materials: &MaterialHandles,
+	layout: &BoardLayout,
+) {
+	let (left, bottom, width) = layout.slider();
+	let knob = Entity::new();
+	commands
+		.spawn(ButtonComponents {
+			style: Style {
+				size: Size::new(Val::Px(width), Val::Px(SLIDER_HEIGHT)),
+				position_type: PositionType::Absolute,
+				position: Rect {
+					left: Val::Px(left),
+					bottom: Val::Px(bottom),
+					..Default::default()
+				},
+				..Default::default()
+			},
+			material: materials.slider,
+			draw: Draw {
+				is_transparent: true,
+				..Default::default()
+			},
+			..Default::default()
+		})
+		.with(SliderTrack)
+		.with_children(|parent| {
+			parent
+				.spawn_as_entity(
+					knob,
+					NodeComponents {
+						style: Style {
+							size: Size::new(Val::Px(KNOB_WIDTH), Val::Px(SLIDER_HEIGHT)),
+							position_type: PositionType::Absolute,
+							..Default::default()
+						},
+						material: materials.red,
+						..Default::default()
+					},
+				)
+				.with(SliderKnob);
+		});
+}
+
+/// The mouse wheel steps through the game, and clicking the slider jumps to
+/// a move. `P` plays through the game from where the board is, or from the
+/// start at the last move, one move every interval. `[` and `]` make the
+/// interval shorter or longer.
+pub fn review_system(
+	mut commands: Commands,
+	time: Res<Time>,
+	materials: Res<MaterialHandles>,
+	layout: Res<BoardLayout>,
+	mut markers: ResMut<BoardMarkers>,
+	global_entities: Res<GlobalEntities>,
+	keyboard_input: Res<Input<KeyCode>>,
+	app_state: Res<AppState>,
+	cursor_moved: Res<Events<CursorMoved>>,
+	wheel: Res<Events<MouseWheel>>,
+	mut state: ResMut<ReviewState>,
+	mut game: ResMut<Game>,
+	mut ui_pieces: Query<(Entity, &UiPiece)>,
+	mut tracks: Query<(&SliderTrack, Mutated<Interaction>)>,
+) {
+	if let Some(event) = state.cursor_reader.latest(&cursor_moved) {
+		state.cursor = event.position;
+	}
+	let mut scrolled = 0.0;
+	for event in state.wheel_reader.iter(&wheel) {
+		scrolled += event.y;
+	}
+	if *app_state != AppState::Playing {
+		state.autoplay = false;
+		return;
+	}
+
+	let old_turn = game.current_turn();
+	if keyboard_input.just_pressed(KeyCode::P) {
+		state.autoplay = !state.autoplay;
+		state.timer.reset();
+		if state.autoplay && old_turn == game.len() {
+			game.set_turn(0).unwrap();
+		}
+	} else if keyboard_input.just_pressed(KeyCode::LBracket) {
+		let interval = state.interval / 2.0;
+		state.set_interval(interval);
+	} else if keyboard_input.just_pressed(KeyCode::RBracket) {
+		let interval = state.interval * 2.0;
+		state.set_interval(interval);
+	}
+
+	// scrolling up goes back
+	if scrolled > 0.0 {
+		game.offset_turn(-1);
+	} else if scrolled < 0.0 {
+		game.offset_turn(1);
+	}
+
+	for (_, interaction) in &mut tracks.iter() {
+		if *interaction == Interaction::Clicked {
+			let (left, _, width) = layout.slider();
+			let along = ((state.cursor.x() - left) / width).max(0.0).min(1.0);
+			let turn = (along * game.len() as f32).round() as usize;
+			game.set_turn(turn).unwrap();
+		}
+	}
+
+	if state.autoplay {
+		state.timer.tick(time.delta_seconds);
+		if state.timer.finished {
+			state.timer.reset();
+			game.offset_turn(1);
+		}
+		if game.current_turn() == game.len() {
+			state.autoplay = false;
+		}
+	}
+
+	if old_turn != game.current_turn() {
+		redraw_board(
+			&mut commands,
+			&materials,
+			&layout,
+			&mut markers,
+			global_entities.board,
+			&game,
+			&mut ui_pieces,
+		);
+	}
+}
+
+/// Keeps the slider above the board, with the knob at the move shown
+pub fn slider_system(
+	layout: Res<BoardLayout>,
+	game: Res<Game>,
+	mut state: ResMut<ReviewState>,
+	mut tracks: Query<(&SliderTrack, &mut Style)>,
+	mut knobs: Query<(&SliderKnob, &mut Style)>,
+) {
+	let key = (*layout, game.current_turn(), game.len());
+	if state.drawn_at == Some(key) {
+		return;
+	}
+	state.drawn_at = Some(key);
+
+	let (left, bottom, width) = layout.slider();
+	for (_, mut style) in &mut tracks.iter() {
+		style.size = Size::new(Val::Px(width), Val::Px(SLIDER_HEIGHT));
+		style.position = Rect {
+			left: Val::Px(left),
+			bottom: Val::Px(bottom),
+			..Default::default()
+		};
+	}
+	let along = if game.is_empty() {
+		1.0
+	} else {
+		game.current_turn() as f32 / game.len() as f32
+	};
+	for (_, mut style) in &mut knobs.iter() {
+		style.position = Rect {
+			left: Val::Px(along * (width - KNOB_WIDTH)),
+			..Default::default()
+		};
+	}
+}
@@ -0,0 +1,237 @@
+use bevy::prelude::*;
+
+use crate::ui::{
+	board::{
+		redraw_board,
+		BoardLayout,
+		BoardMarkers,
+	},
+	menu::{
+		AppState,
+		PlayerType,
+		Players,
+	},
+	net::NetState,
+	piece::UiPiece,
+	GlobalEntities,
+	MaterialHandles,
+};
+use crate::{
+	error::*,
+	game::Game,
+	sgf::{
+		self,
+		load_game,
+		save_game,
+		SgfNode,
+	},
+};
+use std::{
+	fs,
+	path::{
+		Path,
+		PathBuf,
+	},
+};
+
+pub const SAVE_PATH: &str = "game.sgf";
+/// the game in progress, written whenever it changes
+pub const AUTOSAVE_PATH: &str = "autosave.sgf";
+/// the autosave's own property for who plays each color, like `human:bot`
+const PLAYERS: &str = "XP";
+
+/// what the autosave was last written for: the board looked at, the
+/// position the game starts from, the moves, and who plays
+type AutosaveKey = (u64, u64, usize, usize, bool, Players);
+
+/// Where `Ctrl+S` saves and `Ctrl+O` loads, and what was last autosaved
+pub struct SaveState {
+	pub path: PathBuf,
+	/// `None` turns autosaving off
+	pub autosave: Option<PathBuf>,
+	pub status: Option<String>,
+	saved_at: Option<AutosaveKey>,
+}
+
+impl Default for SaveState {
+	fn default() -> Self {
+		Self {
+			path: PathBuf::from(SAVE_PATH),
+			autosave: Some(PathBuf::from(AUTOSAVE_PATH)),
+			status: None,
+			saved_at: None,
+		}
+	}
+}
+
+impl SaveState {
+	/// Take `--sgf <file>` out of the command line, the file to save to and
+	/// load from instead of `game.sgf`
+	pub fn from_args(args: &mut Vec<String>) -> Self {
+		let mut state = Self::default();
+		if let Some(i) = args.iter().position(|arg| arg == "--sgf") {
+			if let Some(path) = args.get(i + 1) {
+				state.path = PathBuf::from(path);
+			}
+			args.drain(i..(i + 2).min(args.len()));
+		}
+		state
+	}
+
+	/// the unfinished game left in the autosave file, if there is one, with
+	/// who was playing it
+	pub fn resume(&self) -> Option<(Game, Players)> {
+		let path = self.autosave.as_ref()?;
+		if !path.exists() {
+			return None;
+		}
+		match read_autosave(path) {
+			Ok((game, players)) if game.result().is_none() => {
+				println!("Resumed the game from {}", path.display());
+				Some((game, players))
+			}
+			Ok(_) => None,
+			Err(err) => {
+				println!("Could not resume from {}: {}", path.display(), err);
+				None
+			}
+		}
+	}
+
+	pub fn save(&mut self, game: &Game) {
+		self.status = Some(match save_game(&self.path, game) {
+			Ok(()) => format!("Saved {}", self.path.display()),
+			Err(err) => format!("Could not save: {}", err),
+		});
+	}
+
+	pub fn load(&mut self) -> Result<Game> {
+		let res = load_game(&self.path);
+		self.status = Some(match &res {
+			Ok(_) => format!("Loaded {}", self.path.display()),
+			Err(err) => format!("Could not load: {}", err),
+		});
+		res
+	}
+}
+
+fn read_autosave(path: &Path) -> Result<(Game, Players)> {
+	let text = fs::read_to_string(path)?;
+	let root = &sgf::parse(&text)?[0];
+	let game = root.to_game()?;
+	let players = root
+		.get(PLAYERS)
+		.and_then(parse_players)
+		.unwrap_or_default();
+	Ok((game, players))
+}
+
+fn format_players(players: Players) -> String {
+	let name = |player: PlayerType| match player {
+		PlayerType::Human => "human",
+		PlayerType::Bot => "bot",
+	};
+	format!("{}:{}", name(players.black), name(players.white))
+}
+
+fn parse_players(text: &str) -> Option<Players> {
+	let player = |name: &str| match name {
+		"human" => Some(PlayerType::Human),
+		"bot" => Some(PlayerType::Bot),
+		_ => None,
+	};
+	let mut names = text.splitn(2, ':');
+	Some(Players {
+		black: player(names.next()?)?,
+		white: player(names.next()?)?,
+	})
+}
+
+/// `Ctrl+S` saves the game, `Ctrl+O` loads the saved one, which also works
+/// from the menu.
+pub fn save_system(
+	mut commands: Commands,
+	materials: Res<MaterialHandles>,
+	layout: Res<BoardLayout>,
+	mut markers: ResMut<BoardMarkers>,
+	global_entities: Res<GlobalEntities>,
+	keyboard_input: Res<Input<KeyCode>>,
+	net: Res<NetState>,
+	mut app_state: ResMut<AppState>,
+	mut state: ResMut<SaveState>,
+	mut game: ResMut<Game>,
+	mut ui_pieces: Query<(Entity, &UiPiece)>,
+) {
+	let ctrl = keyboard_input.pressed(KeyCode::LControl)
+		|| keyboard_input.pressed(KeyCode::RControl);
+	if !ctrl {
+		return;
+	}
+	if keyboard_input.just_pressed(KeyCode::S) {
+		state.save(&game);
+	} else if keyboard_input.just_pressed(KeyCode::O) {
+		if !net.offline() {
+			state.status = Some("Can't load into a network game".to_string());
+			return;
+		}
+		if let Ok(loaded) = state.load() {
+			*game = loaded;
+			*app_state = AppState::Playing;
+			redraw_board(
+				&mut commands,
+				&materials,
+				&layout,
+				&mut markers,
+				global_entities.board,
+				&game,
+				&mut ui_pieces,
+			);
+		}
+	}
+}
+
+/// Writes the game and who plays it to the autosave file whenever they
+/// change, so closing the window at any time keeps it. A finished game, or
+/// one with nothing played or set up yet, is removed so it isn't resumed.
+pub fn autosave_system(
+	game: Res<Game>,
+	players: Res<Players>,
+	net: Res<NetState>,
+	mut state: ResMut<SaveState>,
+) {
+	let path = match &state.autosave {
+		Some(path) if net.offline() => path.clone(),
+		_ => return,
+	};
+	let start = match game.get_board_at_move(0) {
+		Ok(board) => board.hash(),
+		Err(_) => return,
+	};
+	let key = (
+		game.board.hash(),
+		start,
+		game.len(),
+		game.current_turn(),
+		game.result().is_some(),
+		*players,
+	);
+	if state.saved_at == Some(key) {
+		return;
+	}
+	state.saved_at = Some(key);
+	let fresh = game.is_empty() && game.setup().is_none();
+	let res = if game.result().is_some() || fresh {
+		if path.exists() {
+			fs::remove_file(&path)
+		} else {
+			Ok(())
+		}
+	} else {
+		let mut root = SgfNode::from_game(&game);
+		root.push(PLAYERS, &format_players(*players));
+		fs::write(&path, root.to_string())
+	};
+	if let Err(err) = res {
+		println!("Could not autosave to {}: {}", path.display(), err);
+	}
+}
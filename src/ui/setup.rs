@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+
+use crate::ui::{
+	board::{
+		redraw_board,
+		BoardLayout,
+		BoardMarkers,
+	},
+	menu::AppState,
+	net::NetState,
+	piece::UiPiece,
+	systems::BoardEvent,
+	GlobalEntities,
+	MaterialHandles,
+};
+use crate::{
+	board::Board,
+	game::Game,
+	piece::{
+		PieceColor,
+		PlacedPiece,
+	},
+	pos::Pos,
+};
+
+/// The position being set up by hand, and what clicking on it does
+pub struct SetupState {
+	/// the stones put down so far, while setting up
+	board: Option<Board>,
+	/// the color of the stones clicks put down, `None` to clear points
+	tool: Option<PieceColor>,
+	/// who moves first from the position
+	turn: PieceColor,
+	reader: EventReader<BoardEvent>,
+}
+
+impl Default for SetupState {
+	fn default() -> Self {
+		Self {
+			board: None,
+			tool: Some(PieceColor::Black),
+			turn: PieceColor::Black,
+			reader: EventReader::default(),
+		}
+	}
+}
+
+impl SetupState {
+	pub fn status(&self) -> Option<String> {
+		self.board.as_ref()?;
+		let tool = match self.tool {
+			Some(color) => format!("placing {}", color),
+			None => "clearing".to_string(),
+		};
+		Some(format!("Setup: {}, {} to move", tool, self.turn))
+	}
+
+	/// Put down a stone of the tool's color on `pos`, or clear it. Clicking
+	/// a stone of the tool's color takes it away.
+	fn click(&mut self, pos: Pos) {
+		let board = match &mut self.board {
+			Some(board) => board,
+			None => return,
+		};
+		let res = match (self.tool, board.get_color(pos)) {
+			(Some(color), Ok(Some(old))) if color != old => {
+				board.set(PlacedPiece::new(color, pos)).map(|_| ())
+			}
+			(Some(color), Ok(None)) => {
+				board.set(PlacedPiece::new(color, pos)).map(|_| ())
+			}
+			_ => board.remove(pos),
+		};
+		if let Err(err) = res {
+			println!("{}", err);
+		}
+	}
+}
+
+fn stones(board: &Board) -> Vec<PlacedPiece> {
+	board
+		.all_pieces()
+		.iter()
+		.map(|p| PlacedPiece::new(p.piece.color, p.pos))
+		.collect()
+}
+
+/// the position being set up, as a game to draw
+fn preview(board: &Board, turn: PieceColor) -> Game {
+	let mut game = Game::with_size(board.size(), 0).unwrap();
+	game.set_up(stones(board), turn).unwrap();
+	game
+}
+
+/// `E` sets up the position by hand, in a game played on this computer.
+/// Clicks put down stones of the color picked with `B` or `W`, or clear
+/// points with `C`, with no turns or captures, and `Tab` picks who moves
+/// first. `Return` or `E` again plays on from the position, `Escape` leaves
+/// the game as it was.
+pub fn setup_system(
+	mut commands: Commands,
+	materials: Res<MaterialHandles>,
+	layout: Res<BoardLayout>,
+	mut markers: ResMut<BoardMarkers>,
+	global_entities: Res<GlobalEntities>,
+	keyboard_input: Res<Input<KeyCode>>,
+	events: Res<Events<BoardEvent>>,
+	net: Res<NetState>,
+	mut app_state: ResMut<AppState>,
+	mut state: ResMut<SetupState>,
+	mut game: ResMut<Game>,
+	mut ui_pieces: Query<(Entity, &UiPiece)>,
+) {
+	let clicks: Vec<Pos> = state
+		.reader
+		.iter(&events)
+		.filter(|ev| ev.event_type == Interaction::Clicked)
+		.map(|ev| ev.pos)
+		.collect();
+	match *app_state {
+		AppState::Menu => {
+			state.board = None;
+			return;
+		}
+		AppState::Playing => {
+			// a game loaded while setting up
+			state.board = None;
+			if !keyboard_input.just_pressed(KeyCode::E) {
+				return;
+			}
+			if !net.offline() {
+				println!("A network game can't be set up");
+				return;
+			}
+			let board = game.get_board_at_move(game.current_turn()).unwrap();
+			state.board = Some(board);
+			state.turn = game.current_turn_color();
+			*app_state = AppState::Setup;
+		}
+		AppState::Setup => {
+			let done = keyboard_input.just_pressed(KeyCode::Return)
+				|| keyboard_input.just_pressed(KeyCode::E);
+			if done || keyboard_input.just_pressed(KeyCode::Escape) {
+				if let (true, Some(board)) = (done, &state.board) {
+					match game.set_up(stones(board), state.turn) {
+						Ok(()) => println!(
+							"Set up {} stones, {} to move",
+							board.num_pieces_all(),
+							state.turn
+						),
+						Err(err) => println!("{}", err),
+					}
+				}
+				state.board = None;
+				*app_state = AppState::Playing;
+				redraw_board(
+					&mut commands,
+					&materials,
+					&layout,
+					&mut markers,
+					global_entities.board,
+					&game,
+					&mut ui_pieces,
+				);
+				return;
+			}
+			if keyboard_input.just_pressed(KeyCode::B) {
+				state.tool = Some(PieceColor::Black);
+			} else if keyboard_input.just_pressed(KeyCode::W) {
+				state.tool = Some(PieceColor::White);
+			} else if keyboard_input.just_pressed(KeyCode::C) {
+				state.tool = None;
+			} else if keyboard_input.just_pressed(KeyCode::Tab) {
+				state.turn = state.turn.opposite();
+			}
+			if clicks.is_empty() {
+				return;
+			}
+			for pos in clicks {
+				state.click(pos);
+			}
+		}
+	}
+	if let Some(board) = &state.board {
+		redraw_board(
+			&mut commands,
+			&materials,
+			&layout,
+			&mut markers,
+			global_entities.board,
+			&preview(board, state.turn),
+			&mut ui_pieces,
+		);
+	}
+}
@@ -0,0 +1,294 @@
+use crate::ui::{
+	board::{
+		BoardLayout,
+		BoardMarkers,
+	},
+	clock::clock_text,
+	game_end::GameEndState,
+	influence::InfluenceState,
+	joseki::JosekiState,
+	menu::{
+		AppState,
+		PlayerType,
+		Players,
+	},
+	net::NetState,
+	piece::UiPiece,
+	review::ReviewState,
+	save::SaveState,
+	setup::SetupState,
+	GlobalBoard,
+	GlobalEntities,
+	GlobalHover,
+	MaterialHandles,
+	PosValue,
+};
+use bevy::{
+	input::mouse::MouseMotion,
+	prelude::*,
+};
+use crate::{
+	game::Game,
+	piece::{
+		Piece,
+		PieceColor,
+		PlacedPiece,
+	},
+	pos::Pos,
+};
+
+pub struct BoardEvent {
+	pub pos: Pos,
+	pub event_type: Interaction,
+}
+
+#[derive(Default)]
+pub struct BoardEventResource(pub EventReader<BoardEvent>);
+
+#[derive(Default)]
+pub struct MostRecentButtonResource(Pos);
+
+pub fn mouse_system(
+	mut events: ResMut<Events<BoardEvent>>,
+	mut most_recent_button: ResMut<MostRecentButtonResource>,
+	mut button_query: Query<(&Button, Mutated<Interaction>, &PosValue)>,
+) {
+	for (_, interaction, value) in &mut button_query.iter() {
+		let interaction: Mutated<Interaction> = interaction;
+		events.send(BoardEvent {
+			pos: value.0,
+			event_type: *interaction,
+		});
+		most_recent_button.0 = value.0;
+	}
+}
+
+pub fn board_events_system(
+	mut commands: Commands,
+	materials: Res<MaterialHandles>,
+	layout: Res<BoardLayout>,
+	mut markers: ResMut<BoardMarkers>,
+	mut state: ResMut<BoardEventResource>,
+	mut game: ResMut<Game>,
+	mut net: ResMut<NetState>,
+	app_state: Res<AppState>,
+	players: Res<Players>,
+	events: Res<Events<BoardEvent>>,
+	global_entities: Res<GlobalEntities>,
+	global_hover: Query<(Entity, &mut GlobalHover)>,
+	mut ui_pieces: Query<(Entity, &UiPiece)>,
+) {
+	let board_entity = global_entities.board;
+	let hover_entity = global_entities.hover;
+	let mut hover_style = global_hover.get_mut::<Style>(hover_entity).unwrap();
+	let mut hover_draw = global_hover.get_mut::<Draw>(hover_entity).unwrap();
+	let mut hover_mat = global_hover
+		.get_mut::<Handle<ColorMaterial>>(hover_entity)
+		.unwrap();
+
+	let current_turn = game.current_turn_color();
+	// in a network game, only one color is played from this window
+	let our_turn = net.color().map_or(true, |color| color == current_turn)
+		&& players.get(current_turn) == PlayerType::Human;
+
+	for ev in state.0.iter(&events) {
+		let ev: &BoardEvent = ev;
+		if *app_state != AppState::Playing {
+			continue;
+		}
+		match ev.event_type {
+			Interaction::Hovered => {
+				// println!("Hovered on {:?}", ev.pos);
+				layout.place(&mut hover_style, ev.pos);
+				// hover_draw.is_visible = game.board.get_color(ev.pos).is_none();
+				*hover_mat = if our_turn
+					&& game
+						.board
+						.valid_move(PlacedPiece {
+							piece: Piece {
+								color: current_turn,
+							},
+							pos: ev.pos,
+						})
+						.is_ok()
+				{
+					materials.piece_mat(current_turn, true).as_handle()
+				} else {
+					materials.red.as_handle()
+				};
+			}
+			Interaction::Clicked if !our_turn => {
+				println!("It is not your turn");
+			}
+			Interaction::Clicked => {
+				// println!("Clicked on {:?}", ev.pos);
+				match net.play(
+					&mut game,
+					PlacedPiece {
+						piece: Piece {
+							color: current_turn,
+						},
+						pos: ev.pos,
+					},
+				) {
+					Ok(_) => {
+						crate::ui::board::redraw_board(
+							&mut commands,
+							&materials,
+							&layout,
+							&mut markers,
+							board_entity,
+							&game,
+							&mut ui_pieces,
+						);
+					}
+					Err(err) => {
+						println!("{}", err);
+					}
+				};
+			}
+			Interaction::None => {
+				// println!("Exited {:?}", ev.pos);
+			}
+		}
+	}
+}
+
+/// moves stepped by `PageUp` and `PageDown`
+const REVIEW_STEP: i32 = 10;
+
+/// `Left` and `Right` step through the game one move at a time, `PageUp` and
+/// `PageDown` ten, and `Home` and `End` jump to the start and the last move.
+pub fn keyboard_events_system(
+	mut commands: Commands,
+	materials: Res<MaterialHandles>,
+	layout: Res<BoardLayout>,
+	mut markers: ResMut<BoardMarkers>,
+	global_entities: Res<GlobalEntities>,
+	mut game: ResMut<Game>,
+	keyboard_input: Res<Input<KeyCode>>,
+	app_state: Res<AppState>,
+	mut ui_pieces: Query<(Entity, &UiPiece)>,
+) {
+	if *app_state != AppState::Playing {
+		return;
+	}
+	let old_turn = game.current_turn();
+	if keyboard_input.just_pressed(KeyCode::Home) {
+		game.set_turn(0).unwrap();
+	} else if keyboard_input.just_pressed(KeyCode::End) {
+		game.set_turn(game.len()).unwrap();
+	} else {
+		let delta = if keyboard_input.just_pressed(KeyCode::Left) {
+			-1
+		} else if keyboard_input.just_pressed(KeyCode::Right) {
+			1
+		} else if keyboard_input.just_pressed(KeyCode::PageUp) {
+			-REVIEW_STEP
+		} else if keyboard_input.just_pressed(KeyCode::PageDown) {
+			REVIEW_STEP
+		} else {
+			return;
+		};
+		game.offset_turn(delta);
+	}
+	if old_turn != game.current_turn() {
+		println!("Changed turn to {}", game.current_turn());
+		crate::ui::board::redraw_board(
+			&mut commands,
+			&materials,
+			&layout,
+			&mut markers,
+			global_entities.board,
+			&game,
+			&mut ui_pieces,
+		);
+	}
+}
+
+/// `U` takes back the last move, or asks the opponent to. `X` turns down
+/// their request, `N` being taken by the resign and draw confirmation.
+pub fn undo_system(
+	mut commands: Commands,
+	materials: Res<MaterialHandles>,
+	layout: Res<BoardLayout>,
+	mut markers: ResMut<BoardMarkers>,
+	global_entities: Res<GlobalEntities>,
+	keyboard_input: Res<Input<KeyCode>>,
+	mut game: ResMut<Game>,
+	mut net: ResMut<NetState>,
+	app_state: Res<AppState>,
+	mut ui_pieces: Query<(Entity, &UiPiece)>,
+) {
+	if *app_state != AppState::Playing {
+		return;
+	}
+	if keyboard_input.just_pressed(KeyCode::X) {
+		if let Err(err) = net.decline_undo(&mut game) {
+			println!("{}", err);
+		}
+	}
+	if !keyboard_input.just_pressed(KeyCode::U) {
+		return;
+	}
+	match net.undo(&mut game) {
+		Ok(Some(m)) => {
+			println!("Took back {:?}", m);
+			crate::ui::board::redraw_board(
+				&mut commands,
+				&materials,
+				&layout,
+				&mut markers,
+				global_entities.board,
+				&game,
+				&mut ui_pieces,
+			);
+		}
+		Ok(None) => {}
+		Err(err) => println!("{}", err),
+	}
+}
+
+pub fn status_text_system(
+	game: Res<Game>,
+	influence: Res<InfluenceState>,
+	joseki: Res<JosekiState>,
+	net: Res<NetState>,
+	game_end: Res<GameEndState>,
+	save: Res<SaveState>,
+	review: Res<ReviewState>,
+	setup: Res<SetupState>,
+	global_entities: Res<GlobalEntities>,
+	mut text_query: Query<&mut Text>,
+) {
+	let mut text = text_query.get_mut::<Text>(global_entities.text).unwrap();
+	let mut parts = vec![match game.result() {
+		Some(result) => format!("Game over: {}", result),
+		None => format!("{}'s turn", game.current_turn_color()),
+	}];
+	if let Some(clock) = clock_text(&game) {
+		parts.push(clock);
+	}
+	if let Some(estimate) = &influence.estimate {
+		parts.push(format!("{} (est.)", estimate));
+	}
+	if let Some(status) = &joseki.status {
+		parts.push(status.clone());
+	}
+	if let Some(status) = &net.status {
+		parts.push(status.clone());
+	}
+	if let Some(status) = game_end.status() {
+		parts.push(status);
+	}
+	if let Some(status) = &save.status {
+		parts.push(status.clone());
+	}
+	if let Some(status) = review.status() {
+		parts.push(status);
+	}
+	if let Some(status) = setup.status() {
+		parts.push(status);
+	}
+	text.value = parts.join("  ");
+}
@@ -0,0 +1,323 @@
+use bevy::prelude::*;
+
+use crate::ui::{
+	board::{
+		point_label,
+		redraw_board,
+		BoardLayout,
+		BoardMarkers,
+	},
+	menu::AppState,
+	net::NetState,
+	piece::UiPiece,
+	GlobalEntities,
+	MaterialHandles,
+};
+use crate::{
+	game::Game,
+	piece::*,
+	variations::Variations,
+};
+
+/// room for each move in the tree, down for the moves and across for the
+/// variations
+const NODE_SPACING: f32 = 16.0;
+const NODE_SIZE: f32 = 12.0;
+const EDGE_WIDTH: f32 = 2.0;
+/// the red square around the node being looked at
+const CURRENT_SIZE: f32 = 16.0;
+
+/// The panel the variation tree is drawn in
+pub struct TreePanel;
+/// Anything drawn in the tree panel or on the board for it, removed when it
+/// is drawn again
+pub struct TreeEntity;
+/// A node of the tree, with the moves leading to it
+pub struct TreeNode(pub Vec<PlacedPiece>);
+
+/// What the tree was last drawn for
+#[derive(Default)]
+pub struct TreeState {
+	drawn_at: Option<(BoardLayout, usize, Vec<PlacedPiece>, usize)>,
+}
+
+/// A node of the tree where it is drawn, in rows from the start of the game
+/// and lanes for the variations
+struct NodePlace {
+	line: Vec<PlacedPiece>,
+	lane: usize,
+	parent_lane: usize,
+}
+
+/// Every node of the tree, each line of play in its own lane. The main line
+/// is the first lane and a variation takes the next free one, once all the
+/// variations before it have been placed.
+fn place_nodes(variations: &Variations) -> Vec<NodePlace> {
+	let mut places = vec![];
+	let mut next_lane = 1;
+	let mut stack: Vec<(&Variations, Vec<PlacedPiece>, Option<usize>, usize)> =
+		vec![(variations, vec![], Some(0), 0)];
+	while let Some((node, line, lane, parent_lane)) = stack.pop() {
+		let lane = lane.unwrap_or_else(|| {
+			next_lane += 1;
+			next_lane - 1
+		});
+		for (i, (m, child)) in node.children().iter().enumerate().rev() {
+			let mut child_line = line.clone();
+			child_line.push(*m);
+			let child_lane = if i == 0 { Some(lane) } else { None };
+			stack.push((child, child_line, child_lane, lane));
+		}
+		places.push(NodePlace {
+			line,
+			lane,
+			parent_lane,
+		});
+	}
+	places
+}
+
+/// a node's place from the top left of the panel
+fn tree_style(left: f32, top: f32, width: f32, height: f32) -> Style {
+	Style {
+		size: Size::new(Val::Px(width), Val::Px(height)),
+		position_type: PositionType::Absolute,
+		position: Rect {
+			left: Val::Px(left),
+			top: Val::Px(top),
+			..Default::default()
+		},
+		..Default::default()
+	}
+}
+
+pub fn spawn_tree_panel(
+	commands: &mut Commands,
+	materials: &MaterialHandles,
+	layout: &BoardLayout,
+	panel: Entity,
+) {
+	let (left, bottom, width, height) = layout.panel();
+	commands
+		.spawn_as_entity(
+			panel,
+			NodeComponents {
+				style: Style {
+					size: Size::new(Val::Px(width), Val::Px(height)),
+					position_type: PositionType::Absolute,
+					position: Rect {
+						left: Val::Px(left),
+						bottom: Val::Px(bottom),
+						..Default::default()
+					},
+					..Default::default()
+				},
+				material: materials.transparent,
+				draw: Draw {
+					is_transparent: true,
+					..Default::default()
+				},
+				..Default::default()
+			},
+		)
+		.with(TreePanel);
+}
+
+/// Draws the variation tree next to the board, a stone for each move with
+/// the one being looked at in red, scrolled to keep it in view. Where more
+/// than one move was played from the position on the board, they are
+/// lettered on it, the next move in this line first.
+pub fn tree_system(
+	mut commands: Commands,
+	materials: Res<MaterialHandles>,
+	layout: Res<BoardLayout>,
+	global_entities: Res<GlobalEntities>,
+	game: Res<Game>,
+	mut state: ResMut<TreeState>,
+	mut panels: Query<(&TreePanel, &mut Style)>,
+	mut drawn: Query<(Entity, &TreeEntity)>,
+) {
+	let key = (
+		*layout,
+		game.current_turn(),
+		game.moves.clone(),
+		game.variations().count_leaves(),
+	);
+	if state.drawn_at.as_ref() == Some(&key) {
+		return;
+	}
+	state.drawn_at = Some(key);
+
+	for (entity, _) in &mut drawn.iter() {
+		commands.despawn(entity);
+	}
+	let (left, bottom, width, height) = layout.panel();
+	for (_, mut style) in &mut panels.iter() {
+		style.size = Size::new(Val::Px(width), Val::Px(height));
+		style.position = Rect {
+			left: Val::Px(left),
+			bottom: Val::Px(bottom),
+			..Default::default()
+		};
+	}
+
+	let turn = game.current_turn();
+	let rows = (height / NODE_SPACING) as usize;
+	let lanes = (width / NODE_SPACING) as usize;
+	let first_row = turn.saturating_sub(rows / 2);
+	let visible = |row: usize, lane: usize| {
+		row >= first_row && row < first_row + rows && lane < lanes
+	};
+	// the middle of a node, from the top left of the panel
+	let middle = |row: usize, lane: usize| {
+		(
+			(lane as f32 + 0.5) * NODE_SPACING,
+			((row - first_row) as f32 + 0.5) * NODE_SPACING,
+		)
+	};
+	let places = place_nodes(game.variations());
+
+	let mut entities = vec![];
+	for place in places.iter() {
+		let row = place.line.len();
+		if row == 0 || !visible(row, place.lane) || !visible(row - 1, 0) {
+			continue;
+		}
+		let (x, y) = middle(row, place.lane);
+		let (parent_x, parent_y) = middle(row - 1, place.parent_lane);
+		let half = EDGE_WIDTH / 2.0;
+		let mut edges = vec![(x - half, parent_y, EDGE_WIDTH, y - parent_y)];
+		if place.lane != place.parent_lane {
+			edges.push((parent_x, parent_y - half, x - parent_x, EDGE_WIDTH));
+		}
+		for (left, top, width, height) in edges {
+			let entity = Entity::new();
+			commands
+				.spawn_as_entity(
+					entity,
+					NodeComponents {
+						style: tree_style(left, top, width, height),
+						material: materials.slider,
+						..Default::default()
+					},
+				)
+				.with(TreeEntity);
+			entities.push(entity);
+		}
+	}
+
+	let looked_at = &game.moves[..turn];
+	for place in places.iter() {
+		let row = place.line.len();
+		if !visible(row, place.lane) {
+			continue;
+		}
+		let (x, y) = middle(row, place.lane);
+		if place.line.as_slice() == looked_at {
+			let half = CURRENT_SIZE / 2.0;
+			let entity = Entity::new();
+			commands
+				.spawn_as_entity(
+					entity,
+					NodeComponents {
+						style: tree_style(x - half, y - half, CURRENT_SIZE, CURRENT_SIZE),
+						material: materials.red,
+						..Default::default()
+					},
+				)
+				.with(TreeEntity);
+			entities.push(entity);
+		}
+		let material = match place.line.last() {
+			Some(m) => materials.piece_mat(m.piece.color, false),
+			// the start of the game
+			None => materials.slider,
+		};
+		let half = NODE_SIZE / 2.0;
+		let entity = Entity::new();
+		commands
+			.spawn_as_entity(
+				entity,
+				ButtonComponents {
+					style: tree_style(x - half, y - half, NODE_SIZE, NODE_SIZE),
+					material,
+					..Default::default()
+				},
+			)
+			.with(TreeNode(place.line.clone()))
+			.with(TreeEntity);
+		entities.push(entity);
+	}
+	commands.push_children(global_entities.tree, &entities);
+
+	let alternatives = game.alternatives();
+	if alternatives.len() < 2 {
+		return;
+	}
+	for (i, m) in alternatives.into_iter().enumerate() {
+		if m.is_pass() {
+			continue;
+		}
+		let letter = ((b'A' + i as u8) as char).to_string();
+		let entity = Entity::new();
+		commands
+			.spawn_as_entity(
+				entity,
+				point_label(&layout, letter, m.pos, Color::RED, materials.font),
+			)
+			.with(TreeEntity)
+			.push_children(global_entities.board, &[entity]);
+	}
+}
+
+/// Clicking a node of the tree shows the board after its move, following
+/// that variation. `Delete` removes the move on the board from the tree,
+/// with everything played after it.
+pub fn tree_click_system(
+	mut commands: Commands,
+	materials: Res<MaterialHandles>,
+	layout: Res<BoardLayout>,
+	mut markers: ResMut<BoardMarkers>,
+	global_entities: Res<GlobalEntities>,
+	keyboard_input: Res<Input<KeyCode>>,
+	net: Res<NetState>,
+	app_state: Res<AppState>,
+	mut game: ResMut<Game>,
+	mut ui_pieces: Query<(Entity, &UiPiece)>,
+	mut nodes: Query<(&TreeNode, Mutated<Interaction>)>,
+) {
+	let mut clicked = None;
+	for (node, interaction) in &mut nodes.iter() {
+		if *interaction == Interaction::Clicked {
+			clicked = Some(node.0.clone());
+		}
+	}
+	let delete = keyboard_input.just_pressed(KeyCode::Delete);
+	if clicked.is_none() && !delete {
+		return;
+	}
+	// in a network game both boards follow the one line played
+	if *app_state != AppState::Playing || !net.offline() {
+		return;
+	}
+	let res = match clicked {
+		Some(line) => game.go_to_variation(&line),
+		None => {
+			let line = game.moves[..game.current_turn()].to_vec();
+			game.remove_variation(&line)
+		}
+	};
+	if let Err(err) = res {
+		println!("Could not change the variations: {}", err);
+		return;
+	}
+	redraw_board(
+		&mut commands,
+		&materials,
+		&layout,
+		&mut markers,
+		global_entities.board,
+		&game,
+		&mut ui_pieces,
+	);
+}
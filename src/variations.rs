@@ -0,0 +1,119 @@
+use crate::{
+	annotation::Annotations,
+	piece::*,
+	symmetry::Symmetry,
+};
+
+/// Every line a game has been played or read along, as a tree of moves from
+/// the start of the game. The first child of a node is its main line.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Variations {
+	/// notes on the position this node reaches
+	pub annotations: Annotations,
+	children: Vec<(PlacedPiece, Variations)>,
+}
+
+impl Variations {
+	/// the moves played from here, the main line first
+	pub fn children(&self) -> &[(PlacedPiece, Variations)] {
+		&self.children
+	}
+
+	pub fn next_moves(&self) -> Vec<PlacedPiece> {
+		self.children.iter().map(|(m, _)| *m).collect()
+	}
+
+	/// the node reached by playing `line` from here
+	pub fn find(&self, line: &[PlacedPiece]) -> Option<&Variations> {
+		let mut node = self;
+		for m in line {
+			node = &node.children.iter().find(|(c, _)| c == m)?.1;
+		}
+		Some(node)
+	}
+
+	pub fn find_mut(&mut self, line: &[PlacedPiece]) -> Option<&mut Variations> {
+		let mut node = self;
+		for m in line {
+			node = &mut node.children.iter_mut().find(|(c, _)| c == m)?.1;
+		}
+		Some(node)
+	}
+
+	/// add `line` to the tree, as a new variation where it leaves it
+	pub fn add(&mut self, line: &[PlacedPiece]) {
+		let mut node = self;
+		for &m in line {
+			let i = match node.children.iter().position(|(c, _)| *c == m) {
+				Some(i) => i,
+				None => {
+					node.children.push((m, Variations::default()));
+					node.children.len() - 1
+				}
+			};
+			node = &mut node.children[i].1;
+		}
+	}
+
+	/// remove the last move of `line`, and everything played after it
+	pub fn remove(&mut self, line: &[PlacedPiece]) {
+		let (last, parent) = match line.split_last() {
+			Some(split) => split,
+			None => return,
+		};
+		let mut node = self;
+		for m in parent {
+			node = match node.children.iter_mut().find(|(c, _)| c == m) {
+				Some((_, child)) => child,
+				None => return,
+			};
+		}
+		node.children.retain(|(c, _)| c != last);
+	}
+
+	/// the moves from here to the end, taking the first child each time
+	pub fn main_line(&self) -> Vec<PlacedPiece> {
+		let mut moves = vec![];
+		let mut node = self;
+		while let Some((m, child)) = node.children.first() {
+			moves.push(*m);
+			node = child;
+		}
+		moves
+	}
+
+	/// how many lines end somewhere below this node
+	pub fn count_leaves(&self) -> usize {
+		if self.children.is_empty() {
+			1
+		} else {
+			self.children.iter().map(|(_, n)| n.count_leaves()).sum()
+		}
+	}
+
+	/// apply a rotation or reflection to every move and mark
+	pub fn transform(&self, symmetry: Symmetry, size: u8) -> Self {
+		Self {
+			annotations: self.annotations.transform(symmetry, size),
+			children: self
+				.children
+				.iter()
+				.map(|(m, node)| {
+					(m.transform(symmetry, size), node.transform(symmetry, size))
+				})
+				.collect(),
+		}
+	}
+
+	/// swap the colors of every move
+	pub fn invert_colors(&self) -> Self {
+		Self {
+			annotations: self.annotations.clone(),
+			children: self
+				.children
+				.iter()
+				.map(|(m, node)| (m.invert_color(), node.invert_colors()))
+				.collect(),
+		}
+	}
+}
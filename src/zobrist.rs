@@ -0,0 +1,32 @@
+use crate::{
+	board::MAX_SIZE,
+	piece::PieceColor,
+	pos::Pos,
+};
+
+/// Keys are derived from a fixed seed rather than a random table, so hashes
+/// are the same across runs and machines.
+const SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+/// splitmix64 finalizer
+pub(crate) fn mix(x: u64) -> u64 {
+	let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+	z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+	z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+	z ^ (z >> 31)
+}
+
+/// key for a stone of `color` at `pos`
+pub fn piece_key(pos: Pos, color: PieceColor) -> u64 {
+	let index = (pos.x as u64 * MAX_SIZE as u64 + pos.y as u64) * 2
+		+ match color {
+			PieceColor::Black => 0,
+			PieceColor::White => 1,
+		};
+	mix(SEED ^ index)
+}
+
+/// key for the board size, so empty boards of different sizes differ
+pub fn size_key(size: u8) -> u64 {
+	mix(SEED ^ (0x1_0000 + size as u64))
+}